@@ -31,6 +31,13 @@ impl Player for EmptyBot {
 				if let Some(_u) = self.units.my.structures.get(tag) { /* your code here */ }
 			}
 			Event::RandomRaceDetected(_race) => { /* your code here */ }
+			Event::UpgradeCompleted(_upgrade) => { /* your code here */ }
+			Event::UnitTookDamage(tag, _damage) => {
+				if let Some(_u) = self.units.my.all.get(tag) { /* your code here */ }
+			}
+			Event::ExpansionTaken(_loc, _alliance) => { /* your code here */ }
+			// Other events
+			_ => {}
 		}
 		Ok(())
 	}
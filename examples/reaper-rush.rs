@@ -1,5 +1,5 @@
 use rust_sc2::prelude::*;
-use std::{cmp::Ordering, collections::HashSet};
+use std::collections::HashSet;
 
 mod ex_main;
 
@@ -7,7 +7,7 @@ mod ex_main;
 #[derive(Default)]
 struct ReaperRushAI {
 	reapers_retreat: HashSet<u64>,
-	last_loop_distributed: u32,
+	workers: WorkerManager,
 }
 
 impl Player for ReaperRushAI {
@@ -21,18 +21,11 @@ impl Player for ReaperRushAI {
 			self.subtract_resources(UnitTypeId::SCV, true);
 		}
 
-		// Splitting workers to closest mineral crystals
-		for u in &self.units.my.workers {
-			if let Some(mineral) = self.units.mineral_fields.closest(u) {
-				u.gather(mineral.tag(), false);
-			}
-		}
-
 		Ok(())
 	}
 
 	fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
-		self.distribute_workers();
+		self.workers.step(&self._bot);
 		self.build();
 		self.train();
 		self.execute_micro();
@@ -45,146 +38,6 @@ impl Player for ReaperRushAI {
 }
 
 impl ReaperRushAI {
-	const DISTRIBUTION_DELAY: u32 = 8;
-
-	fn distribute_workers(&mut self) {
-		if self.units.my.workers.is_empty() {
-			return;
-		}
-		let mut idle_workers = self.units.my.workers.idle();
-
-		// Check distribution delay if there aren't any idle workers
-		let game_loop = self.state.observation.game_loop();
-		let last_loop = &mut self.last_loop_distributed;
-		if idle_workers.is_empty() && *last_loop + Self::DISTRIBUTION_DELAY > game_loop {
-			return;
-		}
-		*last_loop = game_loop;
-
-		// Distribute
-		let mineral_fields = &self.units.mineral_fields;
-		if mineral_fields.is_empty() {
-			return;
-		}
-		let bases = self.units.my.townhalls.ready();
-		if bases.is_empty() {
-			return;
-		}
-
-		let mut deficit_minings = Units::new();
-		let mut deficit_geysers = Units::new();
-
-		// Distributing mineral workers
-		for base in &bases {
-			match base.assigned_harvesters().cmp(&base.ideal_harvesters()) {
-				Ordering::Less => (0..(base.ideal_harvesters().unwrap()
-					- base.assigned_harvesters().unwrap()))
-					.for_each(|_| {
-						deficit_minings.push(base.clone());
-					}),
-				Ordering::Greater => {
-					let local_minerals = mineral_fields
-						.iter()
-						.closer(11.0, base)
-						.map(|m| m.tag())
-						.collect::<Vec<u64>>();
-
-					idle_workers.extend(
-						self.units
-							.my
-							.workers
-							.filter(|u| {
-								u.target_tag().map_or(false, |target_tag| {
-									local_minerals.contains(&target_tag)
-										|| (u.is_carrying_minerals() && target_tag == base.tag())
-								})
-							})
-							.iter()
-							.take(
-								(base.assigned_harvesters().unwrap() - base.ideal_harvesters().unwrap())
-									as usize,
-							)
-							.cloned(),
-					);
-				}
-				_ => {}
-			}
-		}
-
-		// Distributing gas workers
-		self.units
-			.my
-			.gas_buildings
-			.iter()
-			.ready()
-			.filter(|g| g.vespene_contents().map_or(false, |vespene| vespene > 0))
-			.for_each(
-				|gas| match gas.assigned_harvesters().cmp(&gas.ideal_harvesters()) {
-					Ordering::Less => (0..(gas.ideal_harvesters().unwrap()
-						- gas.assigned_harvesters().unwrap()))
-						.for_each(|_| {
-							deficit_geysers.push(gas.clone());
-						}),
-					Ordering::Greater => {
-						idle_workers.extend(
-							self.units
-								.my
-								.workers
-								.filter(|u| {
-									u.target_tag().map_or(false, |target_tag| {
-										target_tag == gas.tag()
-											|| (u.is_carrying_vespene()
-												&& target_tag == bases.closest(gas).unwrap().tag())
-									})
-								})
-								.iter()
-								.take(
-									(gas.assigned_harvesters().unwrap() - gas.ideal_harvesters().unwrap())
-										as usize,
-								)
-								.cloned(),
-						);
-					}
-					_ => {}
-				},
-			);
-
-		// Distributing idle workers
-		let minerals_near_base = if idle_workers.len() > deficit_minings.len() + deficit_geysers.len() {
-			let minerals = mineral_fields.filter(|m| bases.iter().any(|base| base.is_closer(11.0, *m)));
-			if minerals.is_empty() {
-				None
-			} else {
-				Some(minerals)
-			}
-		} else {
-			None
-		};
-
-		for u in &idle_workers {
-			if let Some(closest) = deficit_geysers.closest(u) {
-				let tag = closest.tag();
-				deficit_geysers.remove(tag);
-				u.gather(tag, false);
-			} else if let Some(closest) = deficit_minings.closest(u) {
-				u.gather(
-					mineral_fields
-						.closer(11.0, closest)
-						.max(|m| m.mineral_contents().unwrap_or(0))
-						.unwrap()
-						.tag(),
-					false,
-				);
-				let tag = closest.tag();
-				deficit_minings.remove(tag);
-			} else if u.is_idle() {
-				if let Some(mineral) = minerals_near_base.as_ref().and_then(|ms| ms.closest(u)) {
-					u.gather(mineral.tag(), false);
-				}
-			}
-		}
-	}
-
 	fn get_builder(&self, pos: Point2, mineral_tags: &[u64]) -> Option<&Unit> {
 		self.units
 			.my
@@ -375,7 +228,7 @@ impl ReaperRushAI {
 							}
 						}
 					} else {
-						match targets.iter().in_range_of(u, 0.0).min_by_key(|t| t.hits()) {
+						match targets.best_target(u, 0.0) {
 							Some(target) => u.attack(Target::Tag(target.tag()), false),
 							None => u.move_to(Target::Pos(closest.position()), false),
 						}
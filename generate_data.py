@@ -0,0 +1,246 @@
+"""
+Generates versioned tech data for `src/tech_data` from game data dumps.
+
+Usage:
+	python generate_data.py <version> <base build> <stableid.json> <data.json> [game_data.json] [--out <dir>]
+
+- `stableid.json` - ids of the game version, located in `Documents/StarCraft II`.
+- `data.json` - tech tree dump of the game version in format of `sc2-techtree`
+(producers, researchers, requirements and costs).
+- `game_data.json` - optional dump of `Bot::game_data` serialized with `serde` feature,
+used to get tech and unit aliases.
+
+Generated file is added to `src/tech_data/versions` (or given `--out` directory) and all versions
+found there are listed in `mod.rs` of that directory.
+
+Small dumps in `misc/tech_data` are used to test the script, `expected.rs` there is its output for them:
+	python generate_data.py 1.0.0 1 misc/tech_data/stableid.json misc/tech_data/data.json --out <dir>
+"""
+
+from json import load
+from pathlib import Path
+from sys import argv
+
+from generate_ids import parse_data
+
+VERSIONS_DIR = Path.cwd() / "src" / "tech_data" / "versions"
+HEAD = """\
+//! Auto generated with `generate_data.py` script from `stableid.json` and game data dumps.
+//! Tech data of different game versions.
+#![allow(missing_docs)]
+"""
+
+
+def invert(names):
+	ids = {}
+	for name, id in names.items():
+		ids.setdefault(id, name)
+	return ids
+
+
+def requirement(req, units, upgrades):
+	if "building" in req and req["building"] in units:
+		return f"Requirement::Unit(UnitTypeId::{units[req['building']]})"
+	if "addon" in req and req["addon"] in units:
+		return f"Requirement::Addon(UnitTypeId::{units[req['addon']]})"
+	if "upgrade" in req and req["upgrade"] in upgrades:
+		return f"Requirement::Upgrade(UpgradeId::{upgrades[req['upgrade']]})"
+	return None
+
+
+def parse_tech(data, units, upgrades):
+	abilities = {a["id"]: a for a in data["Ability"]}
+
+	producers = {}
+	researchers = {}
+	unit_requirements = {}
+	upgrade_requirements = {}
+	morphs = set()
+	unit_costs = {}
+	upgrade_costs = {}
+
+	for u in data["Unit"]:
+		producer = units.get(u["id"])
+		if producer is None:
+			continue
+		unit_costs[producer] = (
+			u.get("minerals", 0),
+			u.get("gas", 0),
+			u.get("supply", 0),
+			u.get("time", 0),
+		)
+
+		for a in u.get("abilities", []):
+			ability = abilities.get(a["ability"])
+			if ability is None or not isinstance(ability.get("target"), dict):
+				continue
+			reqs = [
+				r
+				for r in (requirement(r, units, upgrades) for r in a.get("requirements", []))
+				if r is not None
+			]
+			((kind, target),) = ability["target"].items()
+
+			if kind == "Research":
+				upgrade = upgrades.get(target["upgrade"])
+				if upgrade is None:
+					continue
+				researchers.setdefault(upgrade, []).append(producer)
+				upgrade_requirements.setdefault(upgrade, set()).update(reqs)
+			elif "produces" in target:
+				unit = units.get(target["produces"])
+				if unit is None:
+					continue
+				if producer not in producers.setdefault(unit, []):
+					producers[unit].append(producer)
+				unit_requirements.setdefault(unit, set()).update(reqs)
+				if kind.startswith("Morph"):
+					morphs.add(unit)
+
+	for u in data["Upgrade"]:
+		if u["id"] in upgrades:
+			cost = u.get("cost", {})
+			upgrade_costs[upgrades[u["id"]]] = (
+				cost.get("minerals", 0),
+				cost.get("gas", 0),
+				0,
+				cost.get("time", 0),
+			)
+
+	return (
+		producers,
+		researchers,
+		unit_requirements,
+		upgrade_requirements,
+		morphs,
+		unit_costs,
+		upgrade_costs,
+	)
+
+
+def parse_aliases(game_data):
+	tech_alias = {}
+	unit_alias = {}
+	for id, u in game_data["units"].items():
+		if u.get("tech_alias"):
+			tech_alias[id] = u["tech_alias"]
+		if u.get("unit_alias"):
+			unit_alias[id] = u["unit_alias"]
+	return tech_alias, unit_alias
+
+
+def gen_list(name, items):
+	return f"\t{name}: &[\n" + "".join(f"\t\t{item},\n" for item in items) + "\t],\n"
+
+
+def gen_slices(name, key, value, data):
+	return gen_list(
+		name,
+		(
+			f"({key}::{k}, &[{', '.join(f'{value}{v}' for v in sorted(vs))}])"
+			for k, vs in sorted(data.items())
+		),
+	)
+
+
+def gen_cost(minerals, vespene, supply, time):
+	return (
+		f"Cost {{ minerals: {minerals}, vespene: {vespene}, "
+		f"supply: {float(supply)!r}, time: {float(time)!r} }}"
+	)
+
+
+def gen_version(version, base_build, tech, aliases):
+	(
+		producers,
+		researchers,
+		unit_requirements,
+		upgrade_requirements,
+		morphs,
+		unit_costs,
+		upgrade_costs,
+	) = tech
+	tech_alias, unit_alias = aliases
+
+	return (
+		f"//! Auto generated with `generate_data.py` script for game version {version}.\n"
+		"#![allow(missing_docs)]\n\n"
+		"use crate::{\n"
+		"\tgame_data::Cost,\n"
+		"\tids::{UnitTypeId, UpgradeId},\n"
+		"\ttech_data::TechData,\n"
+		"\ttech_tree::Requirement,\n"
+		"};\n\n"
+		"pub static DATA: TechData = TechData {\n"
+		f'\tversion: "{version}",\n'
+		f"\tbase_build: {base_build},\n"
+		+ gen_slices("producers", "UnitTypeId", "UnitTypeId::", producers)
+		+ gen_slices("researchers", "UpgradeId", "UnitTypeId::", researchers)
+		+ gen_slices("unit_requirements", "UnitTypeId", "", {k: v for k, v in unit_requirements.items() if v})
+		+ gen_slices(
+			"upgrade_requirements", "UpgradeId", "", {k: v for k, v in upgrade_requirements.items() if v}
+		)
+		+ gen_list("morphs", (f"UnitTypeId::{m}" for m in sorted(morphs)))
+		+ gen_slices("tech_alias", "UnitTypeId", "UnitTypeId::", tech_alias)
+		+ gen_list(
+			"unit_alias",
+			(f"(UnitTypeId::{k}, UnitTypeId::{v})" for k, v in sorted(unit_alias.items())),
+		)
+		+ gen_list(
+			"unit_costs",
+			(f"(UnitTypeId::{k}, {gen_cost(*c)})" for k, c in sorted(unit_costs.items())),
+		)
+		+ gen_list(
+			"upgrade_costs",
+			(f"(UpgradeId::{k}, {gen_cost(*c)})" for k, c in sorted(upgrade_costs.items())),
+		)
+		+ "};\n"
+	)
+
+
+def gen_mod(versions_dir):
+	versions = []
+	for file in versions_dir.glob("v*.rs"):
+		text = file.read_text()
+		base_build = int(text.split("base_build: ", 1)[1].split(",", 1)[0])
+		versions.append((base_build, file.stem))
+	versions.sort()
+
+	return (
+		f"{HEAD}\nuse super::TechData;\n\n"
+		+ "".join(f"mod {name};\n" for _, name in versions)
+		+ ("\n" if versions else "")
+		+ "pub(super) static VERSIONS: &[&TechData] = &["
+		+ "".join(f"&{name}::DATA, " for _, name in versions).rstrip(", ")
+		+ "];\n"
+	)
+
+
+def generate():
+	args = argv[1:]
+	versions_dir = VERSIONS_DIR
+	if "--out" in args:
+		i = args.index("--out")
+		versions_dir = Path(args[i + 1])
+		del args[i : i + 2]
+	if len(args) < 4:
+		exit(__doc__)
+
+	version, base_build, stableid_path, data_path = args[:4]
+	units, _, upgrades, _, _ = parse_data(load(Path(stableid_path).open()))
+	units = invert(units)
+	upgrades = invert(upgrades)
+
+	tech = parse_tech(load(Path(data_path).open()), units, upgrades)
+	aliases = ({}, {})
+	if len(args) > 4:
+		aliases = parse_aliases(load(Path(args[4]).open()))
+
+	versions_dir.mkdir(parents=True, exist_ok=True)
+	name = "v" + version.replace(".", "_")
+	(versions_dir / f"{name}.rs").write_text(gen_version(version, int(base_build), tech, aliases))
+	(versions_dir / "mod.rs").write_text(gen_mod(versions_dir))
+
+
+if __name__ == "__main__":
+	generate()
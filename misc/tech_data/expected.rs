@@ -0,0 +1,66 @@
+//! Auto generated with `generate_data.py` script for game version 1.0.0.
+#![allow(missing_docs)]
+
+use crate::{
+	game_data::Cost,
+	ids::{UnitTypeId, UpgradeId},
+	tech_data::TechData,
+	tech_tree::Requirement,
+};
+
+pub static DATA: TechData = TechData {
+	version: "1.0.0",
+	base_build: 1,
+	producers: &[
+		(UnitTypeId::Armory, &[UnitTypeId::SCV]),
+		(UnitTypeId::Barracks, &[UnitTypeId::SCV]),
+		(UnitTypeId::BarracksTechLab, &[UnitTypeId::Barracks]),
+		(UnitTypeId::EngineeringBay, &[UnitTypeId::SCV]),
+		(UnitTypeId::Marauder, &[UnitTypeId::Barracks]),
+		(UnitTypeId::Marine, &[UnitTypeId::Barracks]),
+		(UnitTypeId::OrbitalCommand, &[UnitTypeId::CommandCenter]),
+		(UnitTypeId::SCV, &[UnitTypeId::CommandCenter]),
+		(UnitTypeId::SupplyDepot, &[UnitTypeId::SCV]),
+	],
+	researchers: &[
+		(UpgradeId::Stimpack, &[UnitTypeId::BarracksTechLab]),
+		(UpgradeId::TerranInfantryWeaponsLevel1, &[UnitTypeId::EngineeringBay]),
+		(UpgradeId::TerranInfantryWeaponsLevel2, &[UnitTypeId::EngineeringBay]),
+	],
+	unit_requirements: &[
+		(UnitTypeId::Barracks, &[Requirement::Unit(UnitTypeId::SupplyDepot)]),
+		(UnitTypeId::EngineeringBay, &[Requirement::Unit(UnitTypeId::CommandCenter)]),
+		(UnitTypeId::Marauder, &[Requirement::Addon(UnitTypeId::BarracksTechLab)]),
+		(UnitTypeId::OrbitalCommand, &[Requirement::Unit(UnitTypeId::Barracks)]),
+	],
+	upgrade_requirements: &[
+		(UpgradeId::TerranInfantryWeaponsLevel2, &[Requirement::Unit(UnitTypeId::Armory), Requirement::Upgrade(UpgradeId::TerranInfantryWeaponsLevel1)]),
+	],
+	morphs: &[
+		UnitTypeId::OrbitalCommand,
+	],
+	tech_alias: &[
+		(UnitTypeId::OrbitalCommand, &[UnitTypeId::CommandCenter]),
+		(UnitTypeId::SupplyDepotLowered, &[UnitTypeId::SupplyDepot]),
+	],
+	unit_alias: &[
+		(UnitTypeId::SupplyDepotLowered, UnitTypeId::SupplyDepot),
+	],
+	unit_costs: &[
+		(UnitTypeId::Armory, Cost { minerals: 150, vespene: 100, supply: 0.0, time: 1040.0 }),
+		(UnitTypeId::Barracks, Cost { minerals: 150, vespene: 0, supply: 0.0, time: 1040.0 }),
+		(UnitTypeId::BarracksTechLab, Cost { minerals: 50, vespene: 25, supply: 0.0, time: 400.0 }),
+		(UnitTypeId::CommandCenter, Cost { minerals: 400, vespene: 0, supply: 0.0, time: 1600.0 }),
+		(UnitTypeId::EngineeringBay, Cost { minerals: 125, vespene: 0, supply: 0.0, time: 560.0 }),
+		(UnitTypeId::Marauder, Cost { minerals: 100, vespene: 25, supply: 2.0, time: 480.0 }),
+		(UnitTypeId::Marine, Cost { minerals: 50, vespene: 0, supply: 1.0, time: 400.0 }),
+		(UnitTypeId::OrbitalCommand, Cost { minerals: 550, vespene: 0, supply: 0.0, time: 560.0 }),
+		(UnitTypeId::SCV, Cost { minerals: 50, vespene: 0, supply: 1.0, time: 272.0 }),
+		(UnitTypeId::SupplyDepot, Cost { minerals: 100, vespene: 0, supply: 0.0, time: 480.0 }),
+	],
+	upgrade_costs: &[
+		(UpgradeId::Stimpack, Cost { minerals: 100, vespene: 100, supply: 0.0, time: 1600.0 }),
+		(UpgradeId::TerranInfantryWeaponsLevel1, Cost { minerals: 100, vespene: 100, supply: 0.0, time: 2560.0 }),
+		(UpgradeId::TerranInfantryWeaponsLevel2, Cost { minerals: 175, vespene: 175, supply: 0.0, time: 3040.0 }),
+	],
+};
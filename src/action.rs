@@ -6,7 +6,7 @@ use crate::{
 	FromProto, IntoProto,
 };
 use num_traits::{FromPrimitive, ToPrimitive};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use sc2_proto::{
 	error::ActionResult as ProtoActionResult,
 	raw::{ActionRawUnitCommand_oneof_target as ProtoTarget, ActionRaw_oneof_action as ProtoRawAction},
@@ -19,6 +19,38 @@ use sc2_proto::{
 pub(crate) struct Commander {
 	pub commands: FxHashMap<(AbilityId, Target, bool), Vec<u64>>,
 	pub autocast: FxHashMap<AbilityId, Vec<u64>>,
+	pub forced: FxHashSet<(u64, AbilityId, Target, bool)>,
+}
+
+/// Filter, which drops commands identical to current orders of units,
+/// so re-issuing the same command every step doesn't reset orders and spam the API.
+///
+/// Disabled by default, can be enabled through [`command_filter`](crate::bot::Bot::command_filter) field:
+/// ```
+/// self.command_filter = Some(CommandFilter::new(0.5));
+/// ```
+/// Only commands, which aren't queued, are filtered. Commands without target (train, research, ...)
+/// and commands given to structures aren't filtered either, because giving them again adds one more order.
+/// Commands given with [`force_command`](crate::unit::Unit::force_command) are never dropped.
+#[derive(Debug, Default, Clone)]
+pub struct CommandFilter {
+	/// Maximum distance between target points of identical commands. [Default: `0`]
+	pub tolerance: f32,
+	/// Number of commands dropped on the last step.
+	pub dropped: usize,
+	/// Number of commands dropped during the whole game.
+	pub dropped_total: usize,
+	/// Number of commands checked during the whole game.
+	pub checked_total: usize,
+}
+impl CommandFilter {
+	/// Constructs new filter with given tolerance for target points.
+	pub fn new(tolerance: f32) -> Self {
+		Self {
+			tolerance,
+			..Default::default()
+		}
+	}
 }
 
 /// Target of ability used by unit.
@@ -151,6 +183,39 @@ impl FromProto<&ProtoActionError> for ActionError {
 	}
 }
 
+/// Command issued by bot on previous step with its results.
+/// Stored in [`state.command_results`](crate::game_state::GameState::command_results).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandResult {
+	/// Ability used in command.
+	pub ability: AbilityId,
+	/// Target of the command.
+	pub target: Target,
+	/// Tags of units the command was given to.
+	pub units: Vec<u64>,
+	/// Command was queued.
+	pub queue: bool,
+	/// Result of the command returned by the game when it was sent.
+	pub result: ActionResult,
+	/// Units failed to execute the command with results from
+	/// [`action_errors`](crate::game_state::GameState::action_errors).
+	pub errors: Vec<(u64, ActionResult)>,
+}
+impl CommandResult {
+	/// Checks if command was executed by all units without errors.
+	pub fn is_success(&self) -> bool {
+		self.result == ActionResult::Success && self.errors.is_empty()
+	}
+	/// Returns units failed to execute the command with results of the failure.
+	pub fn failures(&self) -> Vec<(u64, ActionResult)> {
+		if self.result == ActionResult::Success || !self.errors.is_empty() {
+			self.errors.clone()
+		} else {
+			self.units.iter().map(|u| (*u, self.result)).collect()
+		}
+	}
+}
+
 /// Result of executed action.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -604,3 +669,44 @@ impl FromProto<ProtoActionResult> for ActionResult {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn command(result: ActionResult, errors: Vec<(u64, ActionResult)>) -> CommandResult {
+		CommandResult {
+			ability: AbilityId::BarracksTrainMarine,
+			target: Target::None,
+			units: vec![1, 2],
+			queue: false,
+			result,
+			errors,
+		}
+	}
+
+	#[test]
+	fn successful_command() {
+		let command = command(ActionResult::Success, vec![]);
+		assert!(command.is_success());
+		assert!(command.failures().is_empty());
+	}
+
+	#[test]
+	fn failures_of_some_units() {
+		let errors = vec![(2, ActionResult::NotEnoughMinerals)];
+		let command = command(ActionResult::Success, errors.clone());
+		assert!(!command.is_success());
+		assert_eq!(command.failures(), errors);
+	}
+
+	#[test]
+	fn failed_command_fails_for_all_units() {
+		let command = command(ActionResult::NotEnoughFood, vec![]);
+		assert!(!command.is_success());
+		assert_eq!(
+			command.failures(),
+			vec![(1, ActionResult::NotEnoughFood), (2, ActionResult::NotEnoughFood)]
+		);
+	}
+}
@@ -1,34 +1,41 @@
 //! [`Bot`] struct and it's helpers.
 
 use crate::{
-	action::{Action, ActionResult, Commander, Target},
+	action::{Action, ActionResult, CommandFilter, Commander, Target},
 	api::API,
 	client::SC2Result,
 	consts::{RaceValues, FRAMES_PER_SECOND, INHIBITOR_IDS, RACE_VALUES, TECH_ALIAS, UNIT_ALIAS},
+	dangers::Dangers,
 	debug::{DebugCommand, Debugger},
 	distance::*,
 	game_data::{Cost, GameData},
 	game_info::GameInfo,
-	game_state::Effect,
 	game_state::{Alliance, GameState},
 	geometry::{Point2, Point3},
-	ids::{AbilityId, BuffId, EffectId, UnitTypeId, UpgradeId},
+	ids::{AbilityId, BuffId, UnitTypeId, UpgradeId},
+	layout::{Layout, SlotKind},
+	map_analysis::MapAnalysis,
+	memory::EnemyMemory,
 	player::Race,
+	placement::{choose_placement, PlacementGrid},
 	ramp::{Ramp, Ramps},
-	unit::{DataForUnit, SharedUnitData, Unit},
+	scouting::Scouting,
+	tech_data::{self, TechData},
+	unit::{DataForUnit, SharedUnitData, Unit, UnitBase},
 	units::{AllUnits, Units},
 	utils::{dbscan, range_query},
+	vision::{detection_circles, is_detected, Vision},
+	wall::{self, Choke, Wall, WallOptions},
 	FromProto, IntoProto,
 };
 use indexmap::IndexSet;
 use num_traits::ToPrimitive;
-use rand::prelude::*;
 use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 use sc2_proto::{
 	query::{RequestQueryBuildingPlacement, RequestQueryPathing},
 	sc2api::Request,
 };
-use std::{fmt, hash::BuildHasherDefault, process::Child};
+use std::{fmt, hash::BuildHasherDefault, mem, process::Child};
 
 type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
 
@@ -201,6 +208,8 @@ pub struct PlacementOptions {
 	pub random: bool,
 	/// Filter positions where addon can fit. [Default: `false`]
 	pub addon: bool,
+	/// Confirm positions found locally with [`query_placement`](Bot::query_placement). [Default: `true`]
+	pub confirm: bool,
 }
 impl Default for PlacementOptions {
 	fn default() -> Self {
@@ -209,6 +218,7 @@ impl Default for PlacementOptions {
 			step: 2,
 			random: false,
 			addon: false,
+			confirm: true,
 		}
 	}
 }
@@ -393,6 +403,8 @@ pub struct Bot {
 	pub(crate) game_left: bool,
 	#[doc(hidden)]
 	pub disable_fog: bool,
+	/// Drops commands identical to current orders of units if set. [Default: `None`]
+	pub command_filter: Option<CommandFilter>,
 	/// Actual race of your bot.
 	pub race: Race,
 	/// Requested race of your opponent.
@@ -403,6 +415,10 @@ pub struct Bot {
 	pub enemy_player_id: u32,
 	/// Opponent id on ladder, filled in `--OpponentId`.
 	pub opponent_id: String,
+	/// Version of the game (e.g. `"5.0.11.90136"`).
+	pub game_version: String,
+	/// Base build of the game, used to select [`tech_data`](crate::tech_data) for current version.
+	pub base_build: u32,
 	actions: Vec<Action>,
 	commander: Rw<Commander>,
 	/// Debug API
@@ -428,6 +444,20 @@ pub struct Bot {
 	/// Ready enemy units counted by unit type.
 	pub enemies_current: FxHashMap<UnitTypeId, usize>,
 	pub(crate) saved_hallucinations: FxHashSet<u64>,
+	/// Memory of all enemy units ever seen, updated each step.
+	pub enemy_memory: EnemyMemory,
+	/// Recognition of opponent's strategies, updated each step.
+	pub scouting: Scouting,
+	/// Regions, chokes and connectivity graph of the map, computed on game start.
+	pub map_analysis: MapAnalysis,
+	/// Slots for buildings planned at expansions and main ramp on game start.
+	pub layout: Layout,
+	/// Coverage of the map by vision, detection and radar of both players, updated every step.
+	pub vision: Vision,
+	/// Active and predicted danger areas of harmful effects, updated every step.
+	pub dangers: Dangers,
+	/// Grid used to check placement of buildings locally without API queries, updated every step.
+	pub placement: PlacementGrid,
 	/// In-game time in seconds.
 	pub time: f32,
 	/// Amount of minerals bot has.
@@ -459,6 +489,8 @@ pub struct Bot {
 	max_cooldowns: Rw<FxHashMap<UnitTypeId, f32>>,
 	pub(crate) last_units_hits: Rw<FxHashMap<u64, u32>>,
 	pub(crate) last_units_seen: Rw<FxHashMap<u64, u32>>,
+	pub(crate) last_units: Rw<FxHashMap<u64, (Rs<UnitBase>, u32)>>,
+	units_loop: u32,
 	/// Obstacles on map which block vision of ground units, but still pathable.
 	pub vision_blockers: Vec<Point2>,
 	/// Ramps on map.
@@ -466,6 +498,8 @@ pub struct Bot {
 	enemy_upgrades: Rw<FxHashSet<UpgradeId>>,
 	pub(crate) owned_tags: FxHashSet<u64>,
 	pub(crate) under_construction: FxHashSet<u64>,
+	pub(crate) seen_enemies: FxHashSet<u64>,
+	pub(crate) visible_enemies: FxHashSet<u64>,
 	pub(crate) available_frames: Rw<FxHashMap<u64, u32>>,
 }
 
@@ -524,18 +558,46 @@ impl Bot {
 	pub fn enemy_counter(&self) -> CountOptions<'_> {
 		CountOptions::new(self, true)
 	}
+	/// Returns generated [`TechData`] for current game version
+	/// or `None` if there's no data for this version.
+	pub fn tech_data(&self) -> Option<&'static TechData> {
+		tech_data::for_build(self.base_build)
+	}
 	pub(crate) fn get_actions(&mut self) -> &[Action] {
 		let actions = &mut self.actions;
 
 		let mut commander = self.commander.write_lock();
+		let forced = mem::take(&mut commander.forced);
 
+		if let Some(filter) = &mut self.command_filter {
+			filter.dropped = 0;
+		}
 		if !commander.commands.is_empty() {
+			let my_units = &self.units.my.all;
+			let filter = &mut self.command_filter;
 			actions.extend(
 				commander
 					.commands
 					.drain()
-					.map(|((ability, target, queue), units)| {
-						Action::UnitCommand(ability, target, units, queue)
+					.filter_map(|((ability, target, queue), mut units)| {
+						let filtered = !queue && target != Target::None;
+						if let (Some(filter), true) = (filter.as_mut(), filtered) {
+							let len = units.len();
+							units.retain(|tag| {
+								forced.contains(&(*tag, ability, target, queue))
+									|| !my_units.get(*tag).is_some_and(|u| {
+										!u.is_structure() && u.has_order(ability, target, filter.tolerance)
+									})
+							});
+							filter.checked_total += len;
+							filter.dropped += len - units.len();
+							filter.dropped_total += len - units.len();
+						}
+						if units.is_empty() {
+							None
+						} else {
+							Some(Action::UnitCommand(ability, target, units, queue))
+						}
 					}),
 			);
 		}
@@ -871,6 +933,7 @@ impl Bot {
 			max_cooldowns: Rs::clone(&self.max_cooldowns),
 			last_units_hits: Rs::clone(&self.last_units_hits),
 			last_units_seen: Rs::clone(&self.last_units_seen),
+			last_units: Rs::clone(&self.last_units),
 			abilities_units: Rs::clone(&self.abilities_units),
 			enemy_upgrades: Rs::clone(&self.enemy_upgrades),
 			upgrades: Rs::clone(&self.state.observation.raw.upgrades),
@@ -1142,6 +1205,16 @@ impl Bot {
 		}
 
 		self.ramps.all = ramps;
+
+		self.map_analysis = MapAnalysis::new(self);
+
+		// Preparing placement grid, since mineral lines depend on expansions found above
+		let mut placement = mem::take(&mut self.placement);
+		placement.prepare(self);
+		placement.update(self);
+		self.placement = placement;
+		self.layout = Layout::new(self);
+		self.placement.free_defense_slots(&self.layout);
 	}
 	pub(crate) fn prepare_step(&mut self) {
 		let observation = &self.state.observation;
@@ -1215,6 +1288,15 @@ impl Bot {
 			})
 			.collect();
 
+		let last_loop = self.units_loop;
+		*self.last_units.write_lock() = self
+			.units
+			.all
+			.iter()
+			.map(|u| (u.tag(), (Rs::clone(&u.base), last_loop)))
+			.collect();
+		self.units_loop = self.state.observation.game_loop();
+
 		self.units.clear();
 
 		let mut techlab_tags = self.techlab_tags.write_lock();
@@ -1389,6 +1471,7 @@ impl Bot {
 			}
 		}
 		units.all = all_units;
+		units.update_index();
 
 		let enemies = &mut self.units.enemy;
 		for &u in &self.saved_hallucinations {
@@ -1404,26 +1487,6 @@ impl Bot {
 			exp.base = base;
 		}
 
-		fn is_invisible(u: &Unit, detectors: &Units, scans: &[&Effect], gap: f32) -> bool {
-			let additional = u.radius() + gap;
-
-			for d in detectors {
-				if u.is_closer(additional + d.radius() + d.detect_range(), d) {
-					return false;
-				}
-			}
-
-			for scan in scans {
-				for p in &scan.positions {
-					if u.is_closer(additional + scan.radius, *p) {
-						return false;
-					}
-				}
-			}
-
-			true
-		}
-
 		#[cfg(feature = "enemies_cache")]
 		{
 			let cache = &mut self.units.cached;
@@ -1448,21 +1511,17 @@ impl Bot {
 			let mut hidden = Vec::<u64>::new();
 			let enemy_is_zerg = self.enemy_race.is_zerg();
 
-			let detectors = self.units.my.all.filter(|u| u.is_detector());
-			let scans = self
-				.state
-				.observation
-				.raw
-				.effects
-				.iter()
-				.filter(|e| e.id == EffectId::ScannerSweep && e.alliance.is_mine())
-				.collect::<Vec<_>>();
+			let effects = &self.state.observation.raw.effects;
+			let detectors = detection_circles(
+				&self.units.my.all,
+				effects.iter().filter(|e| e.alliance.is_mine()),
+			);
 
 			let current = &self.units.enemy.all;
 			for u in &self.units.cached.all {
 				if current.contains_tag(u.tag()) {
 					// Mark as hidden undetected burrowed units - it's not possible to attack them.
-					if u.is_burrowed() && u.is_revealed() && is_invisible(u, &detectors, &scans, 0.0) {
+					if u.is_burrowed() && u.is_revealed() && !is_detected(u, &detectors, 0.0) {
 						cloaked.push(u.tag());
 					}
 				} else if u.is_flying() || !u.is_structure() {
@@ -1496,7 +1555,7 @@ impl Bot {
 									) && self.units.enemy.units.iter().any(is_transport_close))
 									|| (u.type_id() == UnitTypeId::Drone
 										&& self.units.enemy.structures.iter().any(is_drone_close)))
-								&& is_invisible(u, &detectors, &scans, 0f32)
+								&& !is_detected(u, &detectors, 0.0)
 								&& self.is_surround_visible(u.position(), 2)
 							{
 								burrowed.push(u.tag());
@@ -1505,7 +1564,7 @@ impl Bot {
 								to_remove.push(u.tag());
 							}
 						// Was out of vision previously or burrowed but detected -> probably moved somewhere else
-						} else if !(u.is_burrowed() && is_invisible(u, &detectors, &scans, 0.0)) {
+						} else if !u.is_burrowed() || is_detected(u, &detectors, 0.0) {
 							to_remove.push(u.tag());
 						}
 					// Unit is out of vision -> marking as hidden
@@ -1593,18 +1652,13 @@ impl Bot {
 		self.enemies_ordered = enemies_ordered;
 		self.enemies_current = enemies_current;
 
-		let enemy_scans = self
-			.state
-			.observation
-			.raw
-			.effects
-			.iter()
-			.filter(|e| e.id == EffectId::ScannerSweep && e.alliance.is_enemy())
-			.collect::<Vec<_>>();
+		let effects = &self.state.observation.raw.effects;
+		let enemy_detectors =
+			detection_circles(&enemy_detectors, effects.iter().filter(|e| e.alliance.is_enemy()));
 
-		if !(enemy_detectors.is_empty() && enemy_scans.is_empty()) {
+		if !enemy_detectors.is_empty() {
 			for u in &self.units.my.all {
-				if !(u.is_revealed() || is_invisible(u, &enemy_detectors, &enemy_scans, 1.0))
+				if (!u.is_revealed() && is_detected(u, &enemy_detectors, 1.0))
 					|| u.has_buff(BuffId::OracleRevelation)
 				{
 					u.base.is_revealed.set_locked(true);
@@ -1613,6 +1667,21 @@ impl Bot {
 		}
 	}
 
+	/// Finds placements of given buildings to wall off given choke.
+	///
+	/// Buildings are placed one by one in given order, each touching terrain or previous buildings.
+	/// Returned wall is validated against pathing grid and current structures,
+	/// but it's not checked for creep or psionic matrix.
+	///
+	/// Returns `None` if wall can't be found.
+	pub fn find_wall(&self, choke: &Choke, buildings: &[UnitTypeId], options: WallOptions) -> Option<Wall> {
+		wall::find_wall(self, choke, buildings, options)
+	}
+	/// Returns first free slot of given kind from [`layout`](Self::layout)
+	/// at expansion with given location.
+	pub fn next_slot(&self, kind: SlotKind, loc: Point2) -> Option<Point2> {
+		self.layout.next_slot(&self.placement, kind, loc)
+	}
 	/// Simple wrapper around [`query_placement`](Self::query_placement).
 	/// Checks if it's possible to build given building on given position.
 	pub fn can_place(&self, building: UnitTypeId, pos: Point2) -> bool {
@@ -1638,95 +1707,71 @@ impl Bot {
 		.map(|r| r == ActionResult::Success)
 		.collect()
 	}
+	/// Checks if it's possible to build given building on given position.
+	///
+	/// Local alternative to [`can_place`](Self::can_place), checked with [`PlacementGrid`]
+	/// without API queries.
+	pub fn can_place_local(&self, building: UnitTypeId, pos: Point2) -> bool {
+		self.placement.can_place(building, pos)
+	}
+	/// Multi-version of [`can_place_local`](Self::can_place_local).
+	pub fn can_place_some_local(&self, places: Vec<(UnitTypeId, Point2)>) -> Vec<bool> {
+		let grid = &self.placement;
+		places
+			.into_iter()
+			.map(|(building, pos)| grid.can_place(building, pos))
+			.collect()
+	}
 
-	/// Nice wrapper around [`query_placement`](Self::query_placement).
 	/// Returns correct position where it is possible to build given `building`,
 	/// or `None` if position is not found or `building` can't be built by a worker.
+	///
+	/// Positions are found locally with [`PlacementGrid`] and confirmed
+	/// with [`query_placement`](Self::query_placement) if [`confirm`](PlacementOptions::confirm) is `true`.
 	pub fn find_placement(
 		&self,
 		building: UnitTypeId,
 		near: Point2,
 		options: PlacementOptions,
 	) -> Option<Point2> {
-		if let Some(data) = self.game_data.units.get(&building) {
-			if let Some(ability) = data.ability {
-				let addon = options.addon;
-				if self
-					.query_placement(
-						if addon {
-							vec![
-								(ability, near, None),
-								(AbilityId::TerranBuildSupplyDepot, near.offset(2.5, -0.5), None),
-							]
-						} else {
-							vec![(ability, near, None)]
-						},
-						false,
-					)
-					.unwrap()
-					.iter()
-					.all(|r| matches!(r, ActionResult::Success))
-				{
-					return Some(near);
-				}
+		let grid = &self.placement;
+		if !options.confirm {
+			return grid.find_placement(building, near, options);
+		}
 
-				let placement_step = options.step;
-				for distance in (placement_step..options.max_distance).step_by(placement_step as usize) {
-					let positions = (-distance..=distance)
-						.step_by(placement_step as usize)
-						.flat_map(|offset| {
-							vec![
-								near.offset(offset as f32, (-distance) as f32),
-								near.offset(offset as f32, distance as f32),
-								near.offset((-distance) as f32, offset as f32),
-								near.offset(distance as f32, offset as f32),
-							]
-						})
-						.collect::<Vec<Point2>>();
-					let results = self
-						.query_placement(positions.iter().map(|pos| (ability, *pos, None)).collect(), false)
-						.unwrap();
+		let ability = self.game_data.units.get(&building)?.ability?;
+		for mut positions in grid.placements(building, near, options) {
+			if positions.is_empty() {
+				continue;
+			}
 
-					let mut valid_positions = positions
+			let mut places = positions
+				.iter()
+				.map(|pos| (ability, *pos, None))
+				.collect::<Vec<_>>();
+			if options.addon {
+				places.extend(
+					positions
 						.iter()
-						.zip(results.iter())
-						.filter_map(|(pos, res)| {
-							if matches!(res, ActionResult::Success) {
-								Some(*pos)
-							} else {
-								None
-							}
-						})
-						.collect::<Vec<Point2>>();
-
-					if addon {
-						let results = self
-							.query_placement(
-								valid_positions
-									.iter()
-									.map(|pos| {
-										(AbilityId::TerranBuildSupplyDepot, pos.offset(2.5, -0.5), None)
-									})
-									.collect(),
-								false,
-							)
-							.unwrap();
-						valid_positions = valid_positions
-							.into_iter()
-							.zip(results.into_iter())
-							.filter(|(_, res)| *res == ActionResult::Success)
-							.map(|(pos, _)| pos)
-							.collect::<Vec<Point2>>();
-					}
+						.map(|pos| (AbilityId::TerranBuildSupplyDepot, pos.offset(2.5, -0.5), None)),
+				);
+			}
+			let results = match self.query_placement(places, false) {
+				Ok(results) => results,
+				Err(_) => return None,
+			};
 
-					if !valid_positions.is_empty() {
-						return if options.random {
-							valid_positions.choose(&mut thread_rng()).copied()
-						} else {
-							valid_positions.iter().closest(near).copied()
-						};
-					}
-				}
+			let n = positions.len();
+			let mut i = 0;
+			positions.retain(|_| {
+				let success = results[i] == ActionResult::Success
+					&& (!options.addon || results[n + i] == ActionResult::Success);
+				i += 1;
+				success
+			});
+
+			if !positions.is_empty() {
+				return choose_placement(&positions, near, options.random);
 			}
 		}
 		None
@@ -1896,6 +1941,7 @@ impl Default for Bot {
 			game_step: Rs::new(LockU32::new(1)),
 			game_left: false,
 			disable_fog: false,
+			command_filter: None,
 			race: Race::Random,
 			enemy_race: Race::Random,
 			process: None,
@@ -1903,6 +1949,8 @@ impl Default for Bot {
 			player_id: Default::default(),
 			enemy_player_id: Default::default(),
 			opponent_id: Default::default(),
+			game_version: Default::default(),
+			base_build: Default::default(),
 			actions: Default::default(),
 			commander: Default::default(),
 			debug: Default::default(),
@@ -1933,14 +1981,25 @@ impl Default for Bot {
 			max_cooldowns: Default::default(),
 			last_units_hits: Default::default(),
 			last_units_seen: Default::default(),
+			last_units: Default::default(),
+			units_loop: Default::default(),
 			vision_blockers: Default::default(),
 			ramps: Default::default(),
 			enemy_upgrades: Default::default(),
 			owned_tags: Default::default(),
 			under_construction: Default::default(),
+			seen_enemies: Default::default(),
+			visible_enemies: Default::default(),
 			enemies_ordered: Default::default(),
 			enemies_current: Default::default(),
 			saved_hallucinations: Default::default(),
+			enemy_memory: Default::default(),
+			scouting: Default::default(),
+			map_analysis: Default::default(),
+			layout: Default::default(),
+			vision: Default::default(),
+			dangers: Default::default(),
+			placement: Default::default(),
 			available_frames: Default::default(),
 		}
 	}
@@ -1951,3 +2010,100 @@ impl Drop for Bot {
 		self.close_client();
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::unit::tests::{from_proto, proto};
+	use sc2_proto::raw::UnitOrder as ProtoUnitOrder;
+
+	fn order(ability: AbilityId, pos: Option<(f32, f32)>) -> ProtoUnitOrder {
+		let mut order = ProtoUnitOrder::new();
+		order.set_ability_id(ability as u32);
+		if let Some((x, y)) = pos {
+			order.set_target_world_space_pos(Point3::new(x, y, 0.0).into_proto());
+		}
+		order
+	}
+	/// Bot with marine moving to (5, 5) and barracks training marine.
+	fn bot_with_orders() -> Bot {
+		let mut bot = Bot::default();
+		let mut marine = proto(1, UnitTypeId::Marine, (1.0, 1.0));
+		marine
+			.mut_orders()
+			.push(order(AbilityId::MoveMove, Some((5.0, 5.0))));
+		let mut barracks = proto(2, UnitTypeId::Barracks, (10.5, 10.5));
+		barracks
+			.mut_orders()
+			.push(order(AbilityId::BarracksTrainMarine, None));
+		bot.units.my.all = [from_proto(&marine), from_proto(&barracks)].into_iter().collect();
+		bot.command_filter = Some(CommandFilter::new(0.5));
+		bot
+	}
+	fn command(bot: &Bot, tag: u64, ability: AbilityId, target: Target) {
+		bot.commander
+			.write_lock()
+			.commands
+			.entry((ability, target, false))
+			.or_default()
+			.push(tag);
+	}
+	fn commanded(bot: &mut Bot) -> Vec<(AbilityId, Target)> {
+		bot.get_actions()
+			.iter()
+			.filter_map(|a| match a {
+				Action::UnitCommand(ability, target, _, _) => Some((*ability, *target)),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn identical_commands_dropped() {
+		let mut bot = bot_with_orders();
+		let close = Target::Pos(Point2::new(5.3, 5.0));
+		let far = Target::Pos(Point2::new(8.0, 5.0));
+		command(&bot, 1, AbilityId::MoveMove, close);
+
+		assert!(commanded(&mut bot).is_empty());
+		command(&bot, 1, AbilityId::MoveMove, far);
+		assert_eq!(commanded(&mut bot), vec![(AbilityId::MoveMove, far)]);
+
+		let filter = bot.command_filter.as_ref().unwrap();
+		assert_eq!(filter.dropped, 0);
+		assert_eq!(filter.dropped_total, 1);
+		assert_eq!(filter.checked_total, 2);
+	}
+
+	#[test]
+	fn trains_not_dropped() {
+		let mut bot = bot_with_orders();
+		command(&bot, 2, AbilityId::BarracksTrainMarine, Target::None);
+
+		assert_eq!(
+			commanded(&mut bot),
+			vec![(AbilityId::BarracksTrainMarine, Target::None)]
+		);
+		assert_eq!(bot.command_filter.as_ref().unwrap().checked_total, 0);
+	}
+
+	#[test]
+	fn forced_commands_not_dropped() {
+		let mut bot = bot_with_orders();
+		let target = Target::Pos(Point2::new(5.0, 5.0));
+		command(&bot, 1, AbilityId::MoveMove, target);
+		// Forcing other command of the unit doesn't affect this one
+		bot.commander
+			.write_lock()
+			.forced
+			.insert((1, AbilityId::AttackAttack, target, false));
+		assert!(commanded(&mut bot).is_empty());
+
+		command(&bot, 1, AbilityId::MoveMove, target);
+		bot.commander
+			.write_lock()
+			.forced
+			.insert((1, AbilityId::MoveMove, target, false));
+		assert_eq!(commanded(&mut bot), vec![(AbilityId::MoveMove, target)]);
+	}
+}
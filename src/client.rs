@@ -4,12 +4,13 @@
 //! and simple runner functions for playing once.
 
 use crate::{
+	action::{Action, ActionResult, CommandResult},
 	api::API,
 	bot::{Bot, LockOwned, Rs},
 	game_state::update_state,
 	paths::*,
 	player::Computer,
-	IntoProto, IntoSC2, Player, PlayerSettings,
+	FromProto, IntoProto, IntoSC2, Player, PlayerSettings,
 };
 use sc2_proto::sc2api::{PlayerSetup, PlayerType, PortSet, Request, RequestCreateGame, Status};
 use std::{
@@ -501,6 +502,14 @@ fn get_unused_ports(n: usize) -> Vec<i32> {
 fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
 	let api = bot.api();
 
+	debug!("Requesting Ping");
+	let mut req = Request::new();
+	req.mut_ping();
+	let res = api.send(req)?;
+	let ping = res.get_ping();
+	let game_version = ping.get_game_version().to_string();
+	let base_build = ping.get_base_build();
+
 	debug!("Requesting GameInfo");
 	let mut req = Request::new();
 	req.mut_game_info();
@@ -518,6 +527,8 @@ fn set_static_data(bot: &mut Bot) -> SC2Result<()> {
 	let mut res = api.send(req)?;
 	let game_data = Rs::new(res.take_data().into_sc2());
 
+	bot.game_version = game_version;
+	bot.base_build = base_build;
 	bot.game_info = game_info;
 	bot.game_data = game_data;
 
@@ -621,16 +632,7 @@ where
 	}
 	bot.on_start()?;
 
-	let bot_actions = bot.get_actions();
-	if !bot_actions.is_empty() {
-		let mut req = Request::new();
-		let actions = req.mut_action().mut_actions();
-		for a in bot_actions {
-			actions.push(a.into_proto());
-		}
-		bot.clear_actions();
-		bot.api().send_request(req)?;
-	}
+	send_actions(bot)?;
 	if !realtime {
 		let mut req = Request::new();
 		req.mut_step().set_count(bot.game_step.get_locked());
@@ -670,24 +672,7 @@ where
 		return Ok(false);
 	}
 
-	let bot_actions = bot.get_actions();
-	if !bot_actions.is_empty() {
-		// println!("{:?}: {:?}", iteration, bot_actions);
-		let mut req = Request::new();
-		let actions = req.mut_action().mut_actions();
-		for a in bot_actions {
-			actions.push(a.into_proto());
-		}
-		bot.clear_actions();
-		bot.api().send_request(req)?;
-		/*
-		let res = api.send(req);
-		let results = res.get_action().get_result();
-		if !results.is_empty() {
-			println!("action_results: {:?}", results);
-		}
-		*/
-	}
+	send_actions(bot)?;
 
 	let bot_debug_commands = bot.get_debug_commands();
 	if !bot_debug_commands.is_empty() {
@@ -707,6 +692,48 @@ where
 	Ok(true)
 }
 
+fn send_actions(bot: &mut Bot) -> SC2Result<()> {
+	let bot_actions = bot.get_actions();
+	if bot_actions.is_empty() {
+		bot.state.command_results.clear();
+		return Ok(());
+	}
+
+	let mut req = Request::new();
+	let actions = req.mut_action().mut_actions();
+	let mut commands = Vec::with_capacity(bot_actions.len());
+	for a in bot_actions {
+		actions.push(a.into_proto());
+		commands.push(match a {
+			Action::UnitCommand(ability, target, units, queue) => Some(CommandResult {
+				ability: *ability,
+				target: *target,
+				units: units.clone(),
+				queue: *queue,
+				result: ActionResult::Success,
+				errors: vec![],
+			}),
+			_ => None,
+		});
+	}
+	bot.clear_actions();
+
+	let res = bot.api().send(req)?;
+	let results = res.get_action().get_result();
+	bot.state.command_results = commands
+		.into_iter()
+		.enumerate()
+		.filter_map(|(i, command)| {
+			let mut command = command?;
+			if let Some(result) = results.get(i) {
+				command.result = ActionResult::from_proto(*result);
+			}
+			Some(command)
+		})
+		.collect();
+	Ok(())
+}
+
 fn save_replay(api: &API, path: &str) -> SC2Result<()> {
 	let mut req = Request::new();
 	req.mut_save_replay();
@@ -0,0 +1,435 @@
+//! Creep spread planner for Zerg.
+//!
+//! [`CreepSpreader`] is an opt-in component, which should be stored in your bot,
+//! updated every step and asked to spread creep when needed:
+//! ```
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot {
+//!     creep: CreepSpreader,
+//! }
+//!
+//! impl Player for MyBot {
+//!     fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+//!         self.creep.step(&self._bot);
+//!         self.creep.spread(&self._bot);
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use crate::{
+	action::Target,
+	bot::Bot,
+	consts::FRAMES_PER_SECOND,
+	distance::*,
+	geometry::Point2,
+	ids::{AbilityId, UnitTypeId},
+	pixel_map::fill_footprint,
+	placement::PlacementGrid,
+	unit::Unit,
+	units::Units,
+};
+use ndarray::Array2;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Radius of creep around creep tumor.
+const CREEP_RADIUS: f32 = 10.0;
+/// Tumor is considered failed to spread if it still can spread after this number of game loops.
+const RETRY_DELAY: u32 = FRAMES_PER_SECOND as u32 * 2;
+
+/// Plans creep tumor spots and spreads creep with tumors and queens.
+///
+/// Spots are chosen to cover as much new ground as possible, while moving creep towards
+/// [`targets`](Self::targets). Tumors aren't placed in slots of [`layout`](Bot::layout)
+/// and at expansions, so they don't block own buildings.
+pub struct CreepSpreader {
+	/// Positions creep should be spread towards. Enemy start location is used if empty. [Default: `[]`]
+	pub targets: Vec<Point2>,
+	/// Maximum distance of new tumor from the tumor spawning it. [Default: `10`]
+	pub tumor_range: f32,
+	/// Maximum distance of new tumor from the queen placing it. [Default: `8`]
+	pub queen_range: f32,
+	/// How many tiles of new creep one tile of progress towards target is worth. [Default: `10`]
+	pub target_weight: f32,
+	/// Don't place tumors in building slots and at expansions. [Default: `true`]
+	pub avoid_slots: bool,
+	edge: Vec<Point2>,
+	reserved: Array2<bool>,
+	spent: FxHashSet<u64>,
+	ordered: FxHashMap<u64, u32>,
+	planned: Vec<Point2>,
+}
+impl Default for CreepSpreader {
+	fn default() -> Self {
+		Self {
+			targets: vec![],
+			tumor_range: 10.0,
+			queen_range: 8.0,
+			target_weight: 10.0,
+			avoid_slots: true,
+			edge: vec![],
+			reserved: Default::default(),
+			spent: Default::default(),
+			ordered: Default::default(),
+			planned: vec![],
+		}
+	}
+}
+
+impl CreepSpreader {
+	/// Constructs new spreader with default settings.
+	pub fn new() -> Self {
+		Default::default()
+	}
+	/// Returns tiles on the edge of creep, i.e. tiles with creep next to pathable tiles without it.
+	pub fn edge(&self) -> &[Point2] {
+		&self.edge
+	}
+	/// Checks if given tumor has already spawned new tumor and can't spread anymore.
+	pub fn is_spent(&self, tumor: u64) -> bool {
+		self.spent.contains(&tumor)
+	}
+	/// Returns tags of tumors, which have already spawned new tumors.
+	pub fn spent(&self) -> impl Iterator<Item = u64> + '_ {
+		self.spent.iter().copied()
+	}
+	/// Returns own tumors, which can spawn new tumor now.
+	pub fn active_tumors(&self, bot: &Bot) -> Units {
+		bot.units.my.structures.filter(|u| self.is_active(u))
+	}
+
+	fn is_active(&self, u: &Unit) -> bool {
+		u.type_id() == UnitTypeId::CreepTumorBurrowed
+			&& !self.spent.contains(&u.tag())
+			&& !self.ordered.contains_key(&u.tag())
+			&& u.has_ability(AbilityId::BuildCreepTumorTumor)
+	}
+
+	/// Updates creep edge and state of tumors. Should be called every step.
+	pub fn step(&mut self, bot: &Bot) {
+		let game_loop = bot.state.observation.game_loop();
+		let units = &bot.units.my.all;
+
+		if self.reserved.dim() != bot.game_info.placement_grid.dim() {
+			self.reserved = reserve_slots(bot);
+		}
+
+		// Tumor which lost its ability after the order has spawned new tumor
+		self.spent.retain(|t| units.contains_tag(*t));
+		let mut spent = vec![];
+		self.ordered.retain(|t, ordered_at| match units.get(*t) {
+			Some(u) if !u.has_ability(AbilityId::BuildCreepTumorTumor) => {
+				spent.push(*t);
+				false
+			}
+			Some(_) => game_loop.saturating_sub(*ordered_at) < RETRY_DELAY,
+			None => false,
+		});
+		self.spent.extend(spent);
+
+		let area = bot.game_info.playable_area;
+		self.edge.clear();
+		for x in area.x0 + 1..area.x1 - 1 {
+			for y in area.y0 + 1..area.y1 - 1 {
+				if bot.has_creep((x, y))
+					&& [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+						.iter()
+						.any(|n| bot.is_pathable(*n) && !bot.has_creep(*n))
+				{
+					self.edge.push(Point2::new(x as f32 + 0.5, y as f32 + 0.5));
+				}
+			}
+		}
+
+		self.planned.clear();
+	}
+
+	/// Orders all active tumors to spawn new tumors at best spots in their range.
+	///
+	/// Returns number of tumors ordered.
+	pub fn spread(&mut self, bot: &Bot) -> usize {
+		let tumors = self.active_tumors(bot);
+		if tumors.is_empty() {
+			return 0;
+		}
+
+		let grid = &bot.placement;
+		let game_loop = bot.state.observation.game_loop();
+		let mut count = 0;
+		for tumor in &tumors {
+			if let Some(spot) = self.tumor_spot(bot, grid, tumor.position(), self.tumor_range) {
+				tumor.command(AbilityId::BuildCreepTumorTumor, Target::Pos(spot), false);
+				self.ordered.insert(tumor.tag(), game_loop);
+				self.planned.push(spot);
+				count += 1;
+			}
+		}
+		count
+	}
+	/// Orders given queen to place tumor at best spot near her.
+	///
+	/// Returns chosen spot or `None` if queen can't place tumor now or there're no good spots.
+	pub fn spread_with_queen(&mut self, bot: &Bot, queen: &Unit) -> Option<Point2> {
+		if !queen.has_ability(AbilityId::BuildCreepTumorQueen) {
+			return None;
+		}
+		let grid = &bot.placement;
+		let spot = self.tumor_spot(bot, grid, queen.position(), self.queen_range)?;
+		queen.command(AbilityId::BuildCreepTumorQueen, Target::Pos(spot), false);
+		self.planned.push(spot);
+		Some(spot)
+	}
+
+	/// Returns best spot for new tumor in given range around given position.
+	///
+	/// Spot should have creep and vision, be free to build on and not reserved for buildings.
+	/// Returns `None` if there're no spots, which give new creep or progress towards targets.
+	pub fn tumor_spot(&self, bot: &Bot, grid: &PlacementGrid, from: Point2, range: f32) -> Option<Point2> {
+		let enemy_start = [bot.enemy_start];
+		let targets = if self.targets.is_empty() {
+			&enemy_start[..]
+		} else {
+			&self.targets[..]
+		};
+		let target = *targets.iter().closest(from)?;
+		let from_distance = from.distance(target);
+
+		let r = range as isize;
+		let (fx, fy) = (from.x as isize, from.y as isize);
+		let mut best: Option<(Point2, f32)> = None;
+		for x in fx - r..=fx + r {
+			for y in fy - r..=fy + r {
+				if x < 0 || y < 0 {
+					continue;
+				}
+				let pos = (x as usize, y as usize);
+				let spot = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+				if !spot.is_closer(range, from)
+					|| !bot.has_creep(pos)
+					|| !bot.is_visible(pos)
+					|| !grid.is_free(x, y)
+					|| (self.avoid_slots && self.reserved.get(pos).copied().unwrap_or(true))
+					|| self.planned.iter().any(|p| p.is_closer(1.5, spot))
+				{
+					continue;
+				}
+
+				let score =
+					self.new_creep(bot, spot) + self.target_weight * (from_distance - spot.distance(target));
+				if score > 0.0 && best.map_or(true, |(_, s)| score > s) {
+					best = Some((spot, score));
+				}
+			}
+		}
+		best.map(|(spot, _)| spot)
+	}
+
+	// Approximate number of tiles without creep, which will be covered by tumor at given spot
+	fn new_creep(&self, bot: &Bot, spot: Point2) -> f32 {
+		let area = bot.game_info.playable_area;
+		let r = CREEP_RADIUS as isize;
+		let mut count = 0;
+		for dx in (-r..=r).step_by(2) {
+			for dy in (-r..=r).step_by(2) {
+				let p = spot.offset(dx as f32, dy as f32);
+				if p.x < area.x0 as f32
+					|| p.y < area.y0 as f32
+					|| p.x >= area.x1 as f32
+					|| p.y >= area.y1 as f32
+					|| !p.is_closer(CREEP_RADIUS, spot)
+				{
+					continue;
+				}
+				if bot.is_pathable(p)
+					&& !bot.has_creep(p)
+					&& !self.planned.iter().any(|s| s.is_closer(CREEP_RADIUS, p))
+				{
+					count += 1;
+				}
+			}
+		}
+		// Every sample stands for 2x2 tiles
+		count as f32 * 4.0
+	}
+}
+
+fn reserve_slots(bot: &Bot) -> Array2<bool> {
+	let mut reserved = Array2::from_elem(bot.game_info.placement_grid.dim(), false);
+
+	for exp in &bot.expansions {
+		fill_footprint(&mut reserved, exp.loc, 7, 7, 0);
+	}
+	for base in &bot.layout.bases {
+		for pos in &base.production {
+			fill_footprint(&mut reserved, pos.offset(1.0, 0.0), 5, 3, 0);
+		}
+		for pos in base.supply.iter().chain(&base.defense) {
+			fill_footprint(&mut reserved, *pos, 2, 2, 0);
+		}
+	}
+	let wall = &bot.layout.wall;
+	for pos in wall.depots.iter().chain(wall.pylon.iter()) {
+		fill_footprint(&mut reserved, *pos, 2, 2, 0);
+	}
+	if let Some(barracks) = wall.barracks {
+		fill_footprint(&mut reserved, barracks.offset(1.0, 0.0), 5, 3, 0);
+	}
+	for pos in &wall.buildings {
+		fill_footprint(&mut reserved, *pos, 3, 3, 0);
+	}
+	reserved
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::{Expansion, LockOwned, Locked, Rs, Rw},
+		game_state::Alliance,
+		geometry::Rect,
+		pixel_map::{Pixel, Visibility},
+		unit::{tests::proto, DataForUnit},
+	};
+
+	/// Bot on open 48x48 map with visible creep in 10x10 square at (10, 10).
+	fn bot_with_creep() -> Bot {
+		let mut bot = Bot::default();
+		let dim = (48, 48);
+		bot.game_info.playable_area = Rect::new(0, 0, 48, 48);
+		bot.game_info.placement_grid = Array2::from_elem(dim, Pixel::Empty);
+		bot.state.observation.raw.visibility = Array2::from_elem(dim, Visibility::Visible);
+		let mut creep = Array2::from_elem(dim, Pixel::Set);
+		for x in 10..20 {
+			for y in 10..20 {
+				creep[(x, y)] = Pixel::Empty;
+			}
+		}
+		*bot.state.observation.raw.creep.write_lock() = creep;
+		bot.enemy_start = Point2::new(40.5, 15.5);
+		bot.placement = PlacementGrid::new(&bot);
+		bot
+	}
+
+	#[test]
+	fn edge_of_creep() {
+		let bot = bot_with_creep();
+		let mut spreader = CreepSpreader::new();
+		spreader.step(&bot);
+
+		assert_eq!(spreader.edge().len(), 36);
+		assert!(spreader.edge().contains(&Point2::new(10.5, 15.5)));
+		assert!(spreader.edge().contains(&Point2::new(19.5, 19.5)));
+		assert!(!spreader.edge().contains(&Point2::new(15.5, 15.5)));
+		assert_eq!(spreader.reserved.dim(), (48, 48));
+	}
+
+	#[test]
+	fn tumor_spot_moves_towards_target() {
+		let bot = bot_with_creep();
+		let mut spreader = CreepSpreader::new();
+		spreader.target_weight = 100.0;
+		spreader.step(&bot);
+
+		let from = Point2::new(15.5, 15.5);
+		let spot = spreader.tumor_spot(&bot, &bot.placement, from, 10.0).unwrap();
+		assert_eq!(spot.x, 19.5);
+		assert!(spot.is_closer(10.0, from));
+	}
+
+	#[test]
+	fn tumor_spot_skips_planned_and_reserved() {
+		let bot = bot_with_creep();
+		let mut spreader = CreepSpreader::new();
+		spreader.step(&bot);
+
+		let from = Point2::new(15.5, 15.5);
+		let first = spreader.tumor_spot(&bot, &bot.placement, from, 10.0).unwrap();
+		spreader.planned.push(first);
+		let second = spreader.tumor_spot(&bot, &bot.placement, from, 10.0).unwrap();
+		assert!(!second.is_closer(1.5, first));
+
+		spreader.reserved.fill(true);
+		assert_eq!(spreader.tumor_spot(&bot, &bot.placement, from, 10.0), None);
+		spreader.avoid_slots = false;
+		assert!(spreader.tumor_spot(&bot, &bot.placement, from, 10.0).is_some());
+	}
+
+	#[test]
+	fn tumor_spot_needs_gain() {
+		let bot = bot_with_creep();
+		bot.state.observation.raw.creep.write_lock().fill(Pixel::Empty);
+		let mut spreader = CreepSpreader::new();
+		spreader.step(&bot);
+
+		let spot = spreader.tumor_spot(&bot, &bot.placement, bot.enemy_start, 10.0);
+		assert_eq!(spot, None);
+	}
+
+	#[test]
+	fn tumor_bookkeeping() {
+		let mut bot = bot_with_creep();
+		let abilities: Rw<FxHashMap<u64, FxHashSet<AbilityId>>> = Default::default();
+		let data = Rs::new(DataForUnit {
+			abilities_units: Rs::clone(&abilities),
+			..Default::default()
+		});
+		let visibility = Array2::from_elem((48, 48), Visibility::Visible);
+		let tumor = Unit::from_proto(
+			data,
+			&visibility,
+			&proto(1, UnitTypeId::CreepTumorBurrowed, (15.5, 15.5)),
+		);
+		abilities
+			.write_lock()
+			.insert(1, [AbilityId::BuildCreepTumorTumor].into_iter().collect());
+		bot.units.my.all.push(tumor.clone());
+
+		let mut spreader = CreepSpreader::new();
+		spreader.step(&bot);
+		assert!(spreader.is_active(&tumor));
+
+		// Tumor still able to spread after the order is retried
+		spreader.ordered.insert(1, 0);
+		assert!(!spreader.is_active(&tumor));
+		bot.state.observation.game_loop.set_locked(RETRY_DELAY);
+		spreader.step(&bot);
+		assert!(spreader.is_active(&tumor));
+
+		// Tumor which lost its ability is spent
+		spreader.ordered.insert(1, RETRY_DELAY);
+		abilities.write_lock().clear();
+		spreader.step(&bot);
+		assert!(spreader.is_spent(1));
+		assert!(!spreader.is_active(&tumor));
+
+		bot.units.my.all.remove(1);
+		spreader.step(&bot);
+		assert!(!spreader.is_spent(1));
+	}
+
+	#[test]
+	fn slots_reserved() {
+		let mut bot = bot_with_creep();
+		let loc = Point2::new(24.5, 24.5);
+		bot.expansions.push(Expansion {
+			loc,
+			center: loc,
+			minerals: Default::default(),
+			geysers: Default::default(),
+			alliance: Alliance::Neutral,
+			base: None,
+		});
+		bot.layout.wall.barracks = Some(Point2::new(10.5, 10.5));
+
+		let reserved = reserve_slots(&bot);
+		assert!(reserved[(21, 21)]);
+		assert!(reserved[(27, 27)]);
+		assert!(!reserved[(28, 24)]);
+		assert!(!reserved[(20, 24)]);
+		// Barracks with addon
+		assert!(reserved[(9, 9)]);
+		assert!(reserved[(13, 11)]);
+		assert!(!reserved[(14, 11)]);
+	}
+}
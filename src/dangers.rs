@@ -0,0 +1,448 @@
+//! Danger zones of harmful effects and abilities.
+//!
+//! Dangers are built every step from active [`effects`](crate::game_state::RawData::effects)
+//! (psionic storms, biles, nukes, liberator zones, ...) and units, which will explode soon
+//! (purification novas, KD8 charges), and can be accessed through
+//! [`dangers`](crate::bot::Bot::dangers) field:
+//! ```
+//! for u in &self.units.my.units {
+//!     if let Some(pos) = self.dangers.escape(self, u) {
+//!         u.move_to(Target::Pos(pos), false);
+//!     }
+//! }
+//! ```
+//!
+//! Friendly fire is taken into account, so our own storms and novas are dangers too.
+
+use crate::{
+	bot::Bot,
+	consts::FRAMES_PER_SECOND,
+	distance::*,
+	game_state::Alliance,
+	geometry::Point2,
+	ids::{EffectId, UnitTypeId},
+	unit::Unit,
+};
+use rustc_hash::FxHashMap;
+use std::f32::consts::PI;
+
+/// Distance from the border of danger, which is considered safe.
+const SAFE_MARGIN: f32 = 0.5;
+
+/// What causes the danger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DangerSource {
+	/// Active effect on the map.
+	Effect(EffectId),
+	/// Unit, which will explode soon.
+	Unit(UnitTypeId),
+}
+
+/// Area, where units take damage or can be disabled.
+#[derive(Debug, Clone, Copy)]
+pub struct Danger {
+	/// What causes the danger.
+	pub source: DangerSource,
+	/// Center of the area.
+	pub position: Point2,
+	/// Radius of the area.
+	pub radius: f32,
+	/// Owner of the danger.
+	pub alliance: Alliance,
+	/// Game loops left until danger ends (or hits for predicted dangers),
+	/// `None` if danger lasts while its source is active.
+	pub remaining: Option<u32>,
+	/// `true` if danger isn't active yet and will hit the area later.
+	pub is_predicted: bool,
+	/// Danger affects ground units.
+	pub hits_ground: bool,
+	/// Danger affects air units.
+	pub hits_air: bool,
+}
+impl Danger {
+	/// Checks if given position is inside the danger.
+	pub fn contains<P: Into<Point2>>(&self, pos: P) -> bool {
+		pos.into().is_closer(self.radius, self.position)
+	}
+	/// Checks if danger affects given unit standing at its current position.
+	pub fn affects(&self, unit: &Unit) -> bool {
+		self.affects_at(unit, unit.position())
+	}
+	/// Checks if danger affects given unit standing at given position.
+	pub fn affects_at<P: Into<Point2>>(&self, unit: &Unit, pos: P) -> bool {
+		(if unit.is_flying() {
+			self.hits_air
+		} else {
+			self.hits_ground
+		}) && pos.into().is_closer(self.radius + unit.radius(), self.position)
+	}
+}
+
+struct Info {
+	duration: Option<f32>,
+	is_predicted: bool,
+	hits_ground: bool,
+	hits_air: bool,
+	friendly_fire: bool,
+}
+
+fn effect_info(id: EffectId) -> Option<Info> {
+	let info = |duration, is_predicted, hits_air, friendly_fire| Info {
+		duration,
+		is_predicted,
+		hits_ground: true,
+		hits_air,
+		friendly_fire,
+	};
+	Some(match id {
+		EffectId::PsiStormPersistent => info(Some(2.85), false, true, true),
+		EffectId::NukePersistent => info(Some(14.0), true, true, true),
+		EffectId::RavagerCorrosiveBileCP => info(Some(2.5), true, true, true),
+		EffectId::BlindingCloudCP => info(Some(5.71), false, false, true),
+		EffectId::LurkerMP => info(Some(1.0), false, false, false),
+		EffectId::ThermalLancesForward => info(Some(0.5), false, false, false),
+		EffectId::LiberatorTargetMorphDelayPersistent | EffectId::LiberatorTargetMorphPersistent => {
+			info(None, false, false, false)
+		}
+		_ => return None,
+	})
+}
+
+fn unit_info(id: UnitTypeId) -> Option<(f32, Info)> {
+	let info = |duration| Info {
+		duration: Some(duration),
+		is_predicted: true,
+		hits_ground: true,
+		hits_air: false,
+		friendly_fire: true,
+	};
+	Some(match id {
+		UnitTypeId::DisruptorPhased => (1.5, info(2.1)),
+		UnitTypeId::KD8Charge => (1.0, info(1.0)),
+		_ => return None,
+	})
+}
+
+/// All dangers on the map.
+#[derive(Default, Clone)]
+pub struct Dangers {
+	dangers: Vec<Danger>,
+	first_seen: FxHashMap<(DangerSource, (i32, i32)), u32>,
+}
+
+impl Dangers {
+	pub(crate) fn update(&mut self, bot: &Bot) {
+		let game_loop = bot.state.observation.game_loop();
+		let mut first_seen = FxHashMap::default();
+		self.dangers.clear();
+
+		let mut add = |source, position: Point2, radius, alliance: Alliance, info: Info| {
+			if alliance.is_mine() && !info.friendly_fire {
+				return;
+			}
+			let key = (source, ((position.x * 4.0) as i32, (position.y * 4.0) as i32));
+			let seen = *self.first_seen.get(&key).unwrap_or(&game_loop);
+			first_seen.insert(key, seen);
+
+			let elapsed = game_loop.saturating_sub(seen);
+			self.dangers.push(Danger {
+				source,
+				position,
+				radius,
+				alliance,
+				remaining: info
+					.duration
+					.map(|d| ((d * FRAMES_PER_SECOND) as u32).saturating_sub(elapsed)),
+				is_predicted: info.is_predicted,
+				hits_ground: info.hits_ground,
+				hits_air: info.hits_air,
+			});
+		};
+
+		for e in &bot.state.observation.raw.effects {
+			for p in &e.positions {
+				if let Some(info) = effect_info(e.id) {
+					add(DangerSource::Effect(e.id), *p, e.radius, e.alliance, info);
+				}
+			}
+		}
+		for u in &bot.units.all {
+			if let Some((radius, info)) = unit_info(u.type_id()) {
+				add(
+					DangerSource::Unit(u.type_id()),
+					u.position(),
+					radius,
+					u.alliance(),
+					info,
+				);
+			}
+		}
+
+		self.first_seen = first_seen;
+	}
+
+	/// Returns all dangers.
+	pub fn all(&self) -> &[Danger] {
+		&self.dangers
+	}
+	/// Returns dangers which are active now.
+	pub fn active(&self) -> impl Iterator<Item = &Danger> {
+		self.dangers.iter().filter(|d| !d.is_predicted)
+	}
+	/// Returns dangers which will hit soon.
+	pub fn predicted(&self) -> impl Iterator<Item = &Danger> {
+		self.dangers.iter().filter(|d| d.is_predicted)
+	}
+	/// Returns dangers containing given position.
+	pub fn at<P: Into<Point2> + Copy>(&self, pos: P) -> impl Iterator<Item = &Danger> {
+		self.dangers.iter().filter(move |d| d.contains(pos))
+	}
+	/// Checks if given position is inside of any danger.
+	pub fn is_dangerous<P: Into<Point2> + Copy>(&self, pos: P) -> bool {
+		self.at(pos).next().is_some()
+	}
+	/// Checks if given unit is affected by any danger at given position.
+	pub fn is_dangerous_for<P: Into<Point2> + Copy>(&self, unit: &Unit, pos: P) -> bool {
+		self.dangers.iter().any(|d| d.affects_at(unit, pos))
+	}
+
+	/// Returns the closest safe position for given unit if it's in danger now.
+	///
+	/// Position is safe if all dangers there end or hit before unit reaches it
+	/// with its [`real_speed`](Unit::real_speed). Positions, which let unit leave
+	/// predicted dangers before they hit, are preferred.
+	/// Returns `None` if unit isn't in danger or there're no safe positions nearby.
+	pub fn escape(&self, bot: &Bot, unit: &Unit) -> Option<Point2> {
+		let dangers = self
+			.dangers
+			.iter()
+			.filter(|d| d.affects(unit))
+			.collect::<Vec<_>>();
+		if dangers.is_empty() {
+			return None;
+		}
+		let pos = unit.position();
+		let speed = unit.real_speed() / FRAMES_PER_SECOND;
+
+		// Points right outside of the dangers and on rings around the unit
+		let mut candidates = vec![];
+		for d in &dangers {
+			let distance = d.radius + unit.radius() + SAFE_MARGIN;
+			if pos.is_closer(0.01, d.position) {
+				candidates.extend((0..8).map(|i| d.position.towards_angle(i as f32 * PI / 4.0, distance)));
+			} else {
+				candidates.push(d.position.towards(pos, distance));
+			}
+		}
+		let max_radius = dangers.iter().map(|d| d.radius).fold(0.0, f32::max) * 2.0 + unit.radius() + 1.0;
+		let mut r = 1.0;
+		while r <= max_radius {
+			candidates.extend((0..16).map(|i| pos.towards_angle(i as f32 * PI / 8.0, r)));
+			r += 1.0;
+		}
+
+		candidates
+			.into_iter()
+			.filter(|p| bot.game_info.playable_area.contains(*p))
+			.filter(|p| unit.is_flying() || bot.is_pathable(*p))
+			.filter_map(|p| {
+				let distance = p.distance(pos);
+				let arrival = distance / speed;
+				if !self
+					.dangers
+					.iter()
+					.filter(|d| d.affects_at(unit, p))
+					.all(|d| d.remaining.is_some_and(|r| arrival > r as f32))
+				{
+					return None;
+				}
+				let late = dangers.iter().any(|d| {
+					d.is_predicted
+						&& d.remaining.is_some_and(|r| {
+							exit_distance(pos, p, d.position, d.radius + unit.radius()) / speed > r as f32
+						})
+				});
+				Some((p, late, distance))
+			})
+			.min_by(|(_, late1, d1), (_, late2, d2)| late1.cmp(late2).then(cmp(d1, d2)))
+			.map(|(p, _, _)| p)
+	}
+}
+
+/// Distance along the way from `from` to `to`, after which circle around `center` is left.
+fn exit_distance(from: Point2, to: Point2, center: Point2, radius: f32) -> f32 {
+	let offset = from - center;
+	let b = -offset.dot((to - from).normalize());
+	b + (b * b - offset.dot(offset) + radius * radius).max(0.0).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::LockOwned,
+		game_state::Effect,
+		geometry::Rect,
+		unit::tests::{combat_data, from_proto, proto, unit, with_data},
+	};
+
+	fn danger(position: Point2, radius: f32, hits_air: bool) -> Danger {
+		Danger {
+			source: DangerSource::Effect(EffectId::PsiStormPersistent),
+			position,
+			radius,
+			alliance: Alliance::Enemy,
+			remaining: None,
+			is_predicted: false,
+			hits_ground: true,
+			hits_air,
+		}
+	}
+	/// Bot on open 32x32 map.
+	fn bot() -> Bot {
+		let mut bot = Bot::default();
+		bot.game_info.playable_area = Rect::new(0, 0, 32, 32);
+		bot
+	}
+	fn effect(id: EffectId, position: Point2, radius: f32, alliance: Alliance) -> Effect {
+		Effect {
+			id,
+			positions: vec![position],
+			alliance,
+			owner: 0,
+			radius,
+		}
+	}
+
+	#[test]
+	fn danger_contains_and_affects() {
+		let d = danger(Point2::new(10.0, 10.0), 1.5, false);
+		assert!(d.contains(Point2::new(11.0, 10.0)));
+		assert!(!d.contains(Point2::new(11.6, 10.0)));
+
+		// Unit's radius is counted
+		let ground = unit(1, UnitTypeId::Marine, (11.6, 10.0));
+		assert!(d.affects(&ground));
+		assert!(!d.affects_at(&ground, Point2::new(12.1, 10.0)));
+
+		let mut flying = proto(2, UnitTypeId::Medivac, (10.0, 10.0));
+		flying.set_is_flying(true);
+		let flying = from_proto(&flying);
+		assert!(!d.affects(&flying));
+		assert!(danger(Point2::new(10.0, 10.0), 1.5, true).affects(&flying));
+	}
+
+	#[test]
+	fn dangers_from_effects_and_units() {
+		let mut bot = Bot::default();
+		let storm = effect(
+			EffectId::PsiStormPersistent,
+			Point2::new(10.0, 10.0),
+			1.5,
+			Alliance::Enemy,
+		);
+		bot.state.observation.raw.effects = vec![
+			storm.clone(),
+			// Own lurker spines don't hit own units
+			effect(EffectId::LurkerMP, Point2::new(20.0, 20.0), 0.5, Alliance::Own),
+			effect(
+				EffectId::LiberatorTargetMorphPersistent,
+				Point2::new(30.0, 30.0),
+				5.0,
+				Alliance::Enemy,
+			),
+		];
+		bot.units.all.push(unit(1, UnitTypeId::KD8Charge, (40.0, 40.0)));
+
+		let mut dangers = Dangers::default();
+		dangers.update(&bot);
+		assert_eq!(dangers.all().len(), 3);
+		assert_eq!(dangers.active().count(), 2);
+		assert_eq!(dangers.predicted().count(), 1);
+		assert!(dangers.is_dangerous(Point2::new(10.5, 10.0)));
+		assert!(!dangers.is_dangerous(Point2::new(20.0, 20.0)));
+		assert_eq!(
+			dangers.at(Point2::new(30.0, 30.0)).next().unwrap().remaining,
+			None
+		);
+
+		// Remaining time is counted from the first step danger was seen
+		let storm_remaining =
+			|dangers: &Dangers| dangers.at(Point2::new(10.0, 10.0)).next().unwrap().remaining;
+		assert_eq!(storm_remaining(&dangers), Some(63));
+		bot.state.observation.game_loop.set_locked(10);
+		dangers.update(&bot);
+		assert_eq!(storm_remaining(&dangers), Some(53));
+
+		// New danger at the same place
+		bot.state.observation.raw.effects.clear();
+		dangers.update(&bot);
+		assert!(!dangers.is_dangerous(Point2::new(10.0, 10.0)));
+		bot.state.observation.raw.effects.push(storm);
+		dangers.update(&bot);
+		assert_eq!(storm_remaining(&dangers), Some(63));
+	}
+
+	#[test]
+	fn escape_from_danger() {
+		let bot = bot();
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (10.0, 10.0)));
+		let mut dangers = Dangers::default();
+		assert_eq!(dangers.escape(&bot, &marine), None);
+
+		let mut storm = danger(Point2::new(10.5, 10.0), 1.5, true);
+		storm.remaining = Some(63);
+		dangers.dangers.push(storm);
+		let pos = dangers.escape(&bot, &marine).unwrap();
+		assert!(!dangers.is_dangerous_for(&marine, pos));
+		assert!(pos.is_closer(2.01, marine.position()));
+	}
+
+	#[test]
+	fn escape_in_time() {
+		let bot = bot();
+		let data = combat_data();
+		// Marine moves 0.14 per game loop
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (10.0, 10.0)));
+		let mut dangers = Dangers::default();
+		dangers.dangers.push(danger(Point2::new(7.0, 10.0), 3.5, false));
+		let mut bile = danger(Point2::new(10.5, 10.0), 1.0, false);
+		bile.remaining = Some(11);
+		bile.is_predicted = true;
+		dangers.dangers.push(bile);
+
+		// Safe points 2 away to the east are left too late, while bile hits
+		let pos = dangers.escape(&bot, &marine).unwrap();
+		assert!((pos.x - 10.0).abs() < 0.01 && ((pos.y - 10.0).abs() - 3.0).abs() < 0.01);
+
+		// Dangers, which will be over before unit arrives, don't matter
+		dangers.dangers[0].remaining = Some(5);
+		let pos = dangers.escape(&bot, &marine).unwrap();
+		assert!(pos.is_closer(1.01, marine.position()));
+	}
+
+	#[test]
+	fn exit_distance_from_circle() {
+		let center = Point2::new(10.5, 10.0);
+		let from = Point2::new(10.0, 10.0);
+		assert!((exit_distance(from, Point2::new(20.0, 10.0), center, 1.5) - 2.0).abs() < 1e-5);
+		assert!((exit_distance(from, Point2::new(0.0, 10.0), center, 1.5) - 1.0).abs() < 1e-5);
+		assert!((exit_distance(from, Point2::new(10.0, 20.0), center, 1.5) - 2f32.sqrt()).abs() < 1e-5);
+	}
+
+	#[test]
+	fn escape_inside_playable_area() {
+		let mut bot = bot();
+		bot.game_info.playable_area = Rect::new(2, 2, 30, 30);
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (3.0, 10.0)));
+		let mut dangers = Dangers::default();
+		dangers.dangers.push(danger(Point2::new(3.5, 10.0), 1.5, false));
+
+		// The closest safe point is at (1, 10)
+		let pos = dangers.escape(&bot, &marine).unwrap();
+		assert!(bot.game_info.playable_area.contains(pos));
+		assert!(!dangers.is_dangerous_for(&marine, pos));
+	}
+}
@@ -39,8 +39,9 @@ pub trait Distance: Into<Point2> {
 
 impl<T: Into<Point2>> Distance for T {}
 
+/// Compares partially ordered values, treating incomparable ones (e.g. `NaN`) as equal.
 #[inline]
-fn cmp<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+pub(crate) fn cmp<T: PartialOrd>(a: &T, b: &T) -> Ordering {
 	a.partial_cmp(b).unwrap_or(Ordering::Equal)
 }
 
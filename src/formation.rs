@@ -0,0 +1,276 @@
+//! Formations for moving groups of units.
+//!
+//! Moving many units to one point makes them string out in a line,
+//! so instead every unit can be given own slot in a formation facing the target:
+//! ```
+//! let army = self.units.my.units.ground();
+//! if let Some(center) = army.center() {
+//!     let anchor = center.towards(self.enemy_start, 5.0);
+//!     Formation::default().move_units(&army, anchor, self.enemy_start, false);
+//! }
+//! ```
+
+use crate::{action::Target, distance::*, geometry::Point2, unit::Unit, units::Units};
+use std::{f32::consts::PI, mem};
+
+/// Space left between neighboring units in formation.
+const GAP: f32 = 0.25;
+
+/// Shape of the formation.
+///
+/// Formation is placed at the anchor point (middle of the front row) and faces the target.
+/// Units which don't fit in the front row are placed in next rows behind it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formation {
+	/// Arc around the target, so every unit in a row is at the same distance from it.
+	/// Argument is maximum angle of the arc in radians.
+	Concave(f32),
+	/// Arc with given radius and maximum angle in radians, bent towards the target.
+	Arc {
+		/// Radius of the front row.
+		radius: f32,
+		/// Maximum angle of the arc.
+		angle: f32,
+	},
+	/// Straight rows perpendicular to direction to the target.
+	/// Argument is maximum width of the row.
+	Line(f32),
+}
+impl Default for Formation {
+	/// Concave with 120° arc.
+	fn default() -> Self {
+		Formation::Concave(PI * 2.0 / 3.0)
+	}
+}
+
+impl Formation {
+	/// Computes slots for units with given radii.
+	///
+	/// Slots are filled row by row in given order of radii,
+	/// and returned slot with index `i` is for unit with radius `radii[i]`.
+	pub fn slots(self, anchor: Point2, target: Point2, radii: &[f32]) -> Vec<Point2> {
+		let direction = if anchor.is_closer(0.01, target) {
+			Point2::new(1.0, 0.0)
+		} else {
+			(target - anchor).normalize()
+		};
+
+		// Radius of the front row, `None` for straight rows
+		let curvature = match self {
+			Formation::Concave(_) => Some(anchor.distance(target).max(1.0)),
+			Formation::Arc { radius, .. } => Some(radius.max(1.0)),
+			Formation::Line(_) => None,
+		};
+
+		// Splitting units into rows
+		let mut rows = vec![];
+		let mut row = vec![];
+		let mut length = 0.0;
+		let mut depth = 0.0;
+		for (i, r) in radii.iter().enumerate() {
+			let width = r * 2.0 + GAP;
+			let max_length = match self {
+				Formation::Concave(angle) | Formation::Arc { angle, .. } => {
+					(curvature.unwrap_or(0.0) + depth) * angle
+				}
+				Formation::Line(max_width) => max_width,
+			};
+			if !row.is_empty() && length + width > max_length {
+				let row_depth = row_depth(&row, radii);
+				rows.push((mem::take(&mut row), length, depth));
+				depth += row_depth;
+				length = 0.0;
+			}
+			row.push(i);
+			length += width;
+		}
+		if !row.is_empty() {
+			rows.push((row, length, depth));
+		}
+
+		let side = direction.rotate90(true);
+		let mut slots = vec![Point2::default(); radii.len()];
+		for (row, length, depth) in rows {
+			let mut s = -length / 2.0;
+			for i in row {
+				let offset = s + radii[i] + GAP / 2.0;
+				slots[i] = match curvature {
+					Some(radius) => {
+						let radius = radius + depth;
+						let center = anchor + direction * (radius - depth);
+						center + (-direction).rotate(offset / radius) * radius
+					}
+					None => anchor - direction * depth + side * offset,
+				};
+				s += radii[i] * 2.0 + GAP;
+			}
+		}
+		slots
+	}
+
+	/// Assigns given units to slots of the formation.
+	///
+	/// The biggest units are placed in the front rows and every unit gets the closest free slot
+	/// made for unit of its size.
+	pub fn assign(self, units: &Units, anchor: Point2, target: Point2) -> Vec<(u64, Point2)> {
+		let mut units = units.iter().collect::<Vec<_>>();
+		units.sort_by(|a, b| cmp(&b.radius(), &a.radius()));
+		let radii = units.iter().map(|u| u.radius()).collect::<Vec<_>>();
+		let slots = self.slots(anchor, target, &radii);
+
+		// Units of the same size are interchangeable
+		let mut assigned = Vec::with_capacity(units.len());
+		let mut start = 0;
+		while start < units.len() {
+			let end = (start..units.len())
+				.find(|i| (radii[*i] - radii[start]).abs() > 0.01)
+				.unwrap_or(units.len());
+			assigned.extend(assign_slots(&units[start..end], &slots[start..end]));
+			start = end;
+		}
+		assigned
+	}
+	/// Orders given units to move to their slots in the formation.
+	pub fn move_units(self, units: &Units, anchor: Point2, target: Point2, queue: bool) {
+		for (tag, slot) in self.assign(units, anchor, target) {
+			if let Some(u) = units.get(tag) {
+				u.move_to(Target::Pos(slot), queue);
+			}
+		}
+	}
+}
+
+/// Greedily assigns units to slots minimizing their travel distance:
+/// the closest pairs of unit and slot are matched first.
+///
+/// Returns tags of units with their slots. If there're more units than slots, extra units are skipped.
+pub fn assign_slots(units: &[&Unit], slots: &[Point2]) -> Vec<(u64, Point2)> {
+	let mut pairs = Vec::with_capacity(units.len() * slots.len());
+	for (i, u) in units.iter().enumerate() {
+		for (j, slot) in slots.iter().enumerate() {
+			pairs.push((u.distance_squared(*slot), i, j));
+		}
+	}
+	pairs.sort_unstable_by(|a, b| cmp(&a.0, &b.0));
+
+	let mut unit_used = vec![false; units.len()];
+	let mut slot_used = vec![false; slots.len()];
+	let mut assigned = Vec::with_capacity(units.len().min(slots.len()));
+	for (_, i, j) in pairs {
+		if !(unit_used[i] || slot_used[j]) {
+			unit_used[i] = true;
+			slot_used[j] = true;
+			assigned.push((units[i].tag(), slots[j]));
+		}
+	}
+	assigned
+}
+
+fn row_depth(row: &[usize], radii: &[f32]) -> f32 {
+	row.iter().map(|i| radii[*i] * 2.0).fold(0.0, f32::max) + GAP
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		ids::UnitTypeId,
+		unit::tests::{from_proto, proto},
+	};
+
+	fn assert_close(a: Point2, b: Point2) {
+		assert!(a.is_closer(1e-4, b), "{:?} != {:?}", a, b);
+	}
+
+	#[test]
+	fn line_row_centered_on_anchor() {
+		let (anchor, target) = (Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+		let slots = Formation::Line(10.0).slots(anchor, target, &[0.5; 3]);
+		assert_close(slots[0], Point2::new(10.0, 11.25));
+		assert_close(slots[1], anchor);
+		assert_close(slots[2], Point2::new(10.0, 8.75));
+	}
+
+	#[test]
+	fn line_rows_behind_each_other() {
+		let (anchor, target) = (Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+		let slots = Formation::Line(2.0).slots(anchor, target, &[0.5; 3]);
+		assert_close(slots[0], anchor);
+		assert_close(slots[1], Point2::new(8.75, 10.0));
+		assert_close(slots[2], Point2::new(7.5, 10.0));
+	}
+
+	#[test]
+	fn concave_around_target() {
+		let (anchor, target) = (Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+		let slots = Formation::default().slots(anchor, target, &[0.5; 5]);
+		assert_close(slots[2], anchor);
+		for slot in &slots {
+			assert!((slot.distance(target) - 10.0).abs() < 1e-3);
+		}
+
+		// Next row is further from the target
+		let slots = Formation::Concave(0.1).slots(anchor, target, &[0.5; 2]);
+		assert_close(slots[0], anchor);
+		assert_close(slots[1], Point2::new(8.75, 10.0));
+	}
+
+	#[test]
+	fn arc_with_radius() {
+		let (anchor, target) = (Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+		let formation = Formation::Arc {
+			radius: 5.0,
+			angle: PI,
+		};
+		let center = Point2::new(15.0, 10.0);
+		for slot in formation.slots(anchor, target, &[0.5; 4]) {
+			assert!((slot.distance(center) - 5.0).abs() < 1e-3);
+		}
+	}
+
+	#[test]
+	fn slots_at_target() {
+		let anchor = Point2::new(10.0, 10.0);
+		let slots = Formation::Line(10.0).slots(anchor, anchor, &[0.5; 2]);
+		assert_close(slots[0], Point2::new(10.0, 10.625));
+		assert_close(slots[1], Point2::new(10.0, 9.375));
+	}
+
+	#[test]
+	fn closest_pairs_assigned_first() {
+		let a = from_proto(&proto(1, UnitTypeId::Marine, (0.0, 0.0)));
+		let b = from_proto(&proto(2, UnitTypeId::Marine, (10.0, 0.0)));
+		let c = from_proto(&proto(3, UnitTypeId::Marine, (20.0, 0.0)));
+		let slots = [Point2::new(9.0, 0.0), Point2::new(1.0, 0.0)];
+
+		let assigned = assign_slots(&[&a, &b], &slots);
+		assert_eq!(assigned.len(), 2);
+		assert!(assigned.contains(&(1, slots[1])));
+		assert!(assigned.contains(&(2, slots[0])));
+
+		let assigned = assign_slots(&[&a, &b, &c], &slots);
+		assert_eq!(assigned.len(), 2);
+		assert!(assigned.iter().all(|(tag, _)| *tag != 3));
+	}
+
+	#[test]
+	fn big_units_in_front() {
+		let mut big = proto(1, UnitTypeId::Thor, (0.0, 0.0));
+		big.set_radius(1.0);
+		let units: Units = [
+			from_proto(&big),
+			from_proto(&proto(2, UnitTypeId::Marine, (0.0, 5.0))),
+			from_proto(&proto(3, UnitTypeId::Marine, (0.0, -5.0))),
+		]
+		.into_iter()
+		.collect();
+
+		let (anchor, target) = (Point2::new(10.0, 10.0), Point2::new(20.0, 10.0));
+		let assigned = Formation::Line(3.0).assign(&units, anchor, target);
+		assert_eq!(assigned.len(), 3);
+		for (tag, slot) in assigned {
+			let x = if tag == 1 { 10.0 } else { 7.75 };
+			assert!((slot.x - x).abs() < 1e-4);
+		}
+	}
+}
@@ -1,12 +1,14 @@
 //! Information updated every step stored here.
 
 use crate::{
-	action::{Action, ActionError},
+	action::{Action, ActionError, CommandResult},
 	bot::{Bot, LockOwned, LockU32, Locked, Rs, Rw},
+	game_data::GameData,
 	geometry::Point2,
 	ids::*,
 	pixel_map::{PixelMap, VisibilityMap},
 	score::Score,
+	scouting::Scouting,
 	unit::Unit,
 	units::Units,
 	Event, FromProto, Player, SC2Result,
@@ -18,7 +20,10 @@ use sc2_proto::{
 	raw::{Alliance as ProtoAlliance, PowerSource as ProtoPowerSource},
 	sc2api::{Alert as ProtoAlert, Request, ResponseObservation},
 };
-use std::ops::{Deref, DerefMut};
+use std::{
+	mem,
+	ops::{Deref, DerefMut},
+};
 
 /// Information about current state on current step.
 ///
@@ -29,6 +34,8 @@ pub struct GameState {
 	pub actions: Vec<Action>,
 	/// Results on actions from previous step.
 	pub action_errors: Vec<ActionError>,
+	/// Commands issued by bot on previous step with their results and errors.
+	pub command_results: Vec<CommandResult>,
 	/// Bot's observation here.
 	pub observation: Observation,
 	// player_result,
@@ -44,6 +51,7 @@ where
 	B: Player + DerefMut<Target = Bot> + Deref<Target = Bot>,
 {
 	// Game state
+	let game_data = bot.game_data.clone();
 	let state = &mut bot.state;
 
 	// let player_result = response_observation.get_player_result();
@@ -57,6 +65,18 @@ where
 		.iter()
 		.map(ActionError::from_proto)
 		.collect();
+
+	// Attributing errors to commands issued on previous step
+	attribute_errors(&mut state.command_results, &state.action_errors, &game_data);
+	let mut events = state
+		.command_results
+		.iter()
+		.flat_map(|c| {
+			c.failures()
+				.into_iter()
+				.map(|(u, result)| Event::CommandFailed(u, c.ability, c.target, result))
+		})
+		.collect::<Vec<_>>();
 	state.chat = response_observation
 		.get_chat()
 		.iter()
@@ -140,7 +160,6 @@ where
 		})
 		.collect();
 
-	let mut events = vec![];
 	// Dead units
 	let dead_units = res_raw.get_event().get_dead_units().to_vec();
 
@@ -153,9 +172,10 @@ where
 			bot.under_construction.remove(u);
 			bot.last_units_hits.write_lock().remove(u);
 			bot.last_units_seen.write_lock().remove(u);
+			bot.last_units.write_lock().remove(u);
 			Some(Alliance::Own)
 		} else {
-			let removed = bot.saved_hallucinations.remove(u);
+			let removed = bot.saved_hallucinations.remove(u) | bot.enemy_memory.remove(*u).is_some();
 
 			#[cfg(feature = "enemies_cache")]
 			let removed = {
@@ -187,6 +207,7 @@ where
 	raw.dead_units = dead_units;
 
 	// Upgrades
+	let last_upgrades = raw.upgrades.read_lock().clone();
 	*raw.upgrades.write_lock() = raw_player
 		.get_upgrade_ids()
 		.iter()
@@ -239,8 +260,29 @@ where
 	bot.state.observation.raw.visibility = visibility;
 
 	// Updating units
+	let last_expansions = bot.expansions.iter().map(|exp| exp.alliance).collect::<Vec<_>>();
 	bot.update_units(units);
 
+	// Enemy memory
+	let mut enemy_memory = mem::take(&mut bot.enemy_memory);
+	enemy_memory.update(bot);
+	bot.enemy_memory = enemy_memory;
+
+	// Vision and detection
+	let mut vision = mem::take(&mut bot.vision);
+	vision.update(bot);
+	bot.vision = vision;
+
+	// Dangers of effects
+	let mut dangers = mem::take(&mut bot.dangers);
+	dangers.update(bot);
+	bot.dangers = dangers;
+
+	// Placement grid
+	let mut placement = mem::take(&mut bot.placement);
+	placement.update(bot);
+	bot.placement = placement;
+
 	// Events
 	let mut owned_tags = vec![];
 	let mut under_construction = vec![];
@@ -275,6 +317,8 @@ where
 		bot.under_construction.remove(&tag);
 	}
 
+	events.extend(state_events(bot, &last_upgrades, &last_expansions));
+
 	if bot.enemy_race.is_random() {
 		if let Some(race) = bot
 			.units
@@ -289,9 +333,119 @@ where
 		}
 	}
 
+	// Strategies
+	let mut scouting = mem::replace(&mut bot.scouting, Scouting::empty());
+	events.extend(scouting.update(bot).into_iter().map(Event::StrategyDetected));
+	bot.scouting = scouting;
+
 	Ok(events)
 }
 
+/// Attributes errors of actions to commands with the same unit and ability.
+///
+/// Game can report specific ability of the failed action when general one was used in command
+/// (or vice versa), so such errors are attributed to commands with the same general ability.
+fn attribute_errors(commands: &mut [CommandResult], errors: &[ActionError], game_data: &GameData) {
+	let general = |ability: AbilityId| {
+		game_data
+			.abilities
+			.get(&ability)
+			.and_then(|data| data.remaps_to_ability_id)
+			.unwrap_or(ability)
+	};
+	for e in errors {
+		let command = commands
+			.iter()
+			.position(|c| c.ability == e.ability && c.units.contains(&e.unit))
+			.or_else(|| {
+				let ability = general(e.ability);
+				commands
+					.iter()
+					.position(|c| general(c.ability) == ability && c.units.contains(&e.unit))
+			});
+		if let Some(i) = command {
+			commands[i].errors.push((e.unit, e.result));
+		}
+	}
+}
+
+/// Events of upgrades, changes of units, enemies entering and leaving vision, expansions and alerts.
+fn state_events(
+	bot: &mut Bot,
+	last_upgrades: &FxHashSet<UpgradeId>,
+	last_expansions: &[Alliance],
+) -> Vec<Event> {
+	let mut events = vec![];
+	let upgrades = bot.state.observation.raw.upgrades.read_lock();
+	events.extend(
+		upgrades
+			.difference(last_upgrades)
+			.map(|u| Event::UpgradeCompleted(*u)),
+	);
+	drop(upgrades);
+
+	for u in &bot.units.all {
+		let tag = u.tag();
+		if let Some(last_type) = u.previous_type() {
+			events.push(Event::UnitTypeChanged(tag, last_type, u.type_id()));
+		}
+		let damage = u.damage_taken();
+		if damage > 0 {
+			events.push(Event::UnitTookDamage(tag, damage));
+		}
+		events.extend(u.buffs_gained().into_iter().map(|b| Event::BuffGained(tag, b)));
+		events.extend(u.buffs_lost().into_iter().map(|b| Event::BuffLost(tag, b)));
+	}
+
+	// Enemies in vision
+	let mut visible_enemies = FxHashSet::default();
+	for u in &bot.units.enemy.all {
+		let tag = u.tag();
+		if bot.seen_enemies.insert(tag) {
+			events.push(Event::EnemyUnitFirstSeen(tag));
+		}
+		if u.is_visible() {
+			if !bot.visible_enemies.contains(&tag) {
+				events.push(Event::UnitEnteredVision(tag));
+			}
+			visible_enemies.insert(tag);
+		}
+	}
+	let dead_units = &bot.state.observation.raw.dead_units;
+	for tag in &bot.visible_enemies {
+		if !(visible_enemies.contains(tag) || dead_units.contains(tag)) {
+			events.push(Event::UnitLeftVision(*tag));
+		}
+	}
+	for tag in dead_units {
+		bot.seen_enemies.remove(tag);
+	}
+	bot.visible_enemies = visible_enemies;
+
+	// Expansions
+	for (exp, last) in bot.expansions.iter().zip(last_expansions) {
+		if exp.alliance != *last {
+			if !last.is_neutral() {
+				events.push(Event::ExpansionLost(exp.loc, *last));
+			}
+			if !exp.alliance.is_neutral() {
+				events.push(Event::ExpansionTaken(exp.loc, exp.alliance));
+			}
+		}
+	}
+
+	// Alerts
+	events.extend(
+		bot.state
+			.observation
+			.alerts
+			.iter()
+			.map(|a| Event::AlertReceived(*a)),
+	);
+
+	events
+}
+
 /// Messege in game chat.
 #[derive(Clone)]
 pub struct ChatMessage {
@@ -460,7 +614,7 @@ pub struct Common {
 /// All alerts stored in [`state.observation.alerts`](Observation::alerts).
 #[allow(missing_docs)]
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Alert {
 	AlertError,
 	AddOnComplete,
@@ -519,3 +673,162 @@ pub struct AvailableAbility {
 	pub id: AbilityId,
 	pub requires_point: bool,
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		action::{ActionResult, Target},
+		bot::Expansion,
+		unit::tests::{from_proto, proto, remapped_ability},
+	};
+
+	fn enemy(tag: u64, visible: bool) -> Unit {
+		let mut u = proto(tag, UnitTypeId::Zergling, (10.0, 10.0));
+		u.set_alliance(ProtoAlliance::Enemy);
+		if !visible {
+			u.set_display_type(sc2_proto::raw::DisplayType::Snapshot);
+		}
+		from_proto(&u)
+	}
+	fn expansion(x: f32, alliance: Alliance) -> Expansion {
+		let loc = Point2::new(x, 10.5);
+		Expansion {
+			loc,
+			center: loc,
+			minerals: Default::default(),
+			geysers: Default::default(),
+			alliance,
+			base: None,
+		}
+	}
+	fn command(ability: AbilityId, units: Vec<u64>) -> CommandResult {
+		CommandResult {
+			ability,
+			target: Target::Tag(10),
+			units,
+			queue: false,
+			result: ActionResult::Success,
+			errors: vec![],
+		}
+	}
+	fn error(unit: u64, ability: AbilityId) -> ActionError {
+		ActionError {
+			unit,
+			ability,
+			result: ActionResult::CantTargetThatUnit,
+		}
+	}
+
+	#[test]
+	fn errors_attributed_by_ability() {
+		let mut game_data = GameData::default();
+		game_data.abilities.insert(
+			AbilityId::AttackAttack,
+			remapped_ability(AbilityId::AttackAttack, AbilityId::Attack),
+		);
+		let mut commands = vec![
+			command(AbilityId::Smart, vec![1, 2]),
+			command(AbilityId::Attack, vec![1]),
+			command(AbilityId::AttackAttack, vec![2]),
+		];
+		let errors = [
+			error(1, AbilityId::Smart),
+			error(1, AbilityId::AttackAttack),
+			error(2, AbilityId::AttackAttack),
+		];
+
+		attribute_errors(&mut commands, &errors, &game_data);
+		let result = ActionResult::CantTargetThatUnit;
+		assert_eq!(commands[0].errors, vec![(1, result)]);
+		assert_eq!(commands[1].errors, vec![(1, result)]);
+		assert_eq!(commands[2].errors, vec![(2, result)]);
+	}
+
+	#[test]
+	fn errors_of_other_abilities_unattributed() {
+		let mut commands = vec![command(AbilityId::Attack, vec![1])];
+		let errors = [error(1, AbilityId::MoveMove), error(2, AbilityId::Attack)];
+
+		attribute_errors(&mut commands, &errors, &GameData::default());
+		assert!(commands[0].errors.is_empty());
+		assert!(commands[0].is_success());
+	}
+
+	#[test]
+	fn upgrade_completed() {
+		let mut bot = Bot::default();
+		let last_upgrades = [UpgradeId::Stimpack].into_iter().collect();
+		*bot.state.observation.raw.upgrades.write_lock() =
+			[UpgradeId::Stimpack, UpgradeId::ShieldWall].into_iter().collect();
+
+		let events = state_events(&mut bot, &last_upgrades, &[]);
+		assert_eq!(events.len(), 1);
+		assert!(matches!(
+			events[0],
+			Event::UpgradeCompleted(UpgradeId::ShieldWall)
+		));
+	}
+
+	#[test]
+	fn enemies_enter_and_leave_vision() {
+		let mut bot = Bot::default();
+		bot.units.enemy.all = [enemy(1, true), enemy(2, false)].into_iter().collect();
+
+		let events = state_events(&mut bot, &FxHashSet::default(), &[]);
+		assert_eq!(events.len(), 3);
+		assert!(matches!(events[0], Event::EnemyUnitFirstSeen(1)));
+		assert!(matches!(events[1], Event::UnitEnteredVision(1)));
+		assert!(matches!(events[2], Event::EnemyUnitFirstSeen(2)));
+		assert!(state_events(&mut bot, &FxHashSet::default(), &[]).is_empty());
+
+		bot.units.enemy.all = [enemy(1, false), enemy(2, true)].into_iter().collect();
+		let events = state_events(&mut bot, &FxHashSet::default(), &[]);
+		assert_eq!(events.len(), 2);
+		assert!(matches!(events[0], Event::UnitEnteredVision(2)));
+		assert!(matches!(events[1], Event::UnitLeftVision(1)));
+	}
+
+	#[test]
+	fn dead_enemies_dont_leave_vision() {
+		let mut bot = Bot::default();
+		bot.units.enemy.all = [enemy(1, true)].into_iter().collect();
+		state_events(&mut bot, &FxHashSet::default(), &[]);
+
+		bot.units.enemy.all = Units::new();
+		bot.state.observation.raw.dead_units = vec![1];
+		assert!(state_events(&mut bot, &FxHashSet::default(), &[]).is_empty());
+		assert!(bot.seen_enemies.is_empty());
+		assert!(bot.visible_enemies.is_empty());
+	}
+
+	#[test]
+	fn expansions_taken_and_lost() {
+		let mut bot = Bot::default();
+		bot.expansions = vec![
+			expansion(10.5, Alliance::Own),
+			expansion(20.5, Alliance::Enemy),
+			expansion(30.5, Alliance::Neutral),
+		];
+		let last = [Alliance::Neutral, Alliance::Own, Alliance::Neutral];
+
+		let events = state_events(&mut bot, &FxHashSet::default(), &last);
+		assert_eq!(events.len(), 3);
+		assert!(matches!(events[0], Event::ExpansionTaken(loc, Alliance::Own) if loc.x == 10.5));
+		assert!(matches!(events[1], Event::ExpansionLost(loc, Alliance::Own) if loc.x == 20.5));
+		assert!(matches!(events[2], Event::ExpansionTaken(loc, Alliance::Enemy) if loc.x == 20.5));
+	}
+
+	#[test]
+	fn alerts_received() {
+		let mut bot = Bot::default();
+		bot.state.observation.alerts = vec![Alert::NuclearLaunchDetected];
+
+		let events = state_events(&mut bot, &FxHashSet::default(), &[]);
+		assert_eq!(events.len(), 1);
+		assert!(matches!(
+			events[0],
+			Event::AlertReceived(Alert::NuclearLaunchDetected)
+		));
+	}
+}
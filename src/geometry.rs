@@ -38,6 +38,10 @@ impl Rect {
 	pub fn new(x0: usize, y0: usize, x1: usize, y1: usize) -> Self {
 		Self { x0, y0, x1, y1 }
 	}
+	/// Checks if given point is inside of the rectangle.
+	pub fn contains(&self, pos: Point2) -> bool {
+		pos.x >= self.x0 as f32 && pos.x < self.x1 as f32 && pos.y >= self.y0 as f32 && pos.y < self.y1 as f32
+	}
 }
 
 /// Point on 2D grid, the most frequently used geometric primitive.
@@ -607,4 +611,3 @@ impl IntoProto<Point> for Point3 {
 impl Radius for Point2 {}
 impl Radius for &Point2 {}
 impl Radius for Point3 {}
-impl Radius for &Point3 {}
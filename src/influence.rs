@@ -0,0 +1,187 @@
+//! Influence maps of danger on the map.
+//!
+//! Every tile of [`InfluenceMap`] holds summed dps, which ground or air unit
+//! standing there can take, so micro can look for the safest positions.
+
+use crate::{bot::Bot, distance::*, geometry::Point2, pixel_map::for_circle, unit::Unit, units::Units};
+use ndarray::Array2;
+use std::f32::consts::PI;
+
+/// Grids of danger for ground and air units.
+#[derive(Default, Clone)]
+pub struct InfluenceMap {
+	ground: Array2<f32>,
+	air: Array2<f32>,
+}
+
+impl InfluenceMap {
+	/// Constructs empty map with size of the current map.
+	pub fn new(bot: &Bot) -> Self {
+		let dim = bot.game_info.pathing_grid.dim();
+		Self {
+			ground: Array2::from_elem(dim, 0.0),
+			air: Array2::from_elem(dim, 0.0),
+		}
+	}
+	/// Constructs map with all visible enemy units added with given margin.
+	pub fn from_enemies(bot: &Bot, margin: f32) -> Self {
+		let mut map = Self::new(bot);
+		map.add_units(&bot.units.enemy.all, margin);
+		map
+	}
+
+	/// Adds dps of given unit in its range extended by unit's radius and given margin.
+	pub fn add_unit(&mut self, u: &Unit, margin: f32) {
+		let pos = u.position();
+		let dps = u.ground_dps();
+		if dps > 0.0 {
+			let range = u.real_ground_range() + u.radius() + margin;
+			for_circle(&mut self.ground, pos, range, |cell| *cell += dps);
+		}
+		let dps = u.air_dps();
+		if dps > 0.0 {
+			let range = u.real_air_range() + u.radius() + margin;
+			for_circle(&mut self.air, pos, range, |cell| *cell += dps);
+		}
+	}
+	/// Adds dps of all given units. See [`add_unit`](Self::add_unit).
+	pub fn add_units(&mut self, units: &Units, margin: f32) {
+		for u in units {
+			self.add_unit(u, margin);
+		}
+	}
+	/// Adds given value in circle for ground and/or air units.
+	pub fn add_circle(&mut self, center: Point2, radius: f32, value: f32, ground: bool, air: bool) {
+		if ground {
+			for_circle(&mut self.ground, center, radius, |cell| *cell += value);
+		}
+		if air {
+			for_circle(&mut self.air, center, radius, |cell| *cell += value);
+		}
+	}
+
+	/// Returns danger for ground units at given position.
+	pub fn ground<P: Into<(usize, usize)>>(&self, pos: P) -> f32 {
+		self.ground.get(pos.into()).copied().unwrap_or(0.0)
+	}
+	/// Returns danger for air units at given position.
+	pub fn air<P: Into<(usize, usize)>>(&self, pos: P) -> f32 {
+		self.air.get(pos.into()).copied().unwrap_or(0.0)
+	}
+	/// Returns danger for given unit at given position.
+	pub fn value_for<P: Into<(usize, usize)>>(&self, unit: &Unit, pos: P) -> f32 {
+		if unit.is_flying() {
+			self.air(pos)
+		} else {
+			self.ground(pos)
+		}
+	}
+	/// Checks if there's no danger for given unit at given position.
+	pub fn is_safe_for<P: Into<(usize, usize)>>(&self, unit: &Unit, pos: P) -> bool {
+		self.value_for(unit, pos) <= 0.0
+	}
+	/// Returns the safest position for given unit on circle with given radius around it.
+	///
+	/// Positions outside of playable area and unreachable by ground units are skipped.
+	/// Among equally dangerous positions the closest to `towards` is chosen.
+	pub fn safest_around(&self, bot: &Bot, unit: &Unit, radius: f32, towards: Point2) -> Option<Point2> {
+		let pos = unit.position();
+		(0..16)
+			.map(|i| pos.towards_angle(i as f32 * PI / 8.0, radius))
+			.filter(|p| bot.game_info.playable_area.contains(*p))
+			.filter(|p| unit.is_flying() || bot.is_pathable(*p))
+			.map(|p| (p, self.value_for(unit, p), p.distance_squared(towards)))
+			.min_by(|(_, v1, d1), (_, v2, d2)| cmp(v1, v2).then(cmp(d1, d2)))
+			.map(|(p, _, _)| p)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		geometry::Rect,
+		ids::UnitTypeId,
+		pixel_map::Pixel,
+		unit::tests::{combat_data, proto, with_data},
+	};
+
+	fn bot() -> Bot {
+		let mut bot = Bot::default();
+		bot.game_info.pathing_grid = Array2::from_elem((32, 32), Pixel::Empty);
+		bot.game_info.playable_area = Rect::new(0, 0, 32, 32);
+		bot
+	}
+
+	#[test]
+	fn units_add_dps_in_range() {
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (10.5, 10.5)));
+		let zergling = with_data(&data, &proto(2, UnitTypeId::Zergling, (20.5, 20.5)));
+		let mut map = InfluenceMap::new(&bot());
+		map.add_units(&[marine.clone(), zergling].into_iter().collect(), 1.0);
+
+		// Marine range is 5 and radius is 0.5
+		assert_eq!(map.ground((10, 10)), 10.0);
+		assert_eq!(map.ground((16, 10)), 10.0);
+		assert_eq!(map.ground((17, 10)), 0.0);
+		assert_eq!(map.air((16, 10)), 10.0);
+		// Zergling can't attack air
+		assert_eq!(map.ground((20, 20)), 10.0);
+		assert_eq!(map.air((20, 20)), 0.0);
+		assert_eq!(map.ground((100, 100)), 0.0);
+
+		assert!(!map.is_safe_for(&marine, (10, 10)));
+		assert!(map.is_safe_for(&marine, (25, 10)));
+	}
+
+	#[test]
+	fn circles_for_ground_and_air() {
+		let mut map = InfluenceMap::new(&bot());
+		map.add_circle(Point2::new(5.0, 5.0), 2.0, 3.0, true, false);
+		map.add_circle(Point2::new(5.0, 5.0), 1.0, 2.0, true, true);
+		assert_eq!(map.ground((4, 4)), 5.0);
+		assert_eq!(map.ground((6, 5)), 3.0);
+		assert_eq!(map.air((4, 4)), 2.0);
+		assert_eq!(map.air((6, 5)), 0.0);
+	}
+
+	#[test]
+	fn safest_position_around() {
+		let bot = bot();
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (10.0, 10.0)));
+		let mut map = InfluenceMap::new(&bot);
+
+		// The closest to given position if there's no danger
+		let target = Point2::new(12.0, 10.0);
+		let spot = map.safest_around(&bot, &marine, 2.0, target).unwrap();
+		assert!(spot.is_closer(0.01, target));
+
+		map.add_circle(Point2::new(12.0, 10.0), 3.0, 10.0, true, false);
+		let spot = map.safest_around(&bot, &marine, 2.0, target).unwrap();
+		assert!(map.is_safe_for(&marine, spot));
+		assert!(spot.x < 10.0);
+	}
+
+	#[test]
+	fn safest_position_inside_playable_area() {
+		let mut bot = bot();
+		bot.game_info.playable_area = Rect::new(2, 2, 30, 30);
+		let data = combat_data();
+		let map = InfluenceMap::new(&bot);
+
+		// Pathing grid ends at 32, but playable area at 30
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (29.0, 10.0)));
+		let spot = map
+			.safest_around(&bot, &marine, 2.0, Point2::new(40.0, 10.0))
+			.unwrap();
+		assert!(spot.x < 30.0);
+
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (3.0, 3.0)));
+		let spot = map
+			.safest_around(&bot, &marine, 2.0, Point2::new(0.0, 0.0))
+			.unwrap();
+		assert!(spot.x >= 2.0 && spot.y >= 2.0);
+	}
+}
@@ -0,0 +1,188 @@
+//! Kiting and stutter-step micro for ranged units.
+//!
+//! Every step unit either shoots when its weapon is ready, steps back from threats
+//! while weapon is on cooldown, or chases targets out of its range:
+//! ```
+//! let influence = InfluenceMap::from_enemies(self, 1.0);
+//! let targets = &self.units.enemy.all;
+//! for u in &self.units.my.units.of_type(UnitTypeId::Stalker) {
+//!     kite(self, u, targets, &influence, KiteOptions::default());
+//! }
+//! ```
+//!
+//! Decisions take current [`game_step`](crate::bot::Bot::game_step) into account:
+//! unit shoots if its weapon will be ready before the next step,
+//! and steps back only if it can return before the weapon is ready.
+
+use crate::{
+	action::Target, bot::Bot, distance::*, geometry::Point2, influence::InfluenceMap, unit::Unit,
+	units::Units,
+};
+
+/// Decision made by [`kite_action`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KiteAction {
+	/// Attack target with given tag.
+	Attack(u64),
+	/// Move away from threats to given position.
+	StepBack(Point2),
+	/// Move towards target out of range.
+	Chase(Point2),
+	/// Nothing to do: weapon is on cooldown and there're no threats.
+	Wait,
+}
+
+/// Additional options for [`kite_action`].
+#[derive(Debug, Clone, Copy)]
+pub struct KiteOptions {
+	/// Distance to threats' ranges considered dangerous. [Default: `0.5`]
+	pub margin: f32,
+	/// Chase targets out of range. [Default: `true`]
+	pub chase: bool,
+}
+impl Default for KiteOptions {
+	fn default() -> Self {
+		Self {
+			margin: 0.5,
+			chase: true,
+		}
+	}
+}
+
+/// Decides what given unit should do this step against given targets.
+///
+/// Target to shoot is chosen with [`Units::best_target`], and position to step back
+/// is the safest one on given influence map.
+pub fn kite_action(
+	bot: &Bot,
+	unit: &Unit,
+	targets: &Units,
+	influence: &InfluenceMap,
+	options: KiteOptions,
+) -> KiteAction {
+	let step = bot.game_step() as f32;
+	let cooldown = unit.weapon_cooldown().unwrap_or(0.0);
+
+	// Weapon will be ready before the next step
+	if cooldown <= step {
+		if let Some(target) = targets.best_target(unit, 0.0) {
+			return KiteAction::Attack(target.tag());
+		}
+	}
+
+	let threats = targets
+		.iter()
+		.filter(|t| unit.in_real_range_of(t, t.distance_per_step() + options.margin))
+		.collect::<Vec<_>>();
+
+	// Stepping back only if there's enough time to step away and come back before weapon is ready
+	if !threats.is_empty() && unit.distance_to_weapon_ready() > 2.0 * unit.distance_per_step() {
+		let closest = threats.iter().copied().closest(unit).map(|t| t.position());
+		if let Some(closest) = closest {
+			let distance = unit.distance_per_step().max(1.0);
+			let away = unit.position().towards(closest, -distance * 2.0);
+			if let Some(pos) = influence.safest_around(bot, unit, distance, away) {
+				return KiteAction::StepBack(pos);
+			}
+		}
+	}
+
+	if options.chase && targets.best_target(unit, 0.0).is_none() {
+		if let Some(target) = targets.iter().filter(|t| t.can_be_attacked()).closest(unit) {
+			if unit.real_range_vs(target) > 0.0 {
+				return KiteAction::Chase(target.position());
+			}
+		}
+	}
+
+	KiteAction::Wait
+}
+
+/// Decides what given unit should do with [`kite_action`] and gives it the order.
+///
+/// Returns made decision.
+pub fn kite(
+	bot: &Bot,
+	unit: &Unit,
+	targets: &Units,
+	influence: &InfluenceMap,
+	options: KiteOptions,
+) -> KiteAction {
+	let action = kite_action(bot, unit, targets, influence, options);
+	match action {
+		KiteAction::Attack(tag) => unit.attack(Target::Tag(tag), false),
+		KiteAction::StepBack(pos) | KiteAction::Chase(pos) => unit.move_to(Target::Pos(pos), false),
+		KiteAction::Wait => {}
+	}
+	action
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::LockOwned,
+		geometry::Rect,
+		ids::UnitTypeId,
+		unit::tests::{combat_data, proto, with_data},
+	};
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+
+	/// Marine with given weapon cooldown in game loops and zergling at given position, game step is 2.
+	fn setup(cooldown: f32, zergling: (f32, f32)) -> (Bot, Unit, Units) {
+		let mut bot = Bot::default();
+		bot.game_info.playable_area = Rect::new(0, 0, 32, 32);
+		bot.set_game_step(2);
+		let data = combat_data();
+		data.game_step.set_locked(2);
+
+		let mut marine = proto(1, UnitTypeId::Marine, (10.0, 10.0));
+		marine.set_weapon_cooldown(cooldown);
+		let mut enemy = proto(2, UnitTypeId::Zergling, zergling);
+		enemy.set_alliance(ProtoAlliance::Enemy);
+		let targets = std::iter::once(with_data(&data, &enemy)).collect();
+		(bot, with_data(&data, &marine), targets)
+	}
+
+	#[test]
+	fn attack_when_weapon_ready() {
+		let (bot, marine, targets) = setup(2.0, (11.5, 10.0));
+		let influence = InfluenceMap::default();
+		let action = kite_action(&bot, &marine, &targets, &influence, KiteOptions::default());
+		assert_eq!(action, KiteAction::Attack(2));
+	}
+
+	#[test]
+	fn step_back_on_cooldown() {
+		let (bot, marine, targets) = setup(10.0, (11.5, 10.0));
+		let influence = InfluenceMap::default();
+		match kite_action(&bot, &marine, &targets, &influence, KiteOptions::default()) {
+			KiteAction::StepBack(pos) => assert!(pos.is_closer(0.01, Point2::new(9.0, 10.0))),
+			action => panic!("unexpected {:?}", action),
+		}
+	}
+
+	#[test]
+	fn no_step_back_without_time_to_return() {
+		// Weapon is ready in 3 loops, so marine can step away, but can't come back in time
+		let (bot, marine, targets) = setup(3.0, (11.5, 10.0));
+		let influence = InfluenceMap::default();
+		let action = kite_action(&bot, &marine, &targets, &influence, KiteOptions::default());
+		assert_eq!(action, KiteAction::Wait);
+	}
+
+	#[test]
+	fn chase_targets_out_of_range() {
+		let (bot, marine, targets) = setup(0.0, (30.0, 10.0));
+		let influence = InfluenceMap::default();
+		let action = kite_action(&bot, &marine, &targets, &influence, KiteOptions::default());
+		assert_eq!(action, KiteAction::Chase(Point2::new(30.0, 10.0)));
+
+		let options = KiteOptions {
+			chase: false,
+			..Default::default()
+		};
+		let action = kite_action(&bot, &marine, &targets, &influence, options);
+		assert_eq!(action, KiteAction::Wait);
+	}
+}
@@ -0,0 +1,422 @@
+//! Base layout planner.
+//!
+//! Precomputes slots for buildings at every expansion and wall at the main ramp,
+//! so bot can ask for next free slot without blocking it's own walkways.
+//!
+//! Layout is computed on game start and can be accessed through
+//! [`layout`](crate::bot::Bot::layout) field.
+
+use crate::{
+	bot::{Bot, Expansion},
+	distance::*,
+	geometry::Point2,
+	pixel_map::{corner, fill_circle, fill_footprint},
+	placement::PlacementGrid,
+	ramp::Ramp,
+};
+use ndarray::Array2;
+
+/// Kind of slot in the base layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlotKind {
+	/// 3x3 production building with 2x2 space for addon on the right side.
+	Production,
+	/// 2x2 supply depot or pylon.
+	Supply,
+	/// 2x2 turret or cannon in the mineral line.
+	Defense,
+}
+
+/// Planned slots for buildings at one expansion.
+#[derive(Debug, Default, Clone)]
+pub struct BaseLayout {
+	/// Location of the expansion (townhall position).
+	pub loc: Point2,
+	/// Positions of production buildings, sorted by distance to townhall.
+	pub production: Vec<Point2>,
+	/// Positions of supply depots or pylons, sorted by distance to townhall.
+	pub supply: Vec<Point2>,
+	/// Positions of turrets or cannons in the mineral line.
+	pub defense: Vec<Point2>,
+}
+impl BaseLayout {
+	/// Returns planned positions of given slot kind.
+	pub fn slots(&self, kind: SlotKind) -> &[Point2] {
+		match kind {
+			SlotKind::Production => &self.production,
+			SlotKind::Supply => &self.supply,
+			SlotKind::Defense => &self.defense,
+		}
+	}
+}
+
+/// Planned wall at the main ramp.
+#[derive(Debug, Default, Clone)]
+pub struct WallLayout {
+	/// Terran corner depots, from [`Ramp::corner_depots`].
+	pub depots: Vec<Point2>,
+	/// Terran barracks with space for addon, from [`Ramp::barracks_correct_placement`].
+	pub barracks: Option<Point2>,
+	/// Protoss pylon, from [`Ramp::protoss_wall_pylon`].
+	pub pylon: Option<Point2>,
+	/// Protoss 3x3 buildings, from [`Ramp::protoss_wall_buildings`].
+	pub buildings: Vec<Point2>,
+}
+impl WallLayout {
+	fn new(ramp: &Ramp) -> Self {
+		Self {
+			depots: ramp
+				.corner_depots()
+				.map_or_else(Vec::new, |depots| depots.to_vec()),
+			barracks: ramp.barracks_correct_placement(),
+			pylon: ramp.protoss_wall_pylon(),
+			buildings: ramp
+				.protoss_wall_buildings()
+				.map_or_else(Vec::new, |buildings| buildings.to_vec()),
+		}
+	}
+}
+
+/// Slots for buildings planned at every expansion and wall at the main ramp.
+#[derive(Debug, Default, Clone)]
+pub struct Layout {
+	/// Layouts of all expansions in the same order as [`expansions`](Bot::expansions).
+	pub bases: Vec<BaseLayout>,
+	/// Wall at the main ramp.
+	pub wall: WallLayout,
+}
+
+const MAIN_RADIUS: f32 = 20.0;
+const BASE_RADIUS: f32 = 12.0;
+
+impl Layout {
+	pub(crate) fn new(bot: &Bot) -> Self {
+		let grid = &bot.placement;
+		let mut reserved = Array2::from_elem(bot.game_info.placement_grid.dim(), false);
+
+		// Keeping wall and the way from main base to ramp free
+		let wall = WallLayout::new(&bot.ramps.my);
+		for depot in &wall.depots {
+			fill_footprint(&mut reserved, *depot, 2, 2, 0);
+		}
+		if let Some(barracks) = wall.barracks {
+			fill_footprint(&mut reserved, barracks.offset(1.0, 0.0), 5, 3, 0);
+		}
+		if let Some(pylon) = wall.pylon {
+			fill_footprint(&mut reserved, pylon, 2, 2, 0);
+		}
+		for building in &wall.buildings {
+			fill_footprint(&mut reserved, *building, 3, 3, 0);
+		}
+		if let Some((x, y)) = bot.ramps.my.top_center() {
+			reserve_path(
+				&mut reserved,
+				bot.start_location,
+				Point2::new(x as f32 + 0.5, y as f32 + 0.5),
+				2.0,
+			);
+		}
+
+		// Keeping space around townhalls free
+		for exp in &bot.expansions {
+			fill_footprint(&mut reserved, exp.loc, 5, 5, 3);
+		}
+
+		let bases = bot
+			.expansions
+			.iter()
+			.map(|exp| {
+				let radius = if exp.loc.is_closer(1.0, bot.start_location) {
+					MAIN_RADIUS
+				} else {
+					BASE_RADIUS
+				};
+				plan_base(bot, grid, &mut reserved, exp, radius)
+			})
+			.collect();
+
+		Self { bases, wall }
+	}
+
+	/// Returns layout of expansion with given location.
+	pub fn base(&self, loc: Point2) -> Option<&BaseLayout> {
+		self.bases.iter().find(|base| base.loc.is_closer(1.0, loc))
+	}
+	/// Returns free slots of given kind at expansion with given location.
+	///
+	/// Slots are checked with given [`PlacementGrid`], so slots taken by any structures are skipped.
+	pub fn free_slots<'a>(
+		&'a self,
+		grid: &'a PlacementGrid,
+		kind: SlotKind,
+		loc: Point2,
+	) -> impl Iterator<Item = Point2> + 'a {
+		self.base(loc)
+			.map_or(&[][..], |base| base.slots(kind))
+			.iter()
+			.copied()
+			.filter(move |pos| is_slot_free(grid, kind, *pos))
+	}
+	/// Returns first free slot of given kind at expansion with given location.
+	pub fn next_slot(&self, grid: &PlacementGrid, kind: SlotKind, loc: Point2) -> Option<Point2> {
+		self.free_slots(grid, kind, loc).next()
+	}
+}
+
+fn is_slot_free(grid: &PlacementGrid, kind: SlotKind, pos: Point2) -> bool {
+	match kind {
+		SlotKind::Production => grid.is_free_area(pos, 3) && grid.is_free_area(pos.offset(2.5, -0.5), 2),
+		SlotKind::Supply | SlotKind::Defense => grid.is_free_area(pos, 2),
+	}
+}
+
+fn plan_base(
+	bot: &Bot,
+	grid: &PlacementGrid,
+	reserved: &mut Array2<bool>,
+	exp: &Expansion,
+	radius: f32,
+) -> BaseLayout {
+	let loc = exp.loc;
+	let level = bot.get_height(loc);
+	let r = radius as isize;
+
+	let fits = |reserved: &Array2<bool>, pos: Point2, width: usize, height: usize| {
+		let (x0, y0) = corner(pos, width, height);
+		(x0..x0 + width as isize).all(|x| {
+			(y0..y0 + height as isize).all(|y| {
+				let cell = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+				!is_reserved(reserved, x, y) && cell.is_closer(radius, loc) && bot.get_height(cell) == level
+			})
+		})
+	};
+
+	// Production buildings with addons placed in columns with walkways between them
+	let mut production = vec![];
+	let mut candidates = lattice(loc.floor() + 0.5, r, 7, 4);
+	sort_by_distance(&mut candidates, loc);
+	for pos in candidates {
+		let block = pos.offset(1.0, 0.0);
+		if fits(reserved, block, 5, 3)
+			&& grid.is_free_area(pos, 3)
+			&& grid.is_free_area(pos.offset(2.5, -0.5), 2)
+			&& !grid.is_mineral_line(pos, 3)
+			&& !grid.is_mineral_line(pos.offset(2.5, -0.5), 2)
+		{
+			fill_footprint(reserved, block, 5, 3, 1);
+			production.push(pos);
+		}
+	}
+
+	// Supplies placed in pairs
+	let mut supply = vec![];
+	let mut candidates = lattice(loc.round(), r, 5, 3);
+	sort_by_distance(&mut candidates, loc);
+	for pos in candidates {
+		let block = pos.offset(1.0, 0.0);
+		if fits(reserved, block, 4, 2) {
+			let pair = [pos, pos.offset(2.0, 0.0)];
+			if pair
+				.iter()
+				.all(|p| grid.is_free_area(*p, 2) && !grid.is_mineral_line(*p, 2))
+			{
+				fill_footprint(reserved, block, 4, 2, 1);
+				supply.extend(pair);
+			}
+		}
+	}
+
+	// Defense placed in the middle of mineral line
+	let mut defense = vec![];
+	let minerals = exp
+		.minerals
+		.iter()
+		.filter_map(|tag| bot.units.mineral_fields.get(*tag))
+		.map(|u| u.position())
+		.collect::<Vec<_>>();
+	if !minerals.is_empty() {
+		let center = minerals.iter().fold(Point2::default(), |sum, p| sum + *p) / minerals.len() as f32;
+		let direction = (center - loc).normalize();
+		let side = direction.rotate90(true);
+		let middle = loc + direction * 4.5;
+
+		if let Some(pos) = [0.0, 1.0, -1.0, 2.0, -2.0]
+			.iter()
+			.map(|offset| (middle + side * *offset).round())
+			.find(|pos| grid.is_free_area(*pos, 2) && !pos.is_closer(3.5, loc))
+		{
+			fill_footprint(reserved, pos, 2, 2, 0);
+			defense.push(pos);
+		}
+	}
+
+	BaseLayout {
+		loc,
+		production,
+		supply,
+		defense,
+	}
+}
+
+fn lattice(origin: Point2, radius: isize, step_x: usize, step_y: usize) -> Vec<Point2> {
+	let nx = radius / step_x as isize + 1;
+	let ny = radius / step_y as isize + 1;
+	let mut positions = vec![];
+	for i in -nx..=nx {
+		for j in -ny..=ny {
+			positions.push(origin.offset((i * step_x as isize) as f32, (j * step_y as isize) as f32));
+		}
+	}
+	positions
+}
+
+fn sort_by_distance(positions: &mut [Point2], target: Point2) {
+	positions.sort_unstable_by(|a, b| cmp(&a.distance_squared(target), &b.distance_squared(target)));
+}
+
+fn is_reserved(reserved: &Array2<bool>, x: isize, y: isize) -> bool {
+	x < 0 || y < 0 || reserved.get((x as usize, y as usize)).copied().unwrap_or(true)
+}
+
+fn reserve_path(reserved: &mut Array2<bool>, from: Point2, to: Point2, width: f32) {
+	let steps = (from.distance(to) * 2.0) as usize;
+	for i in 0..=steps {
+		let p = from + (to - from) * (i as f32 / steps.max(1) as f32);
+		fill_circle(reserved, p, width);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		game_state::Alliance, ids::UnitTypeId, pixel_map::Pixel, placement::tests::block, unit::tests::unit,
+	};
+
+	/// Bot on open flat map with one expansion and mineral line to the right of it.
+	fn bot_with_base() -> Bot {
+		let mut bot = Bot::default();
+		bot.game_info.placement_grid = Array2::from_elem((64, 64), Pixel::Empty);
+
+		let loc = Point2::new(32.5, 32.5);
+		let mut minerals = vec![];
+		for (i, y) in (29..36).enumerate() {
+			let tag = i as u64 + 1;
+			let x = if y == 29 || y == 35 { 39.0 } else { 40.0 };
+			let mineral = unit(tag, UnitTypeId::MineralField, (x, y as f32 + 0.5));
+			bot.units.all.push(mineral.clone());
+			bot.units.mineral_fields.push(mineral.clone());
+			bot.units.resources.push(mineral);
+			minerals.push(tag);
+		}
+		bot.start_location = loc;
+		bot.expansions.push(Expansion {
+			loc,
+			center: Point2::new(39.5, 32.5),
+			minerals: minerals.into_iter().collect(),
+			geysers: Default::default(),
+			alliance: Alliance::Neutral,
+			base: None,
+		});
+		bot.placement = PlacementGrid::new(&bot);
+		bot
+	}
+
+	#[test]
+	fn lattice_covers_radius() {
+		let origin = Point2::new(10.5, 10.5);
+		let positions = lattice(origin, 7, 7, 3);
+		assert_eq!(positions.len(), 5 * 7);
+		assert!(positions.contains(&origin));
+		assert!(positions.contains(&origin.offset(-14.0, 9.0)));
+	}
+
+	#[test]
+	fn sort_positions_by_distance() {
+		let target = Point2::new(0.0, 0.0);
+		let mut positions = vec![
+			Point2::new(5.0, 0.0),
+			Point2::new(1.0, 1.0),
+			Point2::new(0.0, 3.0),
+		];
+		sort_by_distance(&mut positions, target);
+		assert_eq!(
+			positions,
+			vec![
+				Point2::new(1.0, 1.0),
+				Point2::new(0.0, 3.0),
+				Point2::new(5.0, 0.0)
+			]
+		);
+	}
+
+	#[test]
+	fn reserve_path_between_points() {
+		let mut reserved = Array2::from_elem((16, 16), false);
+		reserve_path(&mut reserved, Point2::new(2.0, 2.0), Point2::new(12.0, 2.0), 1.0);
+		assert!((2..12).all(|x| reserved[(x, 1)] && reserved[(x, 2)]));
+		assert!(!reserved[(7, 4)]);
+		assert!(is_reserved(&reserved, 5, 2));
+		assert!(!is_reserved(&reserved, 5, 8));
+		assert!(is_reserved(&reserved, -1, 8));
+		assert!(is_reserved(&reserved, 16, 8));
+	}
+
+	#[test]
+	fn slots_avoid_mineral_line_and_townhall() {
+		let bot = bot_with_base();
+		let layout = Layout::new(&bot);
+		let base = layout.base(bot.start_location).unwrap();
+		assert!(!base.production.is_empty());
+		assert!(!base.supply.is_empty());
+
+		let grid = &bot.placement;
+		let townhall = bot.start_location;
+		for pos in &base.production {
+			assert!(!grid.is_mineral_line(*pos, 3));
+			assert!(!grid.is_mineral_line(pos.offset(2.5, -0.5), 2));
+			assert!((pos.x - townhall.x).abs() >= 4.0 || (pos.y - townhall.y).abs() >= 4.0);
+		}
+		for pos in &base.supply {
+			assert!(!grid.is_mineral_line(*pos, 2));
+			assert!((pos.x - townhall.x).abs() >= 3.5 || (pos.y - townhall.y).abs() >= 3.5);
+		}
+	}
+
+	#[test]
+	fn defense_slot_is_placeable_in_mineral_line() {
+		let mut bot = bot_with_base();
+		bot.layout = Layout::new(&bot);
+		let defense = bot.layout.base(bot.start_location).unwrap().defense.clone();
+		assert_eq!(defense.len(), 1);
+		let pos = defense[0];
+		assert!(pos.x > bot.start_location.x + 3.0);
+		assert!(bot.placement.is_mineral_line(pos, 2));
+
+		let grid = PlacementGrid::new(&bot);
+		assert!(!grid.is_mineral_line(pos, 2));
+		assert!(grid.is_mineral_line(pos.offset(0.0, 2.0), 2));
+		assert!(grid.is_free_area(pos, 2));
+	}
+
+	#[test]
+	fn next_slot_skips_occupied() {
+		let bot = bot_with_base();
+		let layout = Layout::new(&bot);
+		let loc = bot.start_location;
+		let supply = layout.base(loc).unwrap().supply.clone();
+		let mut grid = bot.placement.clone();
+		assert_eq!(layout.next_slot(&grid, SlotKind::Supply, loc), Some(supply[0]));
+
+		block(&mut grid, supply[0], 2, 2);
+		assert_eq!(layout.next_slot(&grid, SlotKind::Supply, loc), Some(supply[1]));
+		assert_eq!(
+			layout.free_slots(&grid, SlotKind::Supply, loc).count(),
+			supply.len() - 1
+		);
+		assert_eq!(
+			layout.next_slot(&grid, SlotKind::Supply, Point2::new(5.0, 5.0)),
+			None
+		);
+	}
+}
@@ -343,20 +343,25 @@ pub mod prelude {
 	#[cfg(feature = "rayon")]
 	pub use crate::units::rayon::ParUnitsIterator;
 	pub use crate::{
-		action::Target,
+		action::{CommandFilter, Target},
 		bot::PlacementOptions,
 		client::{
 			run_ladder_game, run_vs_computer, run_vs_human, LaunchOptions, RunnerMulti, RunnerSingle,
 			SC2Result,
 		},
 		consts::{ALL_PRODUCERS, PRODUCERS, RESEARCHERS, TECH_REQUIREMENTS},
+		creep::CreepSpreader,
 		distance::{Center, Distance, DistanceIterator, DistanceSlice},
+		formation::Formation,
 		game_state::Alliance,
 		geometry::Point2,
 		ids::*,
 		player::{AIBuild, Computer, Difficulty, GameResult, Race},
+		spells::{Spell, SpellCaster},
+		squads::Squads,
 		unit::Unit,
 		units::{iter::UnitsIterator, Units},
+		workers::WorkerManager,
 		Event, Player, PlayerSettings,
 	};
 	#[doc(no_inline)]
@@ -370,23 +375,44 @@ pub mod api;
 pub mod bot;
 pub mod client;
 pub mod consts;
+pub mod creep;
+pub mod dangers;
 pub mod debug;
 pub mod distance;
+pub mod formation;
 pub mod game_data;
 pub mod game_info;
 pub mod game_state;
 pub mod geometry;
 pub mod ids;
+pub mod influence;
+pub mod kiting;
+pub mod layout;
+pub mod map_analysis;
+pub mod memory;
 pub mod pixel_map;
+pub mod placement;
 pub mod player;
 pub mod ramp;
 pub mod score;
+pub mod scouting;
+pub mod spells;
+pub mod squads;
+pub mod tech_data;
+pub mod tech_tree;
 pub mod unit;
 pub mod units;
 pub mod utils;
-
-use game_state::Alliance;
+pub mod vision;
+pub mod wall;
+pub mod workers;
+
+use action::{ActionResult, Target};
+use game_state::{Alert, Alliance};
+use geometry::Point2;
+use ids::{AbilityId, BuffId, UnitTypeId, UpgradeId};
 use player::{GameResult, Race};
+use scouting::Strategy;
 
 /**
 `#[bot]` macro implements [`Deref`]`<Target = `[`Bot`]`>` and [`DerefMut`]`<Target = `[`Bot`]`>` for your struct.
@@ -696,6 +722,35 @@ pub enum Event {
 	ConstructionComplete(u64),
 	/// Detected actual race of random opponent.
 	RandomRaceDetected(Race),
+	/// Detected opponent's strategy by one of [`scouting`](crate::scouting) rules.
+	StrategyDetected(Strategy),
+	/// Research of upgrade completed (your only).
+	UpgradeCompleted(UpgradeId),
+	/// Unit changed its type, i.e. morphed, sieged, burrowed, etc. (your and visible enemies).
+	/// Contains previous and new type of the unit.
+	UnitTypeChanged(u64, UnitTypeId, UnitTypeId),
+	/// Enemy unit was seen for the first time.
+	EnemyUnitFirstSeen(u64),
+	/// Enemy unit entered your vision.
+	UnitEnteredVision(u64),
+	/// Enemy unit left your vision (i.e. it's now hidden in fog of war or cloaked).
+	UnitLeftVision(u64),
+	/// Unit took damage since previous step (your and visible enemies). Contains amount of damage.
+	UnitTookDamage(u64, u32),
+	/// Unit got a buff (your and visible enemies).
+	BuffGained(u64, BuffId),
+	/// Unit lost a buff (your and visible enemies).
+	BuffLost(u64, BuffId),
+	/// Expansion at given townhall location was taken by you or opponent.
+	ExpansionTaken(Point2, Alliance),
+	/// You or opponent lost expansion at given townhall location.
+	ExpansionLost(Point2, Alliance),
+	/// Alert received from the game.
+	AlertReceived(Alert),
+	/// Unit failed to execute command issued on previous step.
+	/// Contains tag of the unit, ability and target of the command and result of the failure.
+	/// See also [`state.command_results`](game_state::GameState::command_results).
+	CommandFailed(u64, AbilityId, Target, ActionResult),
 }
 
 /// Trait that bots must implement.
@@ -0,0 +1,514 @@
+//! Map terrain analysis: regions, chokes and connectivity graph.
+//!
+//! Pathable area of the map is decomposed into regions: wide areas on the same height level,
+//! separated by ramps and narrow passages (chokes).
+//!
+//! Analysis is done on game start and can be accessed through
+//! [`map_analysis`](crate::bot::Bot::map_analysis) field.
+
+use crate::{bot::Bot, distance::*, geometry::Point2};
+use ndarray::Array2;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+	cmp::{Ordering, Reverse},
+	collections::{BinaryHeap, VecDeque},
+};
+
+type Pos = (usize, usize);
+
+/// Areas with clearance less or equal to this are considered narrow.
+const NARROW_CLEARANCE: u32 = 3;
+/// Regions smaller than this number of tiles are merged into neighbours.
+const MIN_REGION_SIZE: usize = 64;
+
+/// Wide area of the map on the same height level.
+#[derive(Debug, Clone)]
+pub struct Region {
+	/// Index of the region in [`regions`](MapAnalysis::regions).
+	pub id: usize,
+	/// Number of tiles in the region.
+	pub size: usize,
+	/// The most open point of the region (i.e. furthest from obstacles).
+	pub center: Point2,
+	/// Terrain height of the region.
+	pub height: u8,
+	/// Indices of [`expansions`](Bot::expansions) in the region.
+	pub expansions: Vec<usize>,
+	/// Indices of [`chokes`](MapAnalysis::chokes) on the borders of the region.
+	pub chokes: Vec<usize>,
+	/// Indices of regions connected with this one.
+	pub neighbors: Vec<usize>,
+}
+
+/// Border between two regions.
+#[derive(Debug, Clone)]
+pub struct ChokePoint {
+	/// Index of the choke in [`chokes`](MapAnalysis::chokes).
+	pub id: usize,
+	/// Indices of regions connected by the choke.
+	pub regions: (usize, usize),
+	/// Tiles on the border between regions.
+	pub points: Vec<Pos>,
+	/// Center of the choke.
+	pub center: Point2,
+	/// Approximate width of the choke in tiles.
+	pub width: f32,
+	/// Index of ramp in [`ramps.all`](crate::ramp::Ramps::all) if choke is on the ramp.
+	pub ramp: Option<usize>,
+}
+impl ChokePoint {
+	/// Returns region on the other side of the choke.
+	pub fn other(&self, region: usize) -> usize {
+		if self.regions.0 == region {
+			self.regions.1
+		} else {
+			self.regions.0
+		}
+	}
+}
+
+/// Result of map terrain analysis.
+#[derive(Default, Clone)]
+pub struct MapAnalysis {
+	/// All regions on the map.
+	pub regions: Vec<Region>,
+	/// All chokes on the map.
+	pub chokes: Vec<ChokePoint>,
+	labels: Array2<Option<usize>>,
+	clearance: Array2<u32>,
+}
+
+impl MapAnalysis {
+	pub(crate) fn new(bot: &Bot) -> Self {
+		let area = bot.game_info.playable_area;
+		let dim = bot.game_info.pathing_grid.dim();
+		let in_area = |(x, y): Pos| x >= area.x0 && x < area.x1 && y >= area.y0 && y < area.y1;
+		let pathable = |p: Pos| in_area(p) && bot.is_pathable(p);
+		let neighbors4 = |(x, y): Pos| {
+			[
+				(x.wrapping_add(1), y),
+				(x.wrapping_sub(1), y),
+				(x, y.wrapping_add(1)),
+				(x, y.wrapping_sub(1)),
+			]
+		};
+
+		// Distance to the closest obstacle
+		let mut clearance = Array2::from_elem(dim, 0_u32);
+		let mut queue = VecDeque::new();
+		for x in 0..dim.0 {
+			for y in 0..dim.1 {
+				if pathable((x, y)) {
+					clearance[(x, y)] = u32::MAX;
+				} else {
+					queue.push_back((x, y));
+				}
+			}
+		}
+		while let Some((x, y)) = queue.pop_front() {
+			let d = clearance[(x, y)] + 1;
+			for dx in -1..=1_isize {
+				for dy in -1..=1_isize {
+					let n = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+					if let Some(c) = clearance.get_mut(n) {
+						if *c > d {
+							*c = d;
+							queue.push_back(n);
+						}
+					}
+				}
+			}
+		}
+
+		let ramp_points = bot
+			.ramps
+			.all
+			.iter()
+			.enumerate()
+			.flat_map(|(i, r)| r.points.iter().map(move |p| (*p, i)))
+			.collect::<FxHashMap<Pos, usize>>();
+
+		// Cores of regions: wide areas on the same height level
+		let mut labels = Array2::<Option<usize>>::from_elem(dim, None);
+		let mut cores = vec![];
+		for x in area.x0..area.x1 {
+			for y in area.y0..area.y1 {
+				let start = (x, y);
+				if labels[start].is_some()
+					|| clearance[start] <= NARROW_CLEARANCE
+					|| ramp_points.contains_key(&start)
+				{
+					continue;
+				}
+
+				let id = cores.len();
+				let height = bot.get_height(start);
+				let mut cells = vec![start];
+				labels[start] = Some(id);
+				let mut i = 0;
+				while i < cells.len() {
+					for n in neighbors4(cells[i]) {
+						if labels.get(n).is_some_and(|l| l.is_none())
+							&& clearance[n] > NARROW_CLEARANCE
+							&& !ramp_points.contains_key(&n)
+							&& bot.get_height(n) == height
+						{
+							labels[n] = Some(id);
+							cells.push(n);
+						}
+					}
+					i += 1;
+				}
+				cores.push(cells);
+			}
+		}
+
+		// Dropping too small cores and renumbering others
+		let mut regions = vec![];
+		let mut queue = VecDeque::new();
+		for cells in cores {
+			let id = if cells.len() >= MIN_REGION_SIZE {
+				Some(regions.len())
+			} else {
+				None
+			};
+			for &c in &cells {
+				labels[c] = id;
+				if id.is_some() {
+					queue.push_back(c);
+				}
+			}
+			if let Some(id) = id {
+				let center = *cells.iter().max_by_key(|c| clearance[**c]).unwrap();
+				regions.push(Region {
+					id,
+					size: 0,
+					center: Point2::new(center.0 as f32 + 0.5, center.1 as f32 + 0.5),
+					height: bot.get_height(center),
+					expansions: vec![],
+					chokes: vec![],
+					neighbors: vec![],
+				});
+			}
+		}
+
+		// Growing regions to cover all pathable tiles
+		while let Some(c) = queue.pop_front() {
+			let label = labels[c];
+			for n in neighbors4(c) {
+				if labels.get(n).is_some_and(|l| l.is_none()) && pathable(n) {
+					labels[n] = label;
+					queue.push_back(n);
+				}
+			}
+		}
+
+		// Borders between regions
+		let mut borders = FxHashMap::<(usize, usize), FxHashSet<Pos>>::default();
+		for x in area.x0..area.x1 {
+			for y in area.y0..area.y1 {
+				let a = match labels[(x, y)] {
+					Some(a) => a,
+					None => continue,
+				};
+				regions[a].size += 1;
+				for n in [(x + 1, y), (x, y + 1)] {
+					if let Some(Some(b)) = labels.get(n) {
+						if a != *b {
+							let border = borders.entry((a.min(*b), a.max(*b))).or_default();
+							border.insert((x, y));
+							border.insert(n);
+						}
+					}
+				}
+			}
+		}
+
+		// Splitting borders into separate chokes
+		let mut chokes = vec![];
+		let mut keys = borders.keys().copied().collect::<Vec<_>>();
+		keys.sort_unstable();
+		for key in keys {
+			let mut border = borders.remove(&key).unwrap();
+			loop {
+				let start = match border.iter().next() {
+					Some(start) => *start,
+					None => break,
+				};
+				border.remove(&start);
+				let mut points = vec![start];
+				let mut i = 0;
+				while i < points.len() {
+					let (x, y) = points[i];
+					for dx in -1..=1_isize {
+						for dy in -1..=1_isize {
+							let n = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+							if border.remove(&n) {
+								points.push(n);
+							}
+						}
+					}
+					i += 1;
+				}
+				points.sort_unstable();
+
+				let positions = points
+					.iter()
+					.map(|(x, y)| Point2::new(*x as f32 + 0.5, *y as f32 + 0.5))
+					.collect::<Vec<_>>();
+				let center =
+					positions.iter().fold(Point2::default(), |sum, p| sum + *p) / positions.len() as f32;
+				let width = positions
+					.iter()
+					.flat_map(|p1| positions.iter().map(move |p2| p1.distance_squared(*p2)))
+					.fold(0.0_f32, f32::max)
+					.sqrt() + 1.0;
+				let ramp = points.iter().find_map(|p| ramp_points.get(p).copied());
+
+				let id = chokes.len();
+				regions[key.0].chokes.push(id);
+				regions[key.1].chokes.push(id);
+				chokes.push(ChokePoint {
+					id,
+					regions: key,
+					points,
+					center,
+					width,
+					ramp,
+				});
+			}
+			regions[key.0].neighbors.push(key.1);
+			regions[key.1].neighbors.push(key.0);
+		}
+
+		let mut analysis = Self {
+			regions,
+			chokes,
+			labels,
+			clearance,
+		};
+		for (i, exp) in bot.expansions.iter().enumerate() {
+			if let Some(region) = analysis.nearest_region(exp.loc, 6) {
+				analysis.regions[region].expansions.push(i);
+			}
+		}
+		analysis
+	}
+
+	fn nearest_region(&self, pos: Point2, max_distance: isize) -> Option<usize> {
+		let (x, y) = (pos.x as isize, pos.y as isize);
+		(0..=max_distance).find_map(|r| {
+			(-r..=r)
+				.flat_map(|dx| (-r..=r).map(move |dy| (dx, dy)))
+				.filter(|(dx, dy)| dx.abs() == r || dy.abs() == r)
+				.find_map(|(dx, dy)| {
+					if x + dx < 0 || y + dy < 0 {
+						return None;
+					}
+					self.labels
+						.get(((x + dx) as usize, (y + dy) as usize))
+						.copied()
+						.flatten()
+				})
+		})
+	}
+
+	/// Returns id of region, which contains given position.
+	///
+	/// If position isn't pathable (e.g. occupied by townhall), closest region in 6 tiles is returned.
+	pub fn region_id<P: Into<Point2>>(&self, pos: P) -> Option<usize> {
+		self.nearest_region(pos.into(), 6)
+	}
+	/// Returns region, which contains given position.
+	pub fn region_at<P: Into<Point2>>(&self, pos: P) -> Option<&Region> {
+		self.region_id(pos).map(|id| &self.regions[id])
+	}
+	/// Returns distance from given position to the closest obstacle in tiles
+	/// (`0` for not pathable positions).
+	pub fn clearance<P: Into<(usize, usize)>>(&self, pos: P) -> u32 {
+		self.clearance.get(pos.into()).copied().unwrap_or(0)
+	}
+	/// Returns chokes connecting given region with other regions.
+	pub fn chokes_of(&self, region: usize) -> impl Iterator<Item = &ChokePoint> {
+		self.regions
+			.get(region)
+			.map_or(&[][..], |r| &r.chokes[..])
+			.iter()
+			.map(move |c| &self.chokes[*c])
+	}
+	/// Returns chokes between two given regions in order from first region to second,
+	/// or `None` if there's no ground path between regions.
+	pub fn chokes_between_regions(&self, from: usize, to: usize) -> Option<Vec<&ChokePoint>> {
+		if from >= self.regions.len() || to >= self.regions.len() {
+			return None;
+		}
+
+		// Dijkstra over regions with costs of moving through chokes
+		let mut costs = vec![f32::INFINITY; self.regions.len()];
+		let mut previous = vec![None; self.regions.len()];
+		let mut heap = BinaryHeap::new();
+		costs[from] = 0.0;
+		heap.push(Reverse(Cost(0.0, from)));
+
+		while let Some(Reverse(Cost(cost, region))) = heap.pop() {
+			if region == to {
+				break;
+			}
+			if cost > costs[region] {
+				continue;
+			}
+			let center = self.regions[region].center;
+			for choke in self.chokes_of(region) {
+				let next = choke.other(region);
+				let next_cost =
+					cost + center.distance(choke.center) + choke.center.distance(self.regions[next].center);
+				if next_cost < costs[next] {
+					costs[next] = next_cost;
+					previous[next] = Some(choke.id);
+					heap.push(Reverse(Cost(next_cost, next)));
+				}
+			}
+		}
+
+		if from != to && previous[to].is_none() {
+			return None;
+		}
+		let mut path = vec![];
+		let mut region = to;
+		while let Some(choke) = previous[region] {
+			let choke = &self.chokes[choke];
+			path.push(choke);
+			region = choke.other(region);
+		}
+		path.reverse();
+		Some(path)
+	}
+	/// Returns chokes between regions of two given positions in order from first position to second,
+	/// or `None` if there's no ground path between them.
+	pub fn chokes_between<P1: Into<Point2>, P2: Into<Point2>>(
+		&self,
+		from: P1,
+		to: P2,
+	) -> Option<Vec<&ChokePoint>> {
+		self.chokes_between_regions(self.region_id(from)?, self.region_id(to)?)
+	}
+	/// Returns region of expansion with given index in [`expansions`](Bot::expansions).
+	pub fn expansion_region(&self, expansion: usize) -> Option<&Region> {
+		self.regions.iter().find(|r| r.expansions.contains(&expansion))
+	}
+}
+
+#[derive(PartialEq)]
+struct Cost(f32, usize);
+impl Eq for Cost {}
+impl PartialOrd for Cost {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+impl Ord for Cost {
+	fn cmp(&self, other: &Self) -> Ordering {
+		cmp(&self.0, &other.0).then(self.1.cmp(&other.1))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{bot::Expansion, game_state::Alliance, geometry::Rect, pixel_map::Pixel};
+
+	const ROOMS: [(usize, usize); 3] = [(1, 16), (22, 37), (43, 58)];
+
+	/// Bot on flat map with 3 rooms 16x16 in a row connected by corridors 3 tiles wide.
+	fn three_rooms() -> Bot {
+		let mut bot = Bot::default();
+		bot.game_info.pathing_grid = Array2::from_shape_fn((60, 18), |(x, y)| {
+			let room = ROOMS.iter().any(|(x0, x1)| (*x0..=*x1).contains(&x)) && (1..=16).contains(&y);
+			let corridor = (17..=21).contains(&x) || (38..=42).contains(&x);
+			if room || (corridor && (7..=9).contains(&y)) {
+				Pixel::Empty
+			} else {
+				Pixel::Set
+			}
+		});
+		bot.game_info.playable_area = Rect::new(0, 0, 60, 18);
+		bot.expansions.push(Expansion {
+			loc: Point2::new(50.5, 8.5),
+			center: Point2::new(50.5, 14.5),
+			minerals: Default::default(),
+			geysers: Default::default(),
+			alliance: Alliance::Neutral,
+			base: None,
+		});
+		bot
+	}
+
+	#[test]
+	fn regions_and_chokes() {
+		let bot = three_rooms();
+		let analysis = MapAnalysis::new(&bot);
+		assert_eq!(analysis.regions.len(), 3);
+		assert_eq!(analysis.chokes.len(), 2);
+		assert_eq!(
+			analysis.regions.iter().map(|r| r.size).sum::<usize>(),
+			3 * 256 + 2 * 15
+		);
+
+		for (region, (x0, x1)) in analysis.regions.iter().zip(ROOMS) {
+			assert!(region.center.x > x0 as f32 && region.center.x < x1 as f32);
+			assert_eq!(analysis.region_id(region.center), Some(region.id));
+		}
+
+		let choke = &analysis.chokes[0];
+		assert_eq!(choke.regions, (0, 1));
+		assert_eq!(choke.other(0), 1);
+		assert_eq!(choke.other(1), 0);
+		assert!(choke.center.x > 17.0 && choke.center.x < 22.0);
+		assert!((choke.center.y - 8.5).abs() < 1e-3);
+		assert!(choke.width > 3.0 && choke.width < 4.0);
+		assert_eq!(choke.ramp, None);
+		assert_eq!(analysis.chokes[1].regions, (1, 2));
+
+		let mut neighbors = analysis.regions[1].neighbors.clone();
+		neighbors.sort_unstable();
+		assert_eq!(neighbors, vec![0, 2]);
+		assert_eq!(analysis.chokes_of(1).count(), 2);
+		assert_eq!(analysis.chokes_of(5).count(), 0);
+	}
+
+	#[test]
+	fn positions_and_clearance() {
+		let bot = three_rooms();
+		let analysis = MapAnalysis::new(&bot);
+		assert_eq!(analysis.clearance((8, 8)), 8);
+		assert_eq!(analysis.clearance((19, 8)), 2);
+		assert_eq!(analysis.clearance((0, 0)), 0);
+		assert_eq!(analysis.clearance((100, 100)), 0);
+
+		assert_eq!(analysis.region_id(Point2::new(8.5, 8.5)), Some(0));
+		assert_eq!(analysis.region_id(Point2::new(0.5, 8.5)), Some(0));
+		assert_eq!(analysis.region_id(Point2::new(30.5, 8.5)), Some(1));
+		assert_eq!(analysis.region_at(Point2::new(50.5, 8.5)).map(|r| r.id), Some(2));
+
+		assert_eq!(analysis.regions[2].expansions, vec![0]);
+		assert_eq!(analysis.expansion_region(0).map(|r| r.id), Some(2));
+		assert!(analysis.expansion_region(1).is_none());
+	}
+
+	#[test]
+	fn chokes_on_the_way() {
+		let bot = three_rooms();
+		let analysis = MapAnalysis::new(&bot);
+
+		let path = analysis
+			.chokes_between(Point2::new(8.5, 8.5), Point2::new(50.5, 8.5))
+			.unwrap();
+		assert_eq!(path.iter().map(|c| c.id).collect::<Vec<_>>(), vec![0, 1]);
+
+		let path = analysis.chokes_between_regions(2, 0).unwrap();
+		assert_eq!(path.iter().map(|c| c.id).collect::<Vec<_>>(), vec![1, 0]);
+
+		assert!(analysis.chokes_between_regions(1, 1).unwrap().is_empty());
+		assert!(analysis.chokes_between_regions(0, 3).is_none());
+	}
+}
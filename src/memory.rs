@@ -0,0 +1,422 @@
+//! Memory of all enemy units ever seen.
+//!
+//! Updated automatically every step and can be accessed through
+//! [`enemy_memory`](crate::bot::Bot::enemy_memory) field.
+
+use crate::{
+	bot::Bot, consts::FRAMES_PER_SECOND, distance::Distance, geometry::Point2, ids::UnitTypeId, unit::Unit,
+	units::Units,
+};
+use rustc_hash::FxHashMap;
+
+/// Enemy unit remembered by [`EnemyMemory`].
+#[derive(Clone)]
+pub struct RememberedUnit {
+	/// Last observed state of the unit.
+	pub unit: Unit,
+	/// Last known position of the unit.
+	pub position: Point2,
+	/// Game loop when unit was seen last time.
+	pub last_seen: u32,
+	/// Summed health and shield when unit was seen last time (`None` for snapshots).
+	pub hits: Option<u32>,
+	/// `true` if unit is in current observation.
+	pub is_observed: bool,
+}
+impl RememberedUnit {
+	/// Unit type of remembered unit.
+	pub fn type_id(&self) -> UnitTypeId {
+		self.unit.type_id()
+	}
+	/// Tag of remembered unit.
+	pub fn tag(&self) -> u64 {
+		self.unit.tag()
+	}
+	/// Checks if remembered unit is structure.
+	pub fn is_structure(&self) -> bool {
+		self.unit.is_structure()
+	}
+	/// Returns number of game loops passed since unit was seen last time.
+	pub fn unseen_for(&self, game_loop: u32) -> u32 {
+		game_loop.saturating_sub(self.last_seen)
+	}
+}
+impl From<&RememberedUnit> for Point2 {
+	#[inline]
+	fn from(u: &RememberedUnit) -> Self {
+		u.position
+	}
+}
+
+/// Records every enemy unit with it's last known position, time of sighting, health and type.
+///
+/// Units are removed from memory when:
+/// - they die
+/// - structure isn't there anymore, when it's position is visible
+/// - unit wasn't seen for [`unit_decay`](Self::unit_decay) game loops
+#[derive(Clone)]
+pub struct EnemyMemory {
+	/// Game loops after which unseen units are forgotten. [Default: `60 seconds`]
+	pub unit_decay: u32,
+	/// Game loops after which unseen structures are forgotten.
+	/// Structures are never forgotten if `None`. [Default: `None`]
+	pub structure_decay: Option<u32>,
+	units: FxHashMap<u64, RememberedUnit>,
+	game_loop: u32,
+}
+impl Default for EnemyMemory {
+	fn default() -> Self {
+		Self {
+			unit_decay: (60.0 * FRAMES_PER_SECOND) as u32,
+			structure_decay: None,
+			units: Default::default(),
+			game_loop: 0,
+		}
+	}
+}
+
+impl EnemyMemory {
+	pub(crate) fn update(&mut self, bot: &Bot) {
+		let game_loop = bot.state.observation.game_loop();
+		self.game_loop = game_loop;
+
+		for u in self.units.values_mut() {
+			u.is_observed = false;
+		}
+
+		for u in &bot.units.enemy.all {
+			if u.is_hallucination() {
+				continue;
+			}
+			let is_visible = u.is_visible();
+			let entry = self.units.entry(u.tag()).or_insert_with(|| RememberedUnit {
+				unit: u.clone(),
+				position: u.position(),
+				last_seen: game_loop,
+				hits: u.hits(),
+				is_observed: true,
+			});
+			entry.unit = u.clone();
+			entry.position = u.position();
+			entry.is_observed = true;
+			if is_visible {
+				entry.last_seen = game_loop;
+				entry.hits = u.hits();
+			}
+		}
+
+		let unit_decay = self.unit_decay;
+		let structure_decay = self.structure_decay;
+		self.units.retain(|_, u| {
+			if u.is_observed {
+				return true;
+			}
+			let unseen = u.unseen_for(game_loop);
+			if u.is_structure() {
+				// structure is destroyed or cancelled if it isn't there anymore
+				!bot.is_visible(u.position) && structure_decay.map_or(true, |decay| unseen < decay)
+			} else {
+				unseen < unit_decay
+			}
+		});
+	}
+	pub(crate) fn remove(&mut self, tag: u64) -> Option<RememberedUnit> {
+		self.units.remove(&tag)
+	}
+
+	/// Returns remembered unit by tag.
+	pub fn get(&self, tag: u64) -> Option<&RememberedUnit> {
+		self.units.get(&tag)
+	}
+	/// Checks if unit with given tag is remembered.
+	pub fn contains(&self, tag: u64) -> bool {
+		self.units.contains_key(&tag)
+	}
+	/// Returns number of remembered units.
+	pub fn len(&self) -> usize {
+		self.units.len()
+	}
+	/// Checks if memory is empty.
+	pub fn is_empty(&self) -> bool {
+		self.units.is_empty()
+	}
+	/// Returns iterator over all remembered units.
+	pub fn iter(&self) -> impl Iterator<Item = &RememberedUnit> {
+		self.units.values()
+	}
+	/// Returns iterator over remembered units, which aren't in current observation.
+	pub fn hidden(&self) -> impl Iterator<Item = &RememberedUnit> {
+		self.iter().filter(|u| !u.is_observed)
+	}
+	/// Returns iterator over remembered units excluding structures.
+	pub fn units(&self) -> impl Iterator<Item = &RememberedUnit> {
+		self.iter().filter(|u| !u.is_structure())
+	}
+	/// Returns iterator over remembered structures.
+	pub fn structures(&self) -> impl Iterator<Item = &RememberedUnit> {
+		self.iter().filter(|u| u.is_structure())
+	}
+	/// Returns all remembered units as [`Units`] with their last observed state.
+	pub fn to_units(&self) -> Units {
+		self.iter().map(|u| u.unit.clone()).collect()
+	}
+
+	/// Returns estimated probability of remembered unit to be still alive.
+	/// Value in range from `0` to `1`, decreasing over time since unit was seen last time.
+	///
+	/// Returns `0` for units which aren't in memory.
+	pub fn alive_probability(&self, tag: u64) -> f32 {
+		let u = match self.units.get(&tag) {
+			Some(u) => u,
+			None => return 0.0,
+		};
+		if u.is_observed {
+			return 1.0;
+		}
+		let decay = if u.is_structure() {
+			match self.structure_decay {
+				Some(decay) => decay,
+				None => return 1.0,
+			}
+		} else {
+			self.unit_decay
+		};
+		if decay == 0 {
+			return 0.0;
+		}
+		(1.0 - u.unseen_for(self.game_loop) as f32 / decay as f32).max(0.0)
+	}
+	/// Checks if remembered unit is still alive with probability at least `0.5`.
+	pub fn is_alive(&self, tag: u64) -> bool {
+		self.alive_probability(tag) >= 0.5
+	}
+
+	/// Counts remembered units of given type.
+	pub fn count(&self, unit_type: UnitTypeId) -> usize {
+		self.iter().filter(|u| u.type_id() == unit_type).count()
+	}
+	/// Returns number of remembered units per unit type.
+	pub fn counts(&self) -> FxHashMap<UnitTypeId, usize> {
+		let mut counts = FxHashMap::default();
+		for u in self.iter() {
+			*counts.entry(u.type_id()).or_default() += 1;
+		}
+		counts
+	}
+	/// Returns summed cost (minerals, vespene) of all remembered army units
+	/// (i.e. excluding workers and structures).
+	pub fn army_value(&self) -> (u32, u32) {
+		self.units()
+			.filter(|u| !u.unit.is_worker())
+			.map(|u| u.unit.cost())
+			.fold((0, 0), |(minerals, vespene), cost| {
+				(minerals + cost.minerals, vespene + cost.vespene)
+			})
+	}
+	/// Returns summed supply of all remembered army units (i.e. excluding workers and structures).
+	pub fn army_supply(&self) -> f32 {
+		self.units()
+			.filter(|u| !u.unit.is_worker())
+			.map(|u| u.unit.supply_cost())
+			.sum()
+	}
+	/// Returns iterator over remembered units last seen closer than given distance to given point.
+	pub fn closer<P: Into<Point2> + Copy>(
+		&self,
+		distance: f32,
+		target: P,
+	) -> impl Iterator<Item = &RememberedUnit> {
+		self.iter()
+			.filter(move |u| u.position.is_closer(distance, target))
+	}
+	/// Returns iterator over remembered units seen not earlier than given number of game loops ago.
+	pub fn seen_within(&self, loops: u32) -> impl Iterator<Item = &RememberedUnit> {
+		let game_loop = self.game_loop;
+		self.iter().filter(move |u| u.unseen_for(game_loop) <= loops)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::LockOwned,
+		pixel_map::Visibility,
+		unit::tests::{proto, structure_data, unit, with_data},
+	};
+	use ndarray::Array2;
+	use sc2_proto::raw::{Alliance as ProtoAlliance, DisplayType as ProtoDisplayType, Unit as ProtoUnit};
+
+	fn remember(memory: &mut EnemyMemory, tag: u64, type_id: UnitTypeId, last_seen: u32, is_observed: bool) {
+		let unit = unit(tag, type_id, (tag as f32, 0.0));
+		memory.units.insert(
+			tag,
+			RememberedUnit {
+				position: unit.position(),
+				hits: unit.hits(),
+				unit,
+				last_seen,
+				is_observed,
+			},
+		);
+	}
+
+	fn memory() -> EnemyMemory {
+		let mut memory = EnemyMemory {
+			unit_decay: 100,
+			game_loop: 200,
+			..Default::default()
+		};
+		remember(&mut memory, 1, UnitTypeId::Zergling, 200, true);
+		remember(&mut memory, 2, UnitTypeId::Zergling, 175, false);
+		remember(&mut memory, 3, UnitTypeId::Roach, 100, false);
+		memory
+	}
+
+	#[test]
+	fn alive_probability_decays() {
+		let memory = memory();
+		assert_eq!(memory.alive_probability(1), 1.0);
+		assert_eq!(memory.alive_probability(2), 0.75);
+		assert_eq!(memory.alive_probability(3), 0.0);
+		assert_eq!(memory.alive_probability(4), 0.0);
+		assert!(memory.is_alive(2));
+		assert!(!memory.is_alive(3));
+	}
+
+	#[test]
+	fn queries() {
+		let memory = memory();
+		assert_eq!(memory.len(), 3);
+		assert_eq!(memory.count(UnitTypeId::Zergling), 2);
+		assert_eq!(memory.counts().get(&UnitTypeId::Roach), Some(&1));
+		assert_eq!(memory.hidden().count(), 2);
+
+		let mut seen = memory.seen_within(25).map(|u| u.tag()).collect::<Vec<_>>();
+		seen.sort_unstable();
+		assert_eq!(seen, vec![1, 2]);
+
+		let mut close = memory
+			.closer(1.5, Point2::new(1.0, 0.0))
+			.map(|u| u.tag())
+			.collect::<Vec<_>>();
+		close.sort_unstable();
+		assert_eq!(close, vec![1, 2]);
+	}
+
+	#[test]
+	fn remove() {
+		let mut memory = memory();
+		assert_eq!(memory.remove(2).map(|u| u.tag()), Some(2));
+		assert!(!memory.contains(2));
+		assert!(memory.remove(2).is_none());
+	}
+
+	fn enemy(tag: u64, type_id: UnitTypeId, pos: (f32, f32)) -> ProtoUnit {
+		let mut u = proto(tag, type_id, pos);
+		u.set_alliance(ProtoAlliance::Enemy);
+		u
+	}
+	fn observe(memory: &mut EnemyMemory, bot: &mut Bot, game_loop: u32, units: &[ProtoUnit]) {
+		let data = structure_data(&[UnitTypeId::SpawningPool]);
+		bot.state.observation.game_loop.set_locked(game_loop);
+		bot.units.enemy.all = units.iter().map(|u| with_data(&data, u)).collect();
+		memory.update(bot);
+	}
+
+	#[test]
+	fn update_merges_observations() {
+		let mut bot = Bot::default();
+		let mut memory = EnemyMemory::default();
+		let mut hallucination = enemy(2, UnitTypeId::Zergling, (8.0, 5.0));
+		hallucination.set_is_hallucination(true);
+		observe(
+			&mut memory,
+			&mut bot,
+			10,
+			&[enemy(1, UnitTypeId::Zergling, (5.0, 5.0)), hallucination],
+		);
+		assert_eq!(memory.len(), 1);
+		let zergling = memory.get(1).unwrap();
+		assert_eq!((zergling.last_seen, zergling.hits), (10, Some(100)));
+		assert!(zergling.is_observed);
+		assert!(!memory.contains(2));
+
+		// Snapshot updates position, but not time of sighting and health
+		let mut snapshot = enemy(1, UnitTypeId::Zergling, (6.0, 5.0));
+		snapshot.set_display_type(ProtoDisplayType::Snapshot);
+		snapshot.set_health(50.0);
+		observe(&mut memory, &mut bot, 20, &[snapshot]);
+		let zergling = memory.get(1).unwrap();
+		assert!(zergling.position.is_closer(0.01, Point2::new(6.0, 5.0)));
+		assert_eq!((zergling.last_seen, zergling.hits), (10, Some(100)));
+		assert!(zergling.is_observed);
+
+		let mut visible = enemy(1, UnitTypeId::Zergling, (7.0, 5.0));
+		visible.set_health(50.0);
+		observe(&mut memory, &mut bot, 30, &[visible]);
+		let zergling = memory.get(1).unwrap();
+		assert_eq!((zergling.last_seen, zergling.hits), (30, Some(50)));
+	}
+
+	#[test]
+	fn update_forgets_units() {
+		let mut bot = Bot::default();
+		let mut memory = EnemyMemory {
+			unit_decay: 100,
+			..Default::default()
+		};
+		observe(
+			&mut memory,
+			&mut bot,
+			10,
+			&[enemy(1, UnitTypeId::Zergling, (5.0, 5.0))],
+		);
+
+		observe(&mut memory, &mut bot, 50, &[]);
+		assert_eq!(memory.hidden().map(|u| u.tag()).collect::<Vec<_>>(), vec![1]);
+		assert!((memory.alive_probability(1) - 0.6).abs() < 1e-6);
+
+		observe(&mut memory, &mut bot, 109, &[]);
+		assert!(memory.contains(1));
+		observe(&mut memory, &mut bot, 110, &[]);
+		assert!(memory.is_empty());
+	}
+
+	#[test]
+	fn update_removes_structures_gone_from_sight() {
+		let mut bot = Bot::default();
+		let mut memory = EnemyMemory::default();
+		let pool = enemy(1, UnitTypeId::SpawningPool, (10.5, 10.5));
+		observe(&mut memory, &mut bot, 10, &[pool]);
+		assert_eq!(memory.structures().count(), 1);
+
+		// Structures aren't forgotten in fog of war
+		observe(&mut memory, &mut bot, 10_000, &[]);
+		assert!(memory.contains(1));
+		assert_eq!(memory.alive_probability(1), 1.0);
+
+		bot.state.observation.raw.visibility = Array2::from_elem((32, 32), Visibility::Visible);
+		observe(&mut memory, &mut bot, 10_001, &[]);
+		assert!(memory.is_empty());
+	}
+
+	#[test]
+	fn update_forgets_structures_with_decay() {
+		let mut bot = Bot::default();
+		let mut memory = EnemyMemory {
+			structure_decay: Some(50),
+			..Default::default()
+		};
+		observe(
+			&mut memory,
+			&mut bot,
+			10,
+			&[enemy(1, UnitTypeId::SpawningPool, (10.5, 10.5))],
+		);
+		observe(&mut memory, &mut bot, 59, &[]);
+		assert!(memory.contains(1));
+		observe(&mut memory, &mut bot, 60, &[]);
+		assert!(!memory.contains(1));
+	}
+}
@@ -1,7 +1,7 @@
 //! Data structures, used to store map data.
 #![allow(missing_docs)]
 
-use crate::{geometry::Point2, FromProto};
+use crate::{distance::Distance, geometry::Point2, FromProto};
 use ndarray::Array2;
 use num_traits::FromPrimitive;
 use sc2_proto::common::ImageData;
@@ -32,6 +32,62 @@ impl<T> IndexMut<Point2> for Array2<T> {
 	}
 }
 
+/// Returns cell of the bottom left corner of footprint with given size centered at given position.
+pub(crate) fn corner(pos: Point2, width: usize, height: usize) -> (isize, isize) {
+	(
+		(pos.x - width as f32 / 2.0).round() as isize,
+		(pos.y - height as f32 / 2.0).round() as isize,
+	)
+}
+
+fn cell_mut<T>(grid: &mut Array2<T>, x: isize, y: isize) -> Option<&mut T> {
+	if x < 0 || y < 0 {
+		return None;
+	}
+	grid.get_mut((x as usize, y as usize))
+}
+
+/// Marks cells of footprint with given size centered at given position,
+/// extended by `margin` cells to every side. Cells outside of the grid are skipped.
+pub(crate) fn fill_footprint(
+	grid: &mut Array2<bool>,
+	pos: Point2,
+	width: usize,
+	height: usize,
+	margin: isize,
+) {
+	let (x0, y0) = corner(pos, width, height);
+	for x in x0 - margin..x0 + width as isize + margin {
+		for y in y0 - margin..y0 + height as isize + margin {
+			if let Some(cell) = cell_mut(grid, x, y) {
+				*cell = true;
+			}
+		}
+	}
+}
+
+/// Applies `f` to every cell of the grid with center inside given circle.
+/// Cells outside of the grid are skipped.
+pub(crate) fn for_circle<T, F>(grid: &mut Array2<T>, center: Point2, radius: f32, mut f: F)
+where
+	F: FnMut(&mut T),
+{
+	for x in (center.x - radius).floor() as isize..=(center.x + radius).ceil() as isize {
+		for y in (center.y - radius).floor() as isize..=(center.y + radius).ceil() as isize {
+			if Point2::new(x as f32 + 0.5, y as f32 + 0.5).is_closer(radius, center) {
+				if let Some(cell) = cell_mut(grid, x, y) {
+					f(cell);
+				}
+			}
+		}
+	}
+}
+
+/// Marks every cell of the grid with center inside given circle.
+pub(crate) fn fill_circle(grid: &mut Array2<bool>, center: Point2, radius: f32) {
+	for_circle(grid, center, radius, |cell| *cell = true);
+}
+
 fn to_binary(n: u8) -> impl Iterator<Item = Pixel> {
 	(0..8).rev().map(move |x| Pixel::from_u8((n >> x) & 1).unwrap())
 }
@@ -116,3 +172,61 @@ impl Visibility {
 		!matches!(self, Visibility::Hidden)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn corner_of_footprint() {
+		assert_eq!(corner(Point2::new(10.5, 20.5), 3, 3), (9, 19));
+		assert_eq!(corner(Point2::new(10.0, 20.0), 2, 2), (9, 19));
+		assert_eq!(corner(Point2::new(10.0, 20.5), 2, 1), (9, 20));
+	}
+
+	#[test]
+	fn fill_footprint_with_margin() {
+		let mut grid = Array2::from_elem((8, 8), false);
+		fill_footprint(&mut grid, Point2::new(4.0, 4.0), 2, 2, 0);
+		assert_eq!(grid.iter().filter(|c| **c).count(), 4);
+		assert!(grid[(3, 3)] && grid[(4, 4)] && !grid[(5, 5)]);
+
+		fill_footprint(&mut grid, Point2::new(4.0, 4.0), 2, 2, 1);
+		assert_eq!(grid.iter().filter(|c| **c).count(), 16);
+		assert!(grid[(2, 2)] && grid[(5, 5)] && !grid[(6, 6)]);
+	}
+
+	#[test]
+	fn fill_footprint_clips_to_grid() {
+		let mut grid = Array2::from_elem((4, 4), false);
+		fill_footprint(&mut grid, Point2::new(0.5, 0.5), 3, 3, 0);
+		assert_eq!(grid.iter().filter(|c| **c).count(), 4);
+		assert!(grid[(0, 0)] && grid[(1, 1)] && !grid[(2, 2)]);
+	}
+
+	#[test]
+	fn fill_circle_cells() {
+		let mut grid = Array2::from_elem((16, 16), false);
+		fill_circle(&mut grid, Point2::new(8.0, 8.0), 1.0);
+		let cells = grid
+			.indexed_iter()
+			.filter(|(_, c)| **c)
+			.map(|(i, _)| i)
+			.collect::<Vec<_>>();
+		assert_eq!(cells, vec![(7, 7), (7, 8), (8, 7), (8, 8)]);
+
+		let mut grid = Array2::from_elem((4, 4), false);
+		fill_circle(&mut grid, Point2::new(0.0, 0.0), 3.0);
+		assert!(grid[(0, 0)] && grid[(2, 1)] && !grid[(2, 2)]);
+	}
+
+	#[test]
+	fn for_circle_accumulates() {
+		let mut grid = Array2::from_elem((16, 16), 0.0);
+		for_circle(&mut grid, Point2::new(8.0, 8.0), 2.0, |cell| *cell += 1.5);
+		for_circle(&mut grid, Point2::new(8.0, 8.0), 1.0, |cell| *cell += 1.5);
+		assert_eq!(grid[(8, 8)], 3.0);
+		assert_eq!(grid[(9, 8)], 1.5);
+		assert_eq!(grid[(0, 0)], 0.0);
+	}
+}
@@ -0,0 +1,695 @@
+//! Local building placement without API queries.
+//!
+//! [`PlacementGrid`] is built from map's placement grid, footprints of current structures,
+//! creep, psionic matrix and mineral lines of expansions,
+//! so positions for buildings can be validated and found instantly.
+//!
+//! Terrain, resources and mineral lines are computed once at the start of the game,
+//! while structures, creep and psionic matrix are refreshed every step.
+//! Grid is stored in [`placement`](crate::bot::Bot::placement) field.
+
+use crate::{
+	bot::{Bot, PlacementOptions, Rs},
+	distance::*,
+	game_data::GameData,
+	geometry::Point2,
+	ids::UnitTypeId,
+	layout::Layout,
+	pixel_map::{corner, fill_circle, fill_footprint},
+	player::Race,
+};
+use ndarray::Array2;
+use rand::prelude::*;
+
+/// Grid used to check building placement locally.
+#[derive(Clone)]
+pub struct PlacementGrid {
+	/// Don't allow to place buildings between townhalls and resources,
+	/// except defense slots of [`layout`](crate::bot::Bot::layout). [Default: `true`]
+	pub exclude_mineral_lines: bool,
+	game_data: Rs<GameData>,
+	terrain: Array2<bool>,
+	blocked: Array2<bool>,
+	creep: Array2<bool>,
+	resources: Array2<bool>,
+	mineral_lines: Array2<bool>,
+	power: Vec<(Point2, f32)>,
+	free_geysers: Vec<Point2>,
+}
+
+impl Default for PlacementGrid {
+	fn default() -> Self {
+		Self {
+			exclude_mineral_lines: true,
+			game_data: Default::default(),
+			terrain: Default::default(),
+			blocked: Default::default(),
+			creep: Default::default(),
+			resources: Default::default(),
+			mineral_lines: Default::default(),
+			power: Default::default(),
+			free_geysers: Default::default(),
+		}
+	}
+}
+
+impl PlacementGrid {
+	/// Builds placement grid from current state of the game.
+	pub fn new(bot: &Bot) -> Self {
+		let mut grid = Self::default();
+		grid.prepare(bot);
+		grid.update(bot);
+		grid
+	}
+	/// Computes parts of the grid, which don't change during the game:
+	/// terrain, areas around resources and mineral lines of expansions.
+	pub(crate) fn prepare(&mut self, bot: &Bot) {
+		let placement = &bot.game_info.placement_grid;
+		let dim = placement.dim();
+
+		self.game_data = Rs::clone(&bot.game_data);
+		self.terrain = placement.map(|p| !p.is_empty());
+		self.resources = Array2::from_elem(dim, false);
+		self.mineral_lines = Array2::from_elem(dim, false);
+
+		for u in &bot.units.resources {
+			if u.is_mineral() {
+				fill_footprint(&mut self.resources, u.position(), 2, 1, 0);
+			} else if u.is_geyser() {
+				fill_footprint(&mut self.resources, u.position(), 3, 3, 0);
+			}
+		}
+
+		for exp in &bot.expansions {
+			let resources = exp
+				.minerals
+				.iter()
+				.chain(&exp.geysers)
+				.filter_map(|tag| bot.units.resources.get(*tag))
+				.map(|u| u.position())
+				.collect::<Vec<_>>();
+			if resources.is_empty() {
+				continue;
+			}
+
+			let (x0, y0, x1, y1) = resources.iter().fold(
+				(exp.loc.x, exp.loc.y, exp.loc.x, exp.loc.y),
+				|(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+			);
+			for x in (x0 - 2.0) as isize..=(x1 + 2.0) as isize {
+				for y in (y0 - 2.0) as isize..=(y1 + 2.0) as isize {
+					let cell = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+					if resources
+						.iter()
+						.any(|r| distance_to_segment(cell, exp.loc, *r) < 1.5)
+					{
+						fill_footprint(&mut self.mineral_lines, cell, 1, 1, 0);
+					}
+				}
+			}
+		}
+		self.free_defense_slots(&bot.layout);
+	}
+	/// Refreshes parts of the grid, which change every step:
+	/// footprints of units, creep, psionic matrix and free geysers.
+	pub(crate) fn update(&mut self, bot: &Bot) {
+		self.blocked.clone_from(&self.terrain);
+		self.creep = bot.state.observation.raw.creep.read_lock().map(|p| p.is_empty());
+		self.power = bot
+			.state
+			.observation
+			.raw
+			.psionic_matrix
+			.iter()
+			.map(|m| (m.pos, m.radius))
+			.collect();
+
+		for u in &bot.units.all {
+			if u.is_mineral() {
+				fill_footprint(&mut self.blocked, u.position(), 2, 1, 0);
+			} else if u.is_geyser() {
+				fill_footprint(&mut self.blocked, u.position(), 3, 3, 0);
+			} else if u.is_structure() {
+				if u.is_flying() {
+					continue;
+				}
+				match u.building_size() {
+					Some(size) => fill_footprint(&mut self.blocked, u.position(), size, size, 0),
+					None => fill_circle(&mut self.blocked, u.position(), u.radius()),
+				}
+			} else if u.is_enemy() && !u.is_flying() {
+				fill_circle(&mut self.blocked, u.position(), u.radius());
+			}
+		}
+
+		let gas_buildings = bot
+			.units
+			.all
+			.iter()
+			.filter(|u| is_gas_building(u.type_id()))
+			.map(|u| u.position())
+			.collect::<Vec<_>>();
+		self.free_geysers = bot
+			.units
+			.vespene_geysers
+			.iter()
+			.map(|u| u.position())
+			.filter(|pos| !gas_buildings.iter().any(|gas| gas.is_closer(0.5, *pos)))
+			.collect();
+	}
+	/// Removes defense slots of given layout from mineral lines,
+	/// since they are planned to be there.
+	pub(crate) fn free_defense_slots(&mut self, layout: &Layout) {
+		for pos in layout.bases.iter().flat_map(|base| &base.defense) {
+			let (x0, y0) = corner(*pos, 2, 2);
+			for x in x0.max(0)..x0 + 2 {
+				for y in y0.max(0)..y0 + 2 {
+					if let Some(cell) = self.mineral_lines.get_mut((x as usize, y as usize)) {
+						*cell = false;
+					}
+				}
+			}
+		}
+	}
+
+	/// Returns size of given building in tiles or `None` if it isn't building.
+	pub fn building_size(&self, building: UnitTypeId) -> Option<usize> {
+		if building.is_addon() {
+			return Some(2);
+		}
+		let ability = self.game_data.units.get(&building)?.ability?;
+		self.game_data
+			.abilities
+			.get(&ability)?
+			.footprint_radius
+			.map(|radius| (radius * 2.0) as usize)
+	}
+	/// Aligns given position to grid, so building of given size can be placed there.
+	pub fn align(pos: Point2, size: usize) -> Point2 {
+		if size % 2 == 1 {
+			Point2::new(pos.x.floor() + 0.5, pos.y.floor() + 0.5)
+		} else {
+			Point2::new(pos.x.round(), pos.y.round())
+		}
+	}
+
+	/// Checks if given cell is free to build on.
+	pub fn is_free(&self, x: isize, y: isize) -> bool {
+		get(&self.blocked, x, y).is_some_and(|blocked| !blocked)
+	}
+	/// Checks if area of given size on given position is free to build on.
+	///
+	/// Unlike [`can_place`](Self::can_place) it doesn't check mineral lines, creep and power.
+	pub fn is_free_area(&self, pos: Point2, size: usize) -> bool {
+		let (x0, y0) = corner(pos, size, size);
+		(x0..x0 + size as isize).all(|x| (y0..y0 + size as isize).all(|y| self.is_free(x, y)))
+	}
+	/// Checks if area of given size on given position intersects mineral line of any expansion.
+	pub fn is_mineral_line(&self, pos: Point2, size: usize) -> bool {
+		let (x0, y0) = corner(pos, size, size);
+		(x0..x0 + size as isize)
+			.any(|x| (y0..y0 + size as isize).any(|y| get(&self.mineral_lines, x, y).unwrap_or(false)))
+	}
+	/// Checks if given position is powered by psionic matrix.
+	pub fn is_powered(&self, pos: Point2) -> bool {
+		self.power
+			.iter()
+			.any(|(matrix, radius)| pos.is_closer(*radius, *matrix))
+	}
+	/// Returns positions of geysers without gas buildings on them.
+	pub fn free_geysers(&self) -> &[Point2] {
+		&self.free_geysers
+	}
+
+	/// Checks if it's possible to build given building on given position.
+	pub fn can_place(&self, building: UnitTypeId, pos: Point2) -> bool {
+		if is_gas_building(building) {
+			return self.free_geysers.iter().any(|geyser| geyser.is_closer(0.5, pos));
+		}
+		let size = match self.building_size(building) {
+			Some(size) => size,
+			None => return false,
+		};
+		let race = self
+			.game_data
+			.units
+			.get(&building)
+			.map_or(Race::Random, |data| data.race);
+
+		let (x0, y0) = corner(pos, size, size);
+		for x in x0..x0 + size as isize {
+			for y in y0..y0 + size as isize {
+				if !self.is_free(x, y) {
+					return false;
+				}
+				if self.exclude_mineral_lines
+					&& !building.is_townhall()
+					&& get(&self.mineral_lines, x, y).unwrap_or(false)
+				{
+					return false;
+				}
+				let creep = get(&self.creep, x, y).unwrap_or(false);
+				if race.is_zerg() {
+					if !creep && building != UnitTypeId::Hatchery {
+						return false;
+					}
+				} else if creep {
+					return false;
+				}
+			}
+		}
+
+		if building.is_townhall() {
+			// Townhalls can't be placed closer than 3 tiles to resources
+			for x in x0 - 3..x0 + size as isize + 3 {
+				for y in y0 - 3..y0 + size as isize + 3 {
+					if get(&self.resources, x, y).unwrap_or(false) {
+						return false;
+					}
+				}
+			}
+		}
+
+		!(race.is_protoss() && needs_power(building)) || self.is_powered(pos)
+	}
+	/// Checks if addon can be placed near the building on given position.
+	pub fn can_place_addon(&self, pos: Point2) -> bool {
+		let (x0, y0) = corner(pos.offset(2.5, -0.5), 2, 2);
+		(x0..x0 + 2).all(|x| {
+			(y0..y0 + 2).all(|y| {
+				self.is_free(x, y)
+					&& !get(&self.creep, x, y).unwrap_or(false)
+					&& !(self.exclude_mineral_lines && get(&self.mineral_lines, x, y).unwrap_or(false))
+			})
+		})
+	}
+
+	/// Marks area of given building on given position as occupied,
+	/// useful to plan placement of multiple buildings on the same step.
+	pub fn occupy(&mut self, building: UnitTypeId, pos: Point2) {
+		if is_gas_building(building) {
+			self.free_geysers.retain(|geyser| !geyser.is_closer(0.5, pos));
+		} else if let Some(size) = self.building_size(building) {
+			fill_footprint(&mut self.blocked, pos, size, size, 0);
+		}
+	}
+
+	/// Returns valid positions for given building around given position
+	/// grouped by distance in ascending order.
+	pub fn placements(
+		&self,
+		building: UnitTypeId,
+		near: Point2,
+		options: PlacementOptions,
+	) -> impl Iterator<Item = Vec<Point2>> + '_ {
+		let near = Self::align(near, self.building_size(building).unwrap_or(1));
+		let step = options.step.max(1);
+
+		std::iter::once(vec![near])
+			.chain(
+				(step..options.max_distance)
+					.step_by(step as usize)
+					.map(move |distance| {
+						(-distance..=distance)
+							.step_by(step as usize)
+							.flat_map(|offset| {
+								[
+									near.offset(offset as f32, (-distance) as f32),
+									near.offset(offset as f32, distance as f32),
+									near.offset((-distance) as f32, offset as f32),
+									near.offset(distance as f32, offset as f32),
+								]
+							})
+							.collect()
+					}),
+			)
+			.map(move |positions: Vec<Point2>| {
+				positions
+					.into_iter()
+					.filter(|pos| {
+						self.can_place(building, *pos) && (!options.addon || self.can_place_addon(*pos))
+					})
+					.collect()
+			})
+	}
+	/// Returns correct position where it is possible to build given `building`,
+	/// or `None` if position is not found.
+	///
+	/// Local alternative to [`find_placement`](Bot::find_placement).
+	pub fn find_placement(
+		&self,
+		building: UnitTypeId,
+		near: Point2,
+		options: PlacementOptions,
+	) -> Option<Point2> {
+		self.placements(building, near, options)
+			.find(|positions| !positions.is_empty())
+			.and_then(|positions| choose_placement(&positions, near, options.random))
+	}
+}
+
+pub(crate) fn choose_placement(positions: &[Point2], near: Point2, random: bool) -> Option<Point2> {
+	if random {
+		positions.choose(&mut thread_rng()).copied()
+	} else {
+		positions.iter().closest(near).copied()
+	}
+}
+
+pub(crate) fn is_gas_building(building: UnitTypeId) -> bool {
+	matches!(
+		building,
+		UnitTypeId::Refinery
+			| UnitTypeId::RefineryRich
+			| UnitTypeId::Assimilator
+			| UnitTypeId::AssimilatorRich
+			| UnitTypeId::Extractor
+			| UnitTypeId::ExtractorRich
+	)
+}
+
+fn needs_power(building: UnitTypeId) -> bool {
+	!matches!(
+		building,
+		UnitTypeId::Nexus | UnitTypeId::Pylon | UnitTypeId::Assimilator | UnitTypeId::AssimilatorRich
+	)
+}
+
+fn get(grid: &Array2<bool>, x: isize, y: isize) -> Option<bool> {
+	if x < 0 || y < 0 {
+		return None;
+	}
+	grid.get((x as usize, y as usize)).copied()
+}
+
+fn distance_to_segment(p: Point2, a: Point2, b: Point2) -> f32 {
+	let ab = b - a;
+	let len = ab.x * ab.x + ab.y * ab.y;
+	if len == 0.0 {
+		return p.distance(a);
+	}
+	let t = (((p - a).x * ab.x + (p - a).y * ab.y) / len).clamp(0.0, 1.0);
+	p.distance(a + ab * t)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::{
+		game_data::{AbilityData, AbilityTarget, Attribute, UnitTypeData},
+		ids::AbilityId,
+		layout::BaseLayout,
+		pixel_map::Pixel,
+		unit::{
+			tests::{proto, type_data, with_data},
+			DataForUnit,
+		},
+	};
+
+	fn ability_data(id: AbilityId, radius: f32) -> AbilityData {
+		AbilityData {
+			id,
+			link_name: String::new(),
+			link_index: 0,
+			button_name: None,
+			friendly_name: None,
+			hotkey: None,
+			remaps_to_ability_id: None,
+			available: true,
+			target: AbilityTarget::Point,
+			allow_minimap: false,
+			allow_autocast: false,
+			is_building: true,
+			footprint_radius: Some(radius),
+			is_instant_placement: false,
+			cast_range: None,
+		}
+	}
+
+	fn unit_type(id: UnitTypeId, ability: AbilityId, race: Race) -> UnitTypeData {
+		UnitTypeData {
+			ability: Some(ability),
+			race,
+			..type_data(id)
+		}
+	}
+
+	/// Data for a few buildings of every race.
+	pub(crate) fn game_data() -> GameData {
+		let mut game_data = GameData::default();
+		for (building, ability, radius, race) in [
+			(
+				UnitTypeId::SupplyDepot,
+				AbilityId::TerranBuildSupplyDepot,
+				1.0,
+				Race::Terran,
+			),
+			(
+				UnitTypeId::Barracks,
+				AbilityId::TerranBuildBarracks,
+				1.5,
+				Race::Terran,
+			),
+			(
+				UnitTypeId::CommandCenter,
+				AbilityId::TerranBuildCommandCenter,
+				2.5,
+				Race::Terran,
+			),
+			(
+				UnitTypeId::MissileTurret,
+				AbilityId::TerranBuildMissileTurret,
+				1.0,
+				Race::Terran,
+			),
+			(
+				UnitTypeId::Pylon,
+				AbilityId::ProtossBuildPylon,
+				1.0,
+				Race::Protoss,
+			),
+			(
+				UnitTypeId::Gateway,
+				AbilityId::ProtossBuildGateway,
+				1.5,
+				Race::Protoss,
+			),
+			(
+				UnitTypeId::SpawningPool,
+				AbilityId::ZergBuildSpawningPool,
+				1.5,
+				Race::Zerg,
+			),
+			(
+				UnitTypeId::Hatchery,
+				AbilityId::ZergBuildHatchery,
+				2.5,
+				Race::Zerg,
+			),
+		] {
+			game_data.abilities.insert(ability, ability_data(ability, radius));
+			game_data
+				.units
+				.insert(building, unit_type(building, ability, race));
+		}
+		game_data
+	}
+
+	/// Empty open grid of given size with data from [`game_data`].
+	pub(crate) fn grid(width: usize, height: usize) -> PlacementGrid {
+		let dim = (width, height);
+		PlacementGrid {
+			game_data: Rs::new(game_data()),
+			blocked: Array2::from_elem(dim, false),
+			creep: Array2::from_elem(dim, false),
+			resources: Array2::from_elem(dim, false),
+			mineral_lines: Array2::from_elem(dim, false),
+			..Default::default()
+		}
+	}
+
+	pub(crate) fn block(grid: &mut PlacementGrid, pos: Point2, width: usize, height: usize) {
+		fill_footprint(&mut grid.blocked, pos, width, height, 0);
+	}
+
+	pub(crate) fn fill_mineral_line(grid: &mut PlacementGrid, pos: Point2, width: usize, height: usize) {
+		fill_footprint(&mut grid.mineral_lines, pos, width, height, 0);
+	}
+
+	#[test]
+	fn align_to_building_size() {
+		assert_eq!(
+			PlacementGrid::align(Point2::new(10.3, 20.8), 3),
+			Point2::new(10.5, 20.5)
+		);
+		assert_eq!(
+			PlacementGrid::align(Point2::new(10.3, 20.8), 2),
+			Point2::new(10.0, 21.0)
+		);
+		assert_eq!(
+			PlacementGrid::align(Point2::new(10.5, 20.5), 5),
+			Point2::new(10.5, 20.5)
+		);
+	}
+
+	#[test]
+	fn distance_to_segment_ends_and_middle() {
+		let a = Point2::new(0.0, 0.0);
+		let b = Point2::new(10.0, 0.0);
+		assert!((distance_to_segment(Point2::new(5.0, 3.0), a, b) - 3.0).abs() < 1e-5);
+		assert!((distance_to_segment(Point2::new(-3.0, 4.0), a, b) - 5.0).abs() < 1e-5);
+		assert!((distance_to_segment(Point2::new(13.0, 4.0), a, b) - 5.0).abs() < 1e-5);
+		assert!((distance_to_segment(Point2::new(3.0, 4.0), a, a) - 5.0).abs() < 1e-5);
+	}
+
+	#[test]
+	fn building_sizes() {
+		let grid = grid(8, 8);
+		assert_eq!(grid.building_size(UnitTypeId::SupplyDepot), Some(2));
+		assert_eq!(grid.building_size(UnitTypeId::Gateway), Some(3));
+		assert_eq!(grid.building_size(UnitTypeId::BarracksTechLab), Some(2));
+		assert_eq!(grid.building_size(UnitTypeId::Marine), None);
+	}
+
+	#[test]
+	fn can_place_on_free_cells_only() {
+		let mut grid = grid(32, 32);
+		let pos = Point2::new(10.5, 10.5);
+		assert!(grid.can_place(UnitTypeId::Barracks, pos));
+		assert!(!grid.can_place(UnitTypeId::Barracks, Point2::new(0.5, 0.5)));
+		assert!(!grid.can_place(UnitTypeId::Marine, pos));
+
+		grid.occupy(UnitTypeId::Barracks, pos);
+		assert!(!grid.can_place(UnitTypeId::Barracks, pos));
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, Point2::new(12.0, 12.0)));
+		assert!(grid.can_place(UnitTypeId::SupplyDepot, Point2::new(13.0, 13.0)));
+	}
+
+	#[test]
+	fn can_place_respects_mineral_lines() {
+		let mut grid = grid(32, 32);
+		fill_mineral_line(&mut grid, Point2::new(10.5, 10.5), 1, 1);
+		let pos = Point2::new(11.0, 11.0);
+		assert!(grid.is_mineral_line(pos, 2));
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, pos));
+		assert!(!grid.can_place_addon(Point2::new(8.5, 11.5)));
+
+		grid.exclude_mineral_lines = false;
+		assert!(grid.can_place(UnitTypeId::SupplyDepot, pos));
+		assert!(grid.can_place_addon(Point2::new(8.5, 11.5)));
+	}
+
+	#[test]
+	fn defense_slots_are_removed_from_mineral_lines() {
+		let mut grid = grid(32, 32);
+		fill_mineral_line(&mut grid, Point2::new(12.0, 12.0), 6, 6);
+		let slot = Point2::new(11.0, 11.0);
+		assert!(!grid.can_place(UnitTypeId::MissileTurret, slot));
+
+		let layout = Layout {
+			bases: vec![BaseLayout {
+				defense: vec![slot],
+				..Default::default()
+			}],
+			..Default::default()
+		};
+		grid.free_defense_slots(&layout);
+		assert!(grid.can_place(UnitTypeId::MissileTurret, slot));
+		assert!(!grid.can_place(UnitTypeId::MissileTurret, slot.offset(1.0, 0.0)));
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, Point2::new(13.0, 13.0)));
+	}
+
+	#[test]
+	fn can_place_respects_creep_and_power() {
+		let mut grid = grid(32, 32);
+		let pos = Point2::new(10.5, 10.5);
+		assert!(!grid.can_place(UnitTypeId::SpawningPool, pos));
+		assert!(grid.can_place(UnitTypeId::Hatchery, pos));
+		assert!(grid.can_place(UnitTypeId::Pylon, Point2::new(10.0, 10.0)));
+		assert!(!grid.can_place(UnitTypeId::Gateway, pos));
+
+		grid.power.push((Point2::new(12.0, 12.0), 6.5));
+		assert!(grid.can_place(UnitTypeId::Gateway, pos));
+
+		fill_footprint(&mut grid.creep, Point2::new(11.0, 11.0), 6, 6, 0);
+		assert!(grid.can_place(UnitTypeId::SpawningPool, pos));
+		assert!(!grid.can_place(UnitTypeId::Gateway, pos));
+		assert!(!grid.can_place(UnitTypeId::Barracks, pos));
+	}
+
+	#[test]
+	fn townhalls_keep_distance_to_resources() {
+		let mut grid = grid(32, 32);
+		fill_footprint(&mut grid.resources, Point2::new(17.0, 10.5), 2, 1, 0);
+		assert!(!grid.can_place(UnitTypeId::CommandCenter, Point2::new(11.5, 10.5)));
+		assert!(grid.can_place(UnitTypeId::CommandCenter, Point2::new(10.5, 10.5)));
+	}
+
+	#[test]
+	fn gas_buildings_need_free_geysers() {
+		let mut grid = grid(32, 32);
+		let geyser = Point2::new(10.5, 10.5);
+		grid.free_geysers.push(geyser);
+		assert!(grid.can_place(UnitTypeId::Refinery, geyser));
+		assert!(!grid.can_place(UnitTypeId::Refinery, Point2::new(20.5, 20.5)));
+
+		grid.occupy(UnitTypeId::Assimilator, geyser);
+		assert!(grid.free_geysers().is_empty());
+		assert!(!grid.can_place(UnitTypeId::Extractor, geyser));
+	}
+
+	#[test]
+	fn find_closest_placement() {
+		let mut grid = grid(32, 32);
+		let near = Point2::new(10.5, 10.5);
+		let options = PlacementOptions {
+			confirm: false,
+			..Default::default()
+		};
+		assert_eq!(
+			grid.find_placement(UnitTypeId::Barracks, near, options),
+			Some(near)
+		);
+
+		grid.occupy(UnitTypeId::Barracks, near);
+		let pos = grid.find_placement(UnitTypeId::Barracks, near, options).unwrap();
+		assert!(grid.can_place(UnitTypeId::Barracks, pos));
+		assert!(pos.distance(near) <= 4.0 * 2f32.sqrt() + 1e-3);
+	}
+
+	#[test]
+	fn update_keeps_mineral_lines_and_refreshes_structures_and_creep() {
+		let mut bot = Bot::default();
+		let mut data = game_data();
+		data.units
+			.get_mut(&UnitTypeId::Barracks)
+			.unwrap()
+			.attributes
+			.push(Attribute::Structure);
+		bot.game_data = Rs::new(data);
+		bot.game_info.placement_grid = Array2::from_elem((32, 32), Pixel::Empty);
+		*bot.state.observation.raw.creep.write_lock() = Array2::from_elem((32, 32), Pixel::Set);
+
+		let mut grid = PlacementGrid::new(&bot);
+		fill_mineral_line(&mut grid, Point2::new(20.0, 20.0), 2, 2);
+		let depot = Point2::new(10.0, 10.0);
+		assert!(grid.can_place(UnitTypeId::SupplyDepot, depot));
+
+		let data = Rs::new(DataForUnit {
+			game_data: Rs::clone(&bot.game_data),
+			..Default::default()
+		});
+		let barracks = with_data(&data, &proto(1, UnitTypeId::Barracks, (10.5, 10.5)));
+		bot.units.all.push(barracks);
+		grid.update(&bot);
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, depot));
+		assert!(grid.can_place(UnitTypeId::SupplyDepot, Point2::new(14.0, 10.0)));
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, Point2::new(20.0, 20.0)));
+
+		bot.units.all.remove(1);
+		*bot.state.observation.raw.creep.write_lock() = Array2::from_elem((32, 32), Pixel::Empty);
+		grid.update(&bot);
+		assert!(grid.can_place(UnitTypeId::SpawningPool, Point2::new(10.5, 10.5)));
+		assert!(!grid.can_place(UnitTypeId::SupplyDepot, depot));
+	}
+}
@@ -0,0 +1,450 @@
+//! Recognition of opponent's openings and strategies.
+//!
+//! Rules are checked automatically every step, detected strategies can be accessed through
+//! [`scouting`](crate::bot::Bot::scouting) field and are reported with
+//! [`StrategyDetected`](crate::Event::StrategyDetected) event.
+
+use crate::{
+	bot::Bot,
+	distance::Distance,
+	ids::{UnitTypeId, UpgradeId},
+	memory::RememberedUnit,
+};
+use indexmap::IndexMap;
+use rustc_hash::FxHasher;
+use std::{fmt, hash::BuildHasherDefault};
+
+type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+
+/// Opening or strategy of opponent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strategy {
+	/// Production structures built close to your base.
+	Proxy,
+	/// Spawning pool started very early or early zerglings.
+	EarlyPool,
+	/// Cannons or pylons built near your base.
+	CannonRush,
+	/// Second townhall built early.
+	FastExpand,
+	/// Terran going for factory units.
+	Mech,
+	/// Opponent going for air units.
+	Air,
+	/// Strategy detected by custom rule.
+	Custom(&'static str),
+}
+
+/// Rule used to detect opponent's strategy.
+pub struct Rule {
+	/// Strategy, which will be detected when condition of the rule is met.
+	pub strategy: Strategy,
+	/// Rule is checked only until given in-game time in seconds (or always if `None`).
+	pub until: Option<f32>,
+	condition: Box<dyn Fn(&Bot) -> bool + Send + Sync>,
+}
+impl Rule {
+	/// Constructs new rule, which detects given strategy when condition returns `true`.
+	pub fn new<F>(strategy: Strategy, condition: F) -> Self
+	where
+		F: Fn(&Bot) -> bool + Send + Sync + 'static,
+	{
+		Self {
+			strategy,
+			until: None,
+			condition: Box::new(condition),
+		}
+	}
+	/// Sets in-game time in seconds, after which the rule won't be checked.
+	pub fn until(mut self, time: f32) -> Self {
+		self.until = Some(time);
+		self
+	}
+	/// Checks if condition of the rule is met.
+	pub fn check(&self, bot: &Bot) -> bool {
+		self.until.map_or(true, |time| bot.time <= time) && (self.condition)(bot)
+	}
+}
+impl fmt::Debug for Rule {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Rule")
+			.field("strategy", &self.strategy)
+			.field("until", &self.until)
+			.finish()
+	}
+}
+
+/// Classifies opponent's openings and strategies using set of timing rules.
+///
+/// Has built-in rules for all [`Strategy`] variants except `Custom`,
+/// more rules can be registered with [`add_rule`](Self::add_rule).
+///
+/// # Examples
+/// Detect dark templars rush:
+/// ```
+/// self.scouting.add_rule(
+/// 	Rule::new(Strategy::Custom("DarkTemplars"), |bot| {
+/// 		bot.enemy_memory.count(UnitTypeId::DarkShrine) > 0
+/// 	})
+/// 	.until(420.0),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Scouting {
+	rules: Vec<Rule>,
+	detected: FxIndexMap<Strategy, f32>,
+}
+impl Default for Scouting {
+	fn default() -> Self {
+		Self {
+			rules: vec![
+				Rule::new(Strategy::Proxy, is_proxy).until(240.0),
+				Rule::new(Strategy::EarlyPool, is_early_pool).until(150.0),
+				Rule::new(Strategy::CannonRush, is_cannon_rush).until(240.0),
+				Rule::new(Strategy::FastExpand, is_fast_expand).until(120.0),
+				Rule::new(Strategy::Mech, is_mech).until(420.0),
+				Rule::new(Strategy::Air, is_air).until(480.0),
+			],
+			detected: Default::default(),
+		}
+	}
+}
+
+impl Scouting {
+	/// Constructs scouting without any rules.
+	pub fn empty() -> Self {
+		Self {
+			rules: vec![],
+			detected: Default::default(),
+		}
+	}
+
+	pub(crate) fn update(&mut self, bot: &Bot) -> Vec<Strategy> {
+		let mut detected = vec![];
+		for rule in &self.rules {
+			if !self.detected.contains_key(&rule.strategy) && rule.check(bot) {
+				self.detected.insert(rule.strategy, bot.time);
+				detected.push(rule.strategy);
+			}
+		}
+		detected
+	}
+
+	/// Registers new rule.
+	pub fn add_rule(&mut self, rule: Rule) {
+		self.rules.push(rule);
+	}
+	/// Removes all rules detecting given strategy.
+	pub fn remove_rules(&mut self, strategy: Strategy) {
+		self.rules.retain(|r| r.strategy != strategy);
+	}
+	/// Returns all registered rules.
+	pub fn rules(&self) -> &[Rule] {
+		&self.rules
+	}
+
+	/// Checks if given strategy was detected.
+	pub fn is_detected(&self, strategy: Strategy) -> bool {
+		self.detected.contains_key(&strategy)
+	}
+	/// Returns in-game time in seconds when given strategy was detected.
+	pub fn detected_at(&self, strategy: Strategy) -> Option<f32> {
+		self.detected.get(&strategy).copied()
+	}
+	/// Returns all detected strategies with time of detection in order they were detected.
+	pub fn detected(&self) -> impl Iterator<Item = (Strategy, f32)> + '_ {
+		self.detected.iter().map(|(s, t)| (*s, *t))
+	}
+	/// Forgets all detected strategies.
+	pub fn reset(&mut self) {
+		self.detected.clear();
+	}
+}
+
+fn count_of(bot: &Bot, types: &[UnitTypeId]) -> usize {
+	bot.enemy_memory
+		.iter()
+		.filter(|u| types.contains(&u.type_id()))
+		.count()
+}
+
+fn is_near_base(bot: &Bot, u: &RememberedUnit, distance: f32) -> bool {
+	bot.owned_expansions()
+		.any(|exp| u.position.is_closer(distance, exp.loc))
+		|| u.position.is_closer(distance, bot.start_location)
+}
+
+fn is_proxy(bot: &Bot) -> bool {
+	bot.enemy_memory.structures().any(|u| {
+		matches!(
+			u.type_id(),
+			UnitTypeId::Barracks
+				| UnitTypeId::Factory
+				| UnitTypeId::Starport
+				| UnitTypeId::Gateway
+				| UnitTypeId::WarpGate
+				| UnitTypeId::RoboticsFacility
+				| UnitTypeId::Stargate
+		) && u.position.distance_squared(bot.start_location) < u.position.distance_squared(bot.enemy_start)
+	})
+}
+
+fn is_early_pool(bot: &Bot) -> bool {
+	bot.enemy_memory.structures().any(|u| {
+		u.type_id() == UnitTypeId::SpawningPool && (bot.time < 50.0 || (u.unit.is_ready() && bot.time < 90.0))
+	}) || (bot.time < 120.0 && count_of(bot, &[UnitTypeId::Zergling]) > 0)
+}
+
+fn is_cannon_rush(bot: &Bot) -> bool {
+	bot.enemy_memory.structures().any(|u| {
+		matches!(
+			u.type_id(),
+			UnitTypeId::PhotonCannon | UnitTypeId::Forge | UnitTypeId::Pylon
+		) && is_near_base(bot, u, 25.0)
+	})
+}
+
+fn is_fast_expand(bot: &Bot) -> bool {
+	bot.enemy_memory
+		.structures()
+		.filter(|u| u.unit.is_townhall())
+		.count()
+		> 1
+}
+
+fn is_mech(bot: &Bot) -> bool {
+	count_of(bot, &[UnitTypeId::Factory, UnitTypeId::FactoryFlying]) > 1
+		|| count_of(bot, &[UnitTypeId::Armory]) > 0
+		|| bot.enemy_has_upgrade(UpgradeId::TerranVehicleWeaponsLevel1)
+		|| count_of(
+			bot,
+			&[
+				UnitTypeId::SiegeTank,
+				UnitTypeId::SiegeTankSieged,
+				UnitTypeId::Hellion,
+				UnitTypeId::HellionTank,
+				UnitTypeId::Cyclone,
+				UnitTypeId::WidowMine,
+				UnitTypeId::WidowMineBurrowed,
+				UnitTypeId::Thor,
+			],
+		) > 3
+}
+
+fn is_air(bot: &Bot) -> bool {
+	count_of(
+		bot,
+		&[
+			UnitTypeId::Stargate,
+			UnitTypeId::Spire,
+			UnitTypeId::GreaterSpire,
+			UnitTypeId::FusionCore,
+		],
+	) > 0 || count_of(bot, &[UnitTypeId::Starport, UnitTypeId::StarportFlying]) > 1
+		|| [
+			UpgradeId::TerranShipWeaponsLevel1,
+			UpgradeId::ProtossAirWeaponsLevel1,
+			UpgradeId::ZergFlyerWeaponsLevel1,
+		]
+		.iter()
+		.any(|u| bot.enemy_has_upgrade(*u))
+		|| count_of(
+			bot,
+			&[
+				UnitTypeId::VikingFighter,
+				UnitTypeId::Banshee,
+				UnitTypeId::Liberator,
+				UnitTypeId::Battlecruiser,
+				UnitTypeId::Phoenix,
+				UnitTypeId::VoidRay,
+				UnitTypeId::Oracle,
+				UnitTypeId::Tempest,
+				UnitTypeId::Carrier,
+				UnitTypeId::Mutalisk,
+				UnitTypeId::Corruptor,
+				UnitTypeId::BroodLord,
+			],
+		) > 1
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::LockOwned,
+		consts::FRAMES_PER_SECOND,
+		geometry::Point2,
+		unit::tests::{proto, structure_data, with_data},
+	};
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+	use std::mem;
+
+	fn custom(bot: &Bot) -> bool {
+		bot.time > 10.0
+	}
+
+	#[test]
+	fn rules_are_checked_until_time() {
+		let mut bot = Bot::default();
+		let rule = Rule::new(Strategy::Custom("Test"), custom).until(60.0);
+		assert!(!rule.check(&bot));
+		bot.time = 30.0;
+		assert!(rule.check(&bot));
+		bot.time = 90.0;
+		assert!(!rule.check(&bot));
+	}
+
+	#[test]
+	fn strategy_is_detected_once() {
+		let mut bot = Bot::default();
+		let mut scouting = Scouting::empty();
+		scouting.add_rule(Rule::new(Strategy::Custom("Test"), custom));
+
+		assert!(scouting.update(&bot).is_empty());
+		bot.time = 20.0;
+		assert_eq!(scouting.update(&bot), vec![Strategy::Custom("Test")]);
+		bot.time = 30.0;
+		assert!(scouting.update(&bot).is_empty());
+		assert_eq!(scouting.detected_at(Strategy::Custom("Test")), Some(20.0));
+
+		scouting.reset();
+		assert!(!scouting.is_detected(Strategy::Custom("Test")));
+		scouting.remove_rules(Strategy::Custom("Test"));
+		assert!(scouting.rules().is_empty());
+	}
+
+	#[test]
+	fn default_rules() {
+		assert_eq!(Scouting::default().rules().len(), 6);
+		assert!(Scouting::empty().rules().is_empty());
+	}
+
+	/// Bot with start locations at (20, 20) and (100, 100).
+	fn bot() -> Bot {
+		let mut bot = Bot::default();
+		bot.start_location = Point2::new(20.0, 20.0);
+		bot.enemy_start = Point2::new(100.0, 100.0);
+		bot
+	}
+	/// Makes bot see given enemy units at given time in seconds.
+	fn observe(bot: &mut Bot, time: f32, units: &[(u64, UnitTypeId, (f32, f32))]) {
+		let data = structure_data(&[
+			UnitTypeId::Barracks,
+			UnitTypeId::SpawningPool,
+			UnitTypeId::Pylon,
+			UnitTypeId::Nexus,
+			UnitTypeId::Factory,
+			UnitTypeId::Armory,
+			UnitTypeId::Starport,
+			UnitTypeId::Stargate,
+		]);
+		bot.time = time;
+		bot.state
+			.observation
+			.game_loop
+			.set_locked((time * FRAMES_PER_SECOND) as u32);
+		bot.units.enemy.all = units
+			.iter()
+			.map(|(tag, type_id, pos)| {
+				let mut u = proto(*tag, *type_id, *pos);
+				u.set_alliance(ProtoAlliance::Enemy);
+				with_data(&data, &u)
+			})
+			.collect();
+		let mut memory = mem::take(&mut bot.enemy_memory);
+		memory.update(bot);
+		bot.enemy_memory = memory;
+	}
+	/// Checks given rule after the bot has seen given units.
+	fn check(rule: fn(&Bot) -> bool, time: f32, units: &[(u64, UnitTypeId, (f32, f32))]) -> bool {
+		let mut bot = bot();
+		observe(&mut bot, time, units);
+		rule(&bot)
+	}
+
+	#[test]
+	fn proxy() {
+		assert!(check(is_proxy, 60.0, &[(1, UnitTypeId::Barracks, (40.0, 40.0))]));
+		assert!(!check(is_proxy, 60.0, &[(1, UnitTypeId::Barracks, (90.0, 90.0))]));
+	}
+
+	#[test]
+	fn early_pool() {
+		assert!(check(
+			is_early_pool,
+			40.0,
+			&[(1, UnitTypeId::SpawningPool, (100.0, 95.0))]
+		));
+		assert!(check(
+			is_early_pool,
+			100.0,
+			&[(1, UnitTypeId::Zergling, (60.0, 60.0))]
+		));
+		assert!(!check(
+			is_early_pool,
+			100.0,
+			&[(1, UnitTypeId::SpawningPool, (100.0, 95.0))]
+		));
+	}
+
+	#[test]
+	fn cannon_rush() {
+		assert!(check(
+			is_cannon_rush,
+			90.0,
+			&[(1, UnitTypeId::Pylon, (30.0, 25.0))]
+		));
+		assert!(!check(
+			is_cannon_rush,
+			90.0,
+			&[(1, UnitTypeId::Pylon, (60.0, 60.0))]
+		));
+	}
+
+	#[test]
+	fn fast_expand() {
+		let main = (1, UnitTypeId::Nexus, (100.5, 100.5));
+		let natural = (2, UnitTypeId::Nexus, (80.5, 100.5));
+		assert!(check(is_fast_expand, 90.0, &[main, natural]));
+		assert!(!check(is_fast_expand, 90.0, &[main]));
+	}
+
+	#[test]
+	fn mech() {
+		assert!(check(is_mech, 200.0, &[(1, UnitTypeId::Armory, (95.0, 95.0))]));
+		assert!(!check(is_mech, 200.0, &[(1, UnitTypeId::Factory, (95.0, 95.0))]));
+
+		let bot = bot();
+		bot.enemy_upgrades().insert(UpgradeId::TerranVehicleWeaponsLevel1);
+		assert!(is_mech(&bot));
+	}
+
+	#[test]
+	fn air() {
+		assert!(check(is_air, 200.0, &[(1, UnitTypeId::Stargate, (95.0, 95.0))]));
+		assert!(!check(is_air, 200.0, &[(1, UnitTypeId::Starport, (95.0, 95.0))]));
+	}
+
+	#[test]
+	fn default_rules_detect_strategies_once() {
+		let mut bot = bot();
+		let mut scouting = Scouting::default();
+		let barracks = (1, UnitTypeId::Barracks, (40.0, 40.0));
+
+		observe(&mut bot, 60.0, &[barracks]);
+		assert_eq!(scouting.update(&bot), vec![Strategy::Proxy]);
+		observe(
+			&mut bot,
+			70.0,
+			&[barracks, (2, UnitTypeId::Zergling, (60.0, 60.0))],
+		);
+		assert_eq!(scouting.update(&bot), vec![Strategy::EarlyPool]);
+		observe(&mut bot, 80.0, &[barracks]);
+		assert!(scouting.update(&bot).is_empty());
+
+		assert_eq!(
+			scouting.detected().collect::<Vec<_>>(),
+			vec![(Strategy::Proxy, 60.0), (Strategy::EarlyPool, 70.0)]
+		);
+	}
+}
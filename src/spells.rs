@@ -0,0 +1,566 @@
+//! Spellcasting helpers.
+//!
+//! [`Spell`] holds energy cost, cast range and radius of the spell with its target filter,
+//! so the most valuable target or cast point can be found among units:
+//! ```
+//! let storm = Spell::get(AbilityId::PsiStormPsiStorm).unwrap();
+//! let enemies = &self.units.enemy.all;
+//! for ht in &self.units.my.units.of_type(UnitTypeId::HighTemplar) {
+//!     if let Some((pos, value)) = storm.best_point(ht, enemies, Some(&self.units.my.all), 0.0) {
+//!         if value >= 200.0 {
+//!             ht.command(storm.ability, Target::Pos(pos), false);
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! [`SpellCaster`] is an opt-in component, which also tracks cooldowns of casters between steps
+//! and doesn't let several casters waste spells on the same targets:
+//! ```
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot {
+//!     spells: SpellCaster,
+//! }
+//!
+//! impl Player for MyBot {
+//!     fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+//!         self.spells.step(&self._bot);
+//!
+//!         let snipe = Spell::get(AbilityId::EffectGhostSnipe).unwrap();
+//!         for ghost in &self.units.my.units.of_type(UnitTypeId::Ghost) {
+//!             self.spells.cast_best(&self._bot, ghost, &snipe, 100.0, 0.0);
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use crate::{
+	action::Target,
+	bot::Bot,
+	consts::FRAMES_PER_SECOND,
+	distance::*,
+	geometry::Point2,
+	ids::{AbilityId, BuffId},
+	unit::Unit,
+	units::Units,
+};
+use rustc_hash::FxHashMap;
+
+/// Units spell is cast on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpellTarget {
+	/// Enemy units.
+	Enemy,
+	/// Own units.
+	Own,
+}
+
+/// Data of the spell and how valuable its targets are.
+#[derive(Debug, Clone, Copy)]
+pub struct Spell {
+	/// Ability used to cast the spell.
+	pub ability: AbilityId,
+	/// Energy cost.
+	pub energy: u32,
+	/// Maximum distance from caster to target.
+	pub range: f32,
+	/// Radius of affected area, `None` for spells cast on a single unit.
+	pub radius: Option<f32>,
+	/// Time in seconds until caster can use the spell again.
+	pub cooldown: f32,
+	/// Units spell is cast on.
+	pub target: SpellTarget,
+	/// Spell also affects own units in the area.
+	pub friendly_fire: bool,
+	/// Spell affects ground units.
+	pub ground: bool,
+	/// Spell affects air units.
+	pub air: bool,
+	/// Buff of units already affected by the spell, such units are skipped.
+	pub buff: Option<BuffId>,
+	/// Checks if spell can be used on given unit.
+	pub filter: fn(&Unit) -> bool,
+	/// Returns value of affecting given unit with the spell.
+	pub value: fn(&Unit) -> f32,
+}
+
+impl Spell {
+	/// Returns data of the spell cast with given ability or `None` if it's not supported.
+	///
+	/// Supported spells: psionic storm, EMP, feedback, chrono boost, fungal growth, blinding cloud,
+	/// transfusion, snipe and yamato cannon.
+	pub fn get(ability: AbilityId) -> Option<Self> {
+		let spell =
+			|energy, range, radius, cooldown, filter: fn(&Unit) -> bool, value: fn(&Unit) -> f32| Spell {
+				ability,
+				energy,
+				range,
+				radius,
+				cooldown,
+				target: SpellTarget::Enemy,
+				friendly_fire: false,
+				ground: true,
+				air: true,
+				buff: None,
+				filter,
+				value,
+			};
+
+		Some(match ability {
+			AbilityId::PsiStormPsiStorm => Spell {
+				friendly_fire: true,
+				..spell(75, 9.0, Some(1.5), 1.43, is_not_structure, storm_value)
+			},
+			AbilityId::EMPEMP => Spell {
+				friendly_fire: true,
+				..spell(75, 10.0, Some(1.5), 0.0, has_shield_or_energy, emp_value)
+			},
+			AbilityId::FeedbackFeedback => spell(50, 10.0, None, 0.0, has_energy, feedback_value),
+			AbilityId::EffectChronoBoostEnergyCost => Spell {
+				target: SpellTarget::Own,
+				buff: Some(BuffId::ChronoBoostEnergyCost),
+				..spell(50, f32::INFINITY, None, 0.0, is_producing, |_| 1.0)
+			},
+			AbilityId::FungalGrowthFungalGrowth => Spell {
+				buff: Some(BuffId::FungalGrowth),
+				..spell(75, 10.0, Some(2.25), 0.0, is_not_structure, fungal_value)
+			},
+			AbilityId::BlindingCloudBlindingCloud => Spell {
+				friendly_fire: true,
+				air: false,
+				buff: Some(BuffId::BlindingCloud),
+				..spell(100, 11.0, Some(2.0), 0.0, is_ranged, |u| u.ground_dps())
+			},
+			AbilityId::TransfusionTransfusion => Spell {
+				target: SpellTarget::Own,
+				buff: Some(BuffId::Transfusion),
+				..spell(50, 7.0, None, 1.0, is_transfusable, transfusion_value)
+			},
+			AbilityId::EffectGhostSnipe => spell(50, 10.0, None, 1.43, is_snipeable, snipe_value),
+			AbilityId::YamatoYamatoGun => spell(0, 10.0, None, 71.0, |_| true, yamato_value),
+			_ => return None,
+		})
+	}
+
+	/// Checks if given caster has enough energy for the spell.
+	pub fn has_energy(&self, caster: &Unit) -> bool {
+		self.energy == 0 || caster.energy().is_some_and(|energy| energy >= self.energy)
+	}
+	/// Checks if the spell can affect given unit.
+	pub fn affects(&self, u: &Unit) -> bool {
+		(if u.is_flying() { self.air } else { self.ground })
+			&& self.buff.map_or(true, |buff| !u.has_buff(buff))
+			&& (self.filter)(u)
+	}
+	/// Checks if given position is in cast range of given caster.
+	pub fn in_range<P: Into<Point2>>(&self, caster: &Unit, pos: P, gap: f32) -> bool {
+		let pos: Point2 = pos.into();
+		caster.is_closer(self.range + caster.radius() + gap, pos)
+	}
+
+	/// Returns value of casting area spell at given position:
+	/// sum of values of affected targets minus values of affected allies in case of friendly fire.
+	pub fn value_at(&self, pos: Point2, targets: &Units, allies: Option<&Units>) -> f32 {
+		let radius = self.radius.unwrap_or(0.0);
+		let sum = |units: &Units| -> f32 {
+			units
+				.iter()
+				.filter(|u| u.is_closer(radius + u.radius(), pos) && self.affects(u))
+				.map(|u| (self.value)(u))
+				.sum()
+		};
+
+		let mut value = sum(targets);
+		if self.friendly_fire {
+			if let Some(allies) = allies {
+				value -= sum(allies);
+			}
+		}
+		value
+	}
+	/// Returns the most valuable unit in range of given caster with its value.
+	///
+	/// Works only for spells cast on a single unit.
+	pub fn best_target<'a>(&self, caster: &Unit, targets: &'a Units, gap: f32) -> Option<(&'a Unit, f32)> {
+		if self.radius.is_some() {
+			return None;
+		}
+		targets
+			.iter()
+			.filter(|t| t.tag() != caster.tag() && self.affects(t))
+			.filter(|t| caster.is_closer(self.range + caster.radius() + t.radius() + gap, *t))
+			.map(|t| (t, (self.value)(t)))
+			.filter(|(_, value)| *value > 0.0)
+			.max_by(|(_, v1), (_, v2)| cmp(v1, v2))
+	}
+	/// Returns the most valuable point in range of given caster with its value.
+	///
+	/// Positions of targets and middles between close targets are checked as cast points,
+	/// and points out of range are moved closer to the caster.
+	/// Allies are taken into account only for spells with friendly fire.
+	///
+	/// Works only for area spells.
+	pub fn best_point(
+		&self,
+		caster: &Unit,
+		targets: &Units,
+		allies: Option<&Units>,
+		gap: f32,
+	) -> Option<(Point2, f32)> {
+		let radius = self.radius?;
+		let reach = self.range + caster.radius() + gap;
+		let near = targets
+			.iter()
+			.filter(|t| self.affects(t) && caster.is_closer(reach + radius + t.radius(), *t))
+			.map(|t| t.position())
+			.collect::<Vec<_>>();
+
+		let mut candidates = near.clone();
+		for (i, p1) in near.iter().enumerate() {
+			for p2 in &near[i + 1..] {
+				if p1.is_closer(radius * 2.0, *p2) {
+					candidates.push((*p1 + *p2) / 2.0);
+				}
+			}
+		}
+
+		let pos = caster.position();
+		candidates
+			.into_iter()
+			.map(|p| {
+				if pos.is_closer(reach, p) {
+					p
+				} else {
+					pos.towards(p, reach)
+				}
+			})
+			.map(|p| (p, self.value_at(p, targets, allies)))
+			.filter(|(_, value)| *value > 0.0)
+			.max_by(|(_, v1), (_, v2)| cmp(v1, v2))
+	}
+	/// Returns target of the spell with its value,
+	/// using [`best_point`](Self::best_point) for area spells
+	/// and [`best_target`](Self::best_target) for others.
+	pub fn best(
+		&self,
+		caster: &Unit,
+		targets: &Units,
+		allies: Option<&Units>,
+		gap: f32,
+	) -> Option<(Target, f32)> {
+		if self.radius.is_some() {
+			self.best_point(caster, targets, allies, gap)
+				.map(|(pos, value)| (Target::Pos(pos), value))
+		} else {
+			self.best_target(caster, targets, gap)
+				.map(|(t, value)| (Target::Tag(t.tag()), value))
+		}
+	}
+}
+
+/// Casts spells and tracks cooldowns of own casters.
+///
+/// Cooldowns are tracked only for spells cast with [`cast`](Self::cast)
+/// or [`cast_best`](Self::cast_best), and protect casters from using the spell again
+/// while previous order isn't executed yet.
+#[derive(Default)]
+pub struct SpellCaster {
+	ready: FxHashMap<(u64, AbilityId), u32>,
+	casts: Vec<(AbilityId, Target, Option<f32>)>,
+}
+
+impl SpellCaster {
+	/// Constructs new caster.
+	pub fn new() -> Self {
+		Default::default()
+	}
+
+	/// Updates state of the caster, should be called every step.
+	pub fn step(&mut self, bot: &Bot) {
+		let game_loop = bot.state.observation.game_loop();
+		let units = &bot.units.my.all;
+		self.ready
+			.retain(|(tag, _), ready| *ready > game_loop && units.contains_tag(*tag));
+		self.casts.clear();
+	}
+
+	/// Returns game loops left until given unit can cast given spell again.
+	pub fn cooldown(&self, bot: &Bot, caster: u64, ability: AbilityId) -> u32 {
+		self.ready
+			.get(&(caster, ability))
+			.map_or(0, |ready| ready.saturating_sub(bot.state.observation.game_loop()))
+	}
+	/// Checks if given unit can cast the spell now: it has enough energy, ability is available,
+	/// spell isn't on cooldown and unit isn't casting it already.
+	pub fn can_cast(&self, bot: &Bot, caster: &Unit, spell: &Spell) -> bool {
+		spell.has_energy(caster)
+			&& caster.has_ability(spell.ability)
+			&& !caster.is_using(spell.ability)
+			&& self.cooldown(bot, caster.tag(), spell.ability) == 0
+	}
+	/// Checks if given unit is already targeted by the spell ordered this step.
+	pub fn is_targeted(&self, spell: &Spell, u: &Unit) -> bool {
+		self.casts.iter().any(|(ability, target, radius)| {
+			*ability == spell.ability
+				&& match target {
+					Target::Tag(tag) => *tag == u.tag(),
+					Target::Pos(pos) => radius.is_some_and(|radius| u.is_closer(radius + u.radius(), *pos)),
+					Target::None => false,
+				}
+		})
+	}
+
+	/// Orders given unit to cast the spell on given target and starts tracking its cooldown.
+	pub fn cast(&mut self, bot: &Bot, caster: &Unit, spell: &Spell, target: Target) {
+		caster.command(spell.ability, target, false);
+
+		let cooldown = ((spell.cooldown * FRAMES_PER_SECOND) as u32).max(bot.game_step());
+		self.ready.insert(
+			(caster.tag(), spell.ability),
+			bot.state.observation.game_loop() + cooldown,
+		);
+		self.casts.push((spell.ability, target, spell.radius));
+	}
+	/// Casts the spell with given unit on the best target if its value is at least `min_value`.
+	///
+	/// Enemies or own units are chosen as targets depending on [`Spell::target`],
+	/// and units already targeted by this spell in current step are skipped.
+	///
+	/// Returns target of the cast if it was ordered.
+	pub fn cast_best(
+		&mut self,
+		bot: &Bot,
+		caster: &Unit,
+		spell: &Spell,
+		min_value: f32,
+		gap: f32,
+	) -> Option<Target> {
+		if !self.can_cast(bot, caster, spell) {
+			return None;
+		}
+
+		let (targets, allies) = match spell.target {
+			SpellTarget::Enemy => (&bot.units.enemy.all, Some(&bot.units.my.all)),
+			SpellTarget::Own => (&bot.units.my.all, None),
+		};
+		let targets = targets.filter(|u| !self.is_targeted(spell, u));
+		let (target, value) = spell.best(caster, &targets, allies, gap)?;
+		if value < min_value {
+			return None;
+		}
+
+		self.cast(bot, caster, spell, target);
+		Some(target)
+	}
+}
+
+fn is_not_structure(u: &Unit) -> bool {
+	!u.is_structure()
+}
+fn has_energy(u: &Unit) -> bool {
+	u.energy().is_some_and(|energy| energy > 0)
+}
+fn has_shield_or_energy(u: &Unit) -> bool {
+	u.shield().is_some_and(|shield| shield > 0) || has_energy(u)
+}
+fn is_producing(u: &Unit) -> bool {
+	u.is_structure() && u.is_ready() && !u.is_idle()
+}
+fn is_ranged(u: &Unit) -> bool {
+	!u.is_structure() && u.real_ground_range() > 1.5
+}
+fn is_transfusable(u: &Unit) -> bool {
+	u.is_biological() && !u.is_structure() && missing_health(u) >= 50
+}
+fn is_snipeable(u: &Unit) -> bool {
+	u.is_biological() && !u.is_structure()
+}
+
+fn missing_health(u: &Unit) -> u32 {
+	u.health_max()
+		.unwrap_or(0)
+		.saturating_sub(u.health().unwrap_or(0))
+}
+/// Damage dealt to unit capped by its remaining hits.
+fn damage(u: &Unit, amount: u32) -> f32 {
+	u.hits().map_or(amount, |hits| hits.min(amount)) as f32
+}
+
+fn storm_value(u: &Unit) -> f32 {
+	damage(u, 80)
+}
+fn emp_value(u: &Unit) -> f32 {
+	(u.shield().unwrap_or(0).min(100) + u.energy().unwrap_or(0).min(100)) as f32
+}
+fn feedback_value(u: &Unit) -> f32 {
+	let energy = u.energy().unwrap_or(0);
+	energy as f32 + damage(u, energy / 2)
+}
+fn fungal_value(u: &Unit) -> f32 {
+	// Rooting is worth something by itself
+	damage(u, 30) + 10.0
+}
+fn transfusion_value(u: &Unit) -> f32 {
+	missing_health(u).min(125) as f32
+}
+fn snipe_value(u: &Unit) -> f32 {
+	damage(u, 170)
+}
+fn yamato_value(u: &Unit) -> f32 {
+	damage(u, 240)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::{LockOwned, Locked, Rs},
+		ids::UnitTypeId,
+		unit::{
+			tests::{combat_data, proto, with_data},
+			DataForUnit,
+		},
+	};
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+
+	fn templar(data: &Rs<DataForUnit>, tag: u64, energy: f32) -> Unit {
+		let mut u = proto(tag, UnitTypeId::HighTemplar, (5.0, 10.0));
+		u.set_energy(energy);
+		with_data(data, &u)
+	}
+	fn enemy(data: &Rs<DataForUnit>, tag: u64, pos: (f32, f32), health: f32, energy: f32) -> Unit {
+		let mut u = proto(tag, UnitTypeId::Zergling, pos);
+		u.set_alliance(ProtoAlliance::Enemy);
+		u.set_health(health);
+		u.set_energy(energy);
+		with_data(data, &u)
+	}
+	/// Clump of 3 zerglings with 35 health and one zergling apart.
+	fn zerglings(data: &Rs<DataForUnit>) -> Units {
+		[(14.0, 10.0), (14.5, 10.0), (14.0, 10.5), (14.0, 14.0)]
+			.into_iter()
+			.enumerate()
+			.map(|(i, pos)| enemy(data, i as u64 + 10, pos, 35.0, 0.0))
+			.collect()
+	}
+
+	#[test]
+	fn supported_spells() {
+		assert!(Spell::get(AbilityId::PsiStormPsiStorm).is_some());
+		assert!(Spell::get(AbilityId::YamatoYamatoGun).is_some());
+		assert!(Spell::get(AbilityId::Attack).is_none());
+	}
+
+	#[test]
+	fn energy_and_affected_units() {
+		let data = combat_data();
+		let storm = Spell::get(AbilityId::PsiStormPsiStorm).unwrap();
+		assert!(storm.has_energy(&templar(&data, 1, 75.0)));
+		assert!(!storm.has_energy(&templar(&data, 1, 74.0)));
+
+		let fungal = Spell::get(AbilityId::FungalGrowthFungalGrowth).unwrap();
+		let mut rooted = proto(2, UnitTypeId::Zergling, (0.0, 0.0));
+		rooted.mut_buff_ids().push(BuffId::FungalGrowth as u32);
+		assert!(!fungal.affects(&with_data(&data, &rooted)));
+		assert!(fungal.affects(&enemy(&data, 3, (0.0, 0.0), 35.0, 0.0)));
+
+		let cloud = Spell::get(AbilityId::BlindingCloudBlindingCloud).unwrap();
+		let mut flying = proto(4, UnitTypeId::Marine, (0.0, 0.0));
+		flying.set_is_flying(true);
+		assert!(!cloud.affects(&with_data(&data, &flying)));
+		assert!(cloud.affects(&with_data(&data, &proto(5, UnitTypeId::Marine, (0.0, 0.0)))));
+	}
+
+	#[test]
+	fn area_value_with_friendly_fire() {
+		let data = combat_data();
+		let targets = zerglings(&data);
+		let allies = std::iter::once(with_data(&data, &proto(1, UnitTypeId::Marine, (14.0, 10.2)))).collect();
+		let pos = Point2::new(14.0, 10.0);
+
+		let storm = Spell::get(AbilityId::PsiStormPsiStorm).unwrap();
+		assert_eq!(storm.value_at(pos, &targets, None), 105.0);
+		assert_eq!(storm.value_at(pos, &targets, Some(&allies)), 25.0);
+
+		let fungal = Spell::get(AbilityId::FungalGrowthFungalGrowth).unwrap();
+		assert_eq!(fungal.value_at(pos, &targets, Some(&allies)), 3.0 * 40.0);
+	}
+
+	#[test]
+	fn best_point_on_clump() {
+		let data = combat_data();
+		let storm = Spell::get(AbilityId::PsiStormPsiStorm).unwrap();
+		let caster = templar(&data, 1, 75.0);
+
+		let (pos, value) = storm.best_point(&caster, &zerglings(&data), None, 0.0).unwrap();
+		assert_eq!(value, 105.0);
+		assert!(pos.is_closer(1.0, Point2::new(14.2, 10.2)));
+		assert!(storm.best_target(&caster, &zerglings(&data), 0.0).is_none());
+
+		// Cast point out of range is moved closer to the caster
+		let far = std::iter::once(enemy(&data, 2, (16.0, 10.0), 35.0, 0.0)).collect();
+		let (pos, value) = storm.best_point(&caster, &far, None, 0.0).unwrap();
+		assert_eq!(value, 35.0);
+		assert!(pos.is_closer(0.01, Point2::new(14.5, 10.0)));
+
+		let far = std::iter::once(enemy(&data, 2, (26.0, 10.0), 35.0, 0.0)).collect();
+		assert_eq!(storm.best_point(&caster, &far, None, 0.0), None);
+	}
+
+	#[test]
+	fn best_single_target() {
+		let data = combat_data();
+		let feedback = Spell::get(AbilityId::FeedbackFeedback).unwrap();
+		let caster = templar(&data, 1, 50.0);
+		let targets = [
+			enemy(&data, 2, (10.0, 10.0), 35.0, 50.0),
+			enemy(&data, 3, (12.0, 10.0), 35.0, 150.0),
+			enemy(&data, 4, (40.0, 10.0), 35.0, 200.0),
+			enemy(&data, 5, (10.0, 12.0), 35.0, 0.0),
+		]
+		.into_iter()
+		.collect();
+
+		let (target, value) = feedback.best_target(&caster, &targets, 0.0).unwrap();
+		assert_eq!(target.tag(), 3);
+		assert_eq!(value, 185.0);
+		assert!(feedback.best_point(&caster, &targets, None, 0.0).is_none());
+	}
+
+	#[test]
+	fn caster_tracks_cooldowns_and_targets() {
+		let mut bot = Bot::default();
+		let data = combat_data();
+		let storm = Spell::get(AbilityId::PsiStormPsiStorm).unwrap();
+		for tag in [1, 2] {
+			data.abilities_units
+				.write_lock()
+				.insert(tag, [storm.ability].into_iter().collect());
+			bot.units.my.all.push(templar(&data, tag, 75.0));
+		}
+		bot.units.enemy.all = zerglings(&data);
+		let first = bot.units.my.all[0_usize].clone();
+		let second = bot.units.my.all[1_usize].clone();
+
+		let mut caster = SpellCaster::new();
+		caster.step(&bot);
+		assert!(caster.can_cast(&bot, &first, &storm));
+		let target = caster.cast_best(&bot, &first, &storm, 50.0, 0.0);
+		assert!(matches!(target, Some(Target::Pos(_))));
+		assert_eq!(caster.cooldown(&bot, 1, storm.ability), 32);
+		assert!(!caster.can_cast(&bot, &first, &storm));
+		assert!(caster.is_targeted(&storm, bot.units.enemy.all.get(10).unwrap()));
+
+		// Only zergling apart is left, which isn't worth the storm
+		assert_eq!(caster.cast_best(&bot, &second, &storm, 50.0, 0.0), None);
+
+		caster.step(&bot);
+		assert!(!caster.is_targeted(&storm, bot.units.enemy.all.get(10).unwrap()));
+		bot.state.observation.game_loop.set_locked(32);
+		caster.step(&bot);
+		assert_eq!(caster.cooldown(&bot, 1, storm.ability), 0);
+		assert!(caster.can_cast(&bot, &first, &storm));
+	}
+}
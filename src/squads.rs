@@ -0,0 +1,485 @@
+//! Persistent groups of units.
+//!
+//! [`Squads`] is an opt-in component, which should be stored in your bot,
+//! updated every step and notified about events:
+//! ```
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot {
+//!     squads: Squads,
+//! }
+//!
+//! impl Player for MyBot {
+//!     fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+//!         self.squads.step(&self._bot);
+//!         Ok(())
+//!     }
+//!     fn on_event(&mut self, event: Event) -> SC2Result<()> {
+//!         self.squads.on_event(&event);
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use crate::{
+	action::Target, bot::Bot, distance::*, formation::Formation, geometry::Point2, unit::Unit, units::Units,
+	Event,
+};
+use indexmap::{IndexMap, IndexSet};
+use rustc_hash::{FxHashMap, FxHasher};
+use std::{hash::BuildHasherDefault, mem};
+
+type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
+
+/// Reinforcements join squad when they're closer than this distance to its center.
+const JOIN_DISTANCE: f32 = 8.0;
+
+/// What squad is doing now. Set by bot and executed with [`Squad::execute`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SquadState {
+	/// Squad has no orders.
+	#[default]
+	Idle,
+	/// Squad is gathering at given position.
+	Gather(Point2),
+	/// Squad is attacking given position.
+	Attack(Point2),
+	/// Squad is retreating to given position.
+	Retreat(Point2),
+}
+
+/// Group of units managed together.
+#[derive(Debug, Default, Clone)]
+pub struct Squad {
+	id: usize,
+	tags: FxIndexSet<u64>,
+	reinforcements: FxIndexSet<u64>,
+	/// What squad is doing now.
+	pub state: SquadState,
+}
+
+impl Squad {
+	/// Returns id of the squad.
+	pub fn id(&self) -> usize {
+		self.id
+	}
+	/// Returns tags of units in the squad.
+	pub fn tags(&self) -> impl Iterator<Item = u64> + '_ {
+		self.tags.iter().copied()
+	}
+	/// Returns tags of units on the way to join the squad.
+	pub fn reinforcements(&self) -> impl Iterator<Item = u64> + '_ {
+		self.reinforcements.iter().copied()
+	}
+	/// Checks if given unit is in the squad (reinforcements aren't counted).
+	pub fn contains(&self, tag: u64) -> bool {
+		self.tags.contains(&tag)
+	}
+	/// Returns number of units in the squad.
+	pub fn len(&self) -> usize {
+		self.tags.len()
+	}
+	/// Checks if squad has no units.
+	pub fn is_empty(&self) -> bool {
+		self.tags.is_empty()
+	}
+
+	/// Returns units of the squad.
+	pub fn units(&self, bot: &Bot) -> Units {
+		bot.units.my.all.find_tags(&self.tags)
+	}
+	/// Returns center of the squad.
+	pub fn center(&self, bot: &Bot) -> Option<Point2> {
+		self.units(bot).center()
+	}
+	/// Returns center of the third of units closest to given target,
+	/// i.e. front line of the squad moving to the target.
+	pub fn front<P: Into<Point2> + Copy>(&self, bot: &Bot, target: P) -> Option<Point2> {
+		let units = self.units(bot);
+		let n = (units.len() + 2) / 3;
+		units
+			.sorted(|u| u.distance_squared(target))
+			.iter()
+			.take(n)
+			.map(|u| u.position())
+			.center()
+	}
+	/// Returns combat power of the squad: sum of units' dps multiplied by their health and shield.
+	pub fn power(&self, bot: &Bot) -> f32 {
+		power(&self.units(bot))
+	}
+
+	/// Orders all units of the squad to attack given target.
+	pub fn attack(&self, bot: &Bot, target: Target, queue: bool) {
+		for u in &self.units(bot) {
+			u.attack(target, queue);
+		}
+	}
+	/// Orders all units of the squad to move to given target.
+	pub fn move_to(&self, bot: &Bot, target: Target, queue: bool) {
+		for u in &self.units(bot) {
+			u.move_to(target, queue);
+		}
+	}
+	/// Moves units of the squad into concave facing given target.
+	///
+	/// Concave is built on the arc around target with radius equal to distance from target
+	/// to the squad's center. Units which don't fit in 120° arc are placed in next rows behind.
+	pub fn concave(&self, bot: &Bot, target: Point2) {
+		self.formation(bot, Formation::default(), target);
+	}
+	/// Moves units of the squad into given formation facing given target,
+	/// with the front row at the squad's center.
+	pub fn formation(&self, bot: &Bot, formation: Formation, target: Point2) {
+		let units = self.units(bot);
+		if let Some(center) = units.center() {
+			formation.move_units(&units, center, target, false);
+		}
+	}
+	/// Gives orders to units of the squad according to its [`state`](Self::state).
+	pub fn execute(&self, bot: &Bot) {
+		match self.state {
+			SquadState::Idle => {}
+			SquadState::Attack(pos) => self.attack(bot, Target::Pos(pos), false),
+			SquadState::Gather(pos) | SquadState::Retreat(pos) => self.move_to(bot, Target::Pos(pos), false),
+		}
+	}
+}
+
+/// Persistent squads of units.
+///
+/// Every unit can be in one squad at a time, either as a member or as a reinforcement.
+/// Dead units are removed with [`on_event`](Self::on_event) and [`step`](Self::step).
+#[derive(Default)]
+pub struct Squads {
+	squads: FxIndexMap<usize, Squad>,
+	assigned: FxHashMap<u64, usize>,
+	next_id: usize,
+}
+
+impl Squads {
+	/// Constructs new empty manager.
+	pub fn new() -> Self {
+		Default::default()
+	}
+	/// Creates new empty squad and returns its id.
+	pub fn create(&mut self) -> usize {
+		let id = self.next_id;
+		self.next_id += 1;
+		self.squads.insert(
+			id,
+			Squad {
+				id,
+				..Default::default()
+			},
+		);
+		id
+	}
+	/// Creates new squad of given units and returns its id.
+	/// Units are removed from their previous squads.
+	pub fn create_with<T: IntoIterator<Item = u64>>(&mut self, tags: T) -> usize {
+		let id = self.create();
+		for tag in tags {
+			self.assign(tag, id);
+		}
+		id
+	}
+	/// Removes squad, leaving its units unassigned.
+	pub fn remove(&mut self, id: usize) -> Option<Squad> {
+		let squad = self.squads.shift_remove(&id)?;
+		for tag in squad.tags.iter().chain(&squad.reinforcements) {
+			self.assigned.remove(tag);
+		}
+		Some(squad)
+	}
+	/// Returns squad with given id.
+	pub fn get(&self, id: usize) -> Option<&Squad> {
+		self.squads.get(&id)
+	}
+	/// Returns mutable reference to squad with given id.
+	pub fn get_mut(&mut self, id: usize) -> Option<&mut Squad> {
+		self.squads.get_mut(&id)
+	}
+	/// Returns iterator over all squads.
+	pub fn iter(&self) -> impl Iterator<Item = &Squad> {
+		self.squads.values()
+	}
+	/// Returns mutable iterator over all squads.
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Squad> {
+		self.squads.values_mut()
+	}
+	/// Returns number of squads.
+	pub fn len(&self) -> usize {
+		self.squads.len()
+	}
+	/// Checks if there're no squads.
+	pub fn is_empty(&self) -> bool {
+		self.squads.is_empty()
+	}
+	/// Returns id of the squad given unit is in (as a member or a reinforcement).
+	pub fn squad_of(&self, tag: u64) -> Option<usize> {
+		self.assigned.get(&tag).copied()
+	}
+	/// Checks if given unit is in any squad.
+	pub fn is_assigned(&self, tag: u64) -> bool {
+		self.assigned.contains_key(&tag)
+	}
+
+	/// Adds unit to the squad, removing it from previous one.
+	///
+	/// Returns `false` if there's no squad with given id.
+	pub fn assign(&mut self, tag: u64, id: usize) -> bool {
+		if !self.squads.contains_key(&id) {
+			return false;
+		}
+		self.unassign(tag);
+		self.squads[&id].tags.insert(tag);
+		self.assigned.insert(tag, id);
+		true
+	}
+	/// Sends unit to join the squad as a reinforcement, removing it from previous squad.
+	/// Unit will become a member when it's close enough to the squad.
+	///
+	/// Returns `false` if there's no squad with given id.
+	pub fn reinforce(&mut self, tag: u64, id: usize) -> bool {
+		if !self.squads.contains_key(&id) {
+			return false;
+		}
+		self.unassign(tag);
+		self.squads[&id].reinforcements.insert(tag);
+		self.assigned.insert(tag, id);
+		true
+	}
+	/// Removes unit from its squad.
+	///
+	/// Returns id of the squad unit was in.
+	pub fn unassign(&mut self, tag: u64) -> Option<usize> {
+		let id = self.assigned.remove(&tag)?;
+		if let Some(squad) = self.squads.get_mut(&id) {
+			squad.tags.shift_remove(&tag);
+			squad.reinforcements.shift_remove(&tag);
+		}
+		Some(id)
+	}
+	/// Moves all units from squad `from` to squad `into` and removes squad `from`.
+	///
+	/// Returns `false` if any of squads doesn't exist or they're the same squad.
+	pub fn merge(&mut self, into: usize, from: usize) -> bool {
+		if into == from || !self.squads.contains_key(&into) {
+			return false;
+		}
+		let from = match self.squads.shift_remove(&from) {
+			Some(squad) => squad,
+			None => return false,
+		};
+		for tag in &from.tags {
+			self.assigned.insert(*tag, into);
+		}
+		for tag in &from.reinforcements {
+			self.assigned.insert(*tag, into);
+		}
+		let squad = &mut self.squads[&into];
+		squad.tags.extend(from.tags);
+		squad.reinforcements.extend(from.reinforcements);
+		true
+	}
+	/// Moves given units of the squad to new squad with the same state and returns its id.
+	/// Units not in the squad are ignored.
+	///
+	/// Returns `None` if there's no squad with given id.
+	pub fn split<T: IntoIterator<Item = u64>>(&mut self, id: usize, tags: T) -> Option<usize> {
+		let state = self.squads.get(&id)?.state;
+		let new = self.create();
+		self.squads[&new].state = state;
+		for tag in tags {
+			if self.squads[&id].contains(tag) {
+				self.assign(tag, new);
+			}
+		}
+		Some(new)
+	}
+
+	/// Removes dead units from squads. Should be called from [`on_event`](crate::Player::on_event).
+	pub fn on_event(&mut self, event: &Event) {
+		if let Event::UnitDestroyed(tag, _) = event {
+			self.unassign(*tag);
+		}
+	}
+	/// Removes units that aren't owned anymore and moves reinforcements to their squads,
+	/// joining ones close enough. Should be called every step.
+	pub fn step(&mut self, bot: &Bot) {
+		let units = &bot.units.my.all;
+		let lost = self
+			.assigned
+			.keys()
+			.filter(|tag| !units.contains_tag(**tag))
+			.copied()
+			.collect::<Vec<_>>();
+		for tag in lost {
+			self.unassign(tag);
+		}
+
+		for squad in self.squads.values_mut() {
+			if squad.reinforcements.is_empty() {
+				continue;
+			}
+			let center = match squad.center(bot) {
+				Some(center) => center,
+				None => {
+					// Nothing to join, so reinforcements become the squad
+					let reinforcements = mem::take(&mut squad.reinforcements);
+					squad.tags.extend(reinforcements);
+					continue;
+				}
+			};
+
+			let mut joined = vec![];
+			for tag in &squad.reinforcements {
+				if let Some(u) = units.get(*tag) {
+					if u.is_closer(JOIN_DISTANCE, center) {
+						joined.push(*tag);
+					} else {
+						u.move_to(Target::Pos(center), false);
+					}
+				}
+			}
+			for tag in joined {
+				squad.reinforcements.shift_remove(&tag);
+				squad.tags.insert(tag);
+			}
+		}
+	}
+}
+
+/// Returns combat power of given units: sum of units' dps multiplied by their health and shield.
+pub fn power(units: &Units) -> f32 {
+	units.sum(|u: &Unit| u.ground_dps().max(u.air_dps()) * u.hits().unwrap_or(0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ids::UnitTypeId, unit::tests::unit};
+
+	fn bot_with_marines(positions: &[(u64, (f32, f32))]) -> Bot {
+		let mut bot = Bot::default();
+		for (tag, pos) in positions {
+			bot.units.my.all.push(unit(*tag, UnitTypeId::Marine, *pos));
+		}
+		bot
+	}
+
+	#[test]
+	fn assign_moves_between_squads() {
+		let mut squads = Squads::new();
+		let a = squads.create_with([1, 2]);
+		let b = squads.create();
+		assert_ne!(a, b);
+		assert_eq!(squads.get(a).unwrap().len(), 2);
+
+		assert!(squads.assign(1, b));
+		assert!(!squads.get(a).unwrap().contains(1));
+		assert!(squads.get(b).unwrap().contains(1));
+		assert_eq!(squads.squad_of(1), Some(b));
+
+		assert!(squads.reinforce(2, b));
+		assert!(squads.get(a).unwrap().is_empty());
+		assert!(!squads.get(b).unwrap().contains(2));
+		assert_eq!(
+			squads.get(b).unwrap().reinforcements().collect::<Vec<_>>(),
+			vec![2]
+		);
+		assert_eq!(squads.squad_of(2), Some(b));
+
+		assert!(!squads.assign(3, 100));
+		assert!(!squads.is_assigned(3));
+		assert_eq!(squads.unassign(1), Some(b));
+		assert_eq!(squads.unassign(1), None);
+	}
+
+	#[test]
+	fn merge_and_split() {
+		let mut squads = Squads::new();
+		let a = squads.create_with([1, 2]);
+		let b = squads.create_with([3]);
+		squads.reinforce(4, b);
+
+		assert!(!squads.merge(a, a));
+		assert!(squads.merge(a, b));
+		assert!(squads.get(b).is_none());
+		assert_eq!(squads.len(), 1);
+		assert_eq!(squads.get(a).unwrap().tags().collect::<Vec<_>>(), vec![1, 2, 3]);
+		assert_eq!(squads.squad_of(3), Some(a));
+		assert_eq!(squads.squad_of(4), Some(a));
+
+		squads.get_mut(a).unwrap().state = SquadState::Attack(Point2::new(10.0, 10.0));
+		let c = squads.split(a, [2, 3, 5]).unwrap();
+		let split = squads.get(c).unwrap();
+		assert_eq!(split.tags().collect::<Vec<_>>(), vec![2, 3]);
+		assert_eq!(split.state, SquadState::Attack(Point2::new(10.0, 10.0)));
+		assert!(!squads.is_assigned(5));
+		assert_eq!(squads.split(100, [1]), None);
+	}
+
+	#[test]
+	fn remove_and_destroyed() {
+		let mut squads = Squads::new();
+		let a = squads.create_with([1, 2]);
+		squads.reinforce(3, a);
+
+		squads.on_event(&Event::UnitDestroyed(1, None));
+		assert!(!squads.is_assigned(1));
+		assert_eq!(squads.get(a).unwrap().len(), 1);
+
+		let removed = squads.remove(a).unwrap();
+		assert!(removed.contains(2));
+		assert!(!squads.is_assigned(2));
+		assert!(!squads.is_assigned(3));
+		assert!(squads.is_empty());
+	}
+
+	#[test]
+	fn step_joins_reinforcements() {
+		let bot = bot_with_marines(&[(1, (10.0, 10.0)), (2, (14.0, 10.0)), (3, (40.0, 40.0))]);
+		let mut squads = Squads::new();
+		let a = squads.create_with([1]);
+		for tag in [2, 3, 4] {
+			squads.reinforce(tag, a);
+		}
+
+		squads.step(&bot);
+		let squad = squads.get(a).unwrap();
+		assert_eq!(squad.tags().collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(squad.reinforcements().collect::<Vec<_>>(), vec![3]);
+		assert!(!squads.is_assigned(4));
+	}
+
+	#[test]
+	fn step_reinforcements_become_squad() {
+		let bot = bot_with_marines(&[(1, (10.0, 10.0)), (2, (40.0, 40.0))]);
+		let mut squads = Squads::new();
+		let a = squads.create();
+		squads.reinforce(1, a);
+		squads.reinforce(2, a);
+
+		squads.step(&bot);
+		let squad = squads.get(a).unwrap();
+		assert_eq!(squad.len(), 2);
+		assert_eq!(squad.reinforcements().count(), 0);
+	}
+
+	#[test]
+	fn front_of_squad() {
+		let bot = bot_with_marines(&[(1, (10.0, 10.0)), (2, (12.0, 10.0)), (3, (14.0, 10.0))]);
+		let mut squads = Squads::new();
+		let a = squads.create_with([1, 2, 3]);
+		let squad = squads.get(a).unwrap();
+
+		assert_eq!(squad.center(&bot), Some(Point2::new(12.0, 10.0)));
+		assert_eq!(
+			squad.front(&bot, Point2::new(30.0, 10.0)),
+			Some(Point2::new(14.0, 10.0))
+		);
+	}
+}
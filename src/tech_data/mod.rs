@@ -0,0 +1,212 @@
+//! Tech data (producers, researchers, requirements, aliases and costs) generated from game data.
+//!
+//! Data files for every supported game version are generated with `generate_data.py` script
+//! from `stableid.json` and tech tree dump of the game and selected at runtime by
+//! [`base_build`](crate::bot::Bot::base_build) of the game.
+//! If there's no data for current game version, hand-written tables from
+//! [`consts`](crate::consts) can be used instead.
+
+use crate::{
+	game_data::Cost,
+	ids::{UnitTypeId, UpgradeId},
+	tech_tree::{Requirement, Tech, TechNode, TechTree},
+};
+
+mod versions;
+
+/// Output of `generate_data.py` for dumps in `misc/tech_data`.
+#[cfg(test)]
+#[path = "../../misc/tech_data/expected.rs"]
+mod fixture;
+
+/// Tech data of specific game version.
+#[derive(Debug)]
+pub struct TechData {
+	/// Game version data was generated from (e.g. `"5.0.11"`).
+	pub version: &'static str,
+	/// Base build of game version.
+	pub base_build: u32,
+	/// Producers mapped to units.
+	pub producers: &'static [(UnitTypeId, &'static [UnitTypeId])],
+	/// Researchers mapped to upgrades.
+	pub researchers: &'static [(UpgradeId, &'static [UnitTypeId])],
+	/// Requirements of units except producer.
+	pub unit_requirements: &'static [(UnitTypeId, &'static [Requirement])],
+	/// Requirements of upgrades except researcher.
+	pub upgrade_requirements: &'static [(UpgradeId, &'static [Requirement])],
+	/// Units produced by morphing their producer.
+	pub morphs: &'static [UnitTypeId],
+	/// Tech aliases mapped to units.
+	pub tech_alias: &'static [(UnitTypeId, &'static [UnitTypeId])],
+	/// Unit aliases mapped to units.
+	pub unit_alias: &'static [(UnitTypeId, UnitTypeId)],
+	/// Costs of units.
+	pub unit_costs: &'static [(UnitTypeId, Cost)],
+	/// Costs of upgrades.
+	pub upgrade_costs: &'static [(UpgradeId, Cost)],
+}
+impl TechData {
+	/// Returns producers of given unit.
+	pub fn producers(&self, unit: UnitTypeId) -> &'static [UnitTypeId] {
+		find(self.producers, unit).unwrap_or(&[])
+	}
+	/// Returns researchers of given upgrade.
+	pub fn researchers(&self, upgrade: UpgradeId) -> &'static [UnitTypeId] {
+		find(self.researchers, upgrade).unwrap_or(&[])
+	}
+	/// Returns tech aliases of given unit.
+	pub fn tech_alias(&self, unit: UnitTypeId) -> &'static [UnitTypeId] {
+		find(self.tech_alias, unit).unwrap_or(&[])
+	}
+	/// Returns unit alias of given unit.
+	pub fn unit_alias(&self, unit: UnitTypeId) -> Option<UnitTypeId> {
+		find(self.unit_alias, unit)
+	}
+	/// Returns cost of given unit.
+	pub fn unit_cost(&self, unit: UnitTypeId) -> Option<Cost> {
+		find(self.unit_costs, unit)
+	}
+	/// Returns cost of given upgrade.
+	pub fn upgrade_cost(&self, upgrade: UpgradeId) -> Option<Cost> {
+		find(self.upgrade_costs, upgrade)
+	}
+	/// Constructs [`TechTree`] from this data.
+	pub fn tech_tree(&self) -> TechTree {
+		let units = self.producers.iter().map(|(unit, producers)| TechNode {
+			tech: Tech::Unit(*unit),
+			producers: producers.to_vec(),
+			requirements: find(self.unit_requirements, *unit).unwrap_or(&[]).to_vec(),
+			is_morph: self.morphs.contains(unit),
+		});
+		let upgrades = self.researchers.iter().map(|(upgrade, researchers)| TechNode {
+			tech: Tech::Upgrade(*upgrade),
+			producers: researchers.to_vec(),
+			requirements: find(self.upgrade_requirements, *upgrade).unwrap_or(&[]).to_vec(),
+			is_morph: false,
+		});
+		TechTree::from_nodes(units.chain(upgrades))
+	}
+}
+
+fn find<K: PartialEq, V: Clone>(data: &[(K, V)], key: K) -> Option<V> {
+	data.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone())
+}
+
+/// Returns data of all available game versions sorted by base build.
+pub fn versions() -> &'static [&'static TechData] {
+	versions::VERSIONS
+}
+/// Returns data of latest available game version.
+pub fn latest() -> Option<&'static TechData> {
+	versions::VERSIONS.last().copied()
+}
+/// Returns data of given game version (e.g. `"5.0.11"`).
+pub fn for_version(version: &str) -> Option<&'static TechData> {
+	versions::VERSIONS
+		.iter()
+		.find(|data| data.version == version)
+		.copied()
+}
+/// Returns data of latest available game version not newer than given base build.
+pub fn for_build(base_build: u32) -> Option<&'static TechData> {
+	by_build(versions::VERSIONS, base_build)
+}
+
+fn by_build(versions: &[&'static TechData], base_build: u32) -> Option<&'static TechData> {
+	versions
+		.iter()
+		.rev()
+		.find(|data| data.base_build <= base_build)
+		.copied()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{env, fs, path::Path, process::Command};
+
+	#[test]
+	fn generator_output_matches_fixture() {
+		let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+		let dumps = root.join("misc").join("tech_data");
+		let out = env::temp_dir().join(format!("rust-sc2-tech-data-{}", std::process::id()));
+		let status = Command::new("python3")
+			.current_dir(root)
+			.arg("generate_data.py")
+			.args(["1.0.0", "1"])
+			.args(["stableid.json", "data.json", "game_data.json"].map(|file| dumps.join(file)))
+			.arg("--out")
+			.arg(&out)
+			.status()
+			.expect("failed to run generate_data.py");
+		assert!(status.success());
+
+		let generated = fs::read_to_string(out.join("v1_0_0.rs")).unwrap();
+		let mod_rs = fs::read_to_string(out.join("mod.rs")).unwrap();
+		fs::remove_dir_all(&out).unwrap();
+		assert_eq!(generated, include_str!("../../misc/tech_data/expected.rs"));
+		assert!(mod_rs.contains("mod v1_0_0;"));
+		assert!(mod_rs.contains("&[&v1_0_0::DATA]"));
+	}
+
+	#[test]
+	fn versions_are_selected_by_build() {
+		let versions = [&fixture::DATA];
+		assert_eq!(by_build(&versions, 1).map(|d| d.version), Some("1.0.0"));
+		assert_eq!(by_build(&versions, u32::MAX).map(|d| d.version), Some("1.0.0"));
+		assert!(by_build(&versions, 0).is_none());
+		assert!(by_build(&[], 1).is_none());
+		assert!(versions().windows(2).all(|w| w[0].base_build < w[1].base_build));
+	}
+
+	#[test]
+	fn fixture_data() {
+		let data = &fixture::DATA;
+		assert_eq!(data.producers(UnitTypeId::Marine), &[UnitTypeId::Barracks]);
+		assert_eq!(data.producers(UnitTypeId::Zergling), &[]);
+		assert_eq!(
+			data.researchers(UpgradeId::Stimpack),
+			&[UnitTypeId::BarracksTechLab]
+		);
+		assert_eq!(
+			data.tech_alias(UnitTypeId::OrbitalCommand),
+			&[UnitTypeId::CommandCenter]
+		);
+		assert_eq!(
+			data.unit_alias(UnitTypeId::SupplyDepotLowered),
+			Some(UnitTypeId::SupplyDepot)
+		);
+		let cost = data.unit_cost(UnitTypeId::Marauder).unwrap();
+		assert_eq!((cost.minerals, cost.vespene), (100, 25));
+		assert_eq!(cost.supply, 2.0);
+		let cost = data.upgrade_cost(UpgradeId::TerranInfantryWeaponsLevel2).unwrap();
+		assert_eq!((cost.minerals, cost.vespene), (175, 175));
+		assert_eq!(cost.time, 3040.0);
+		assert!(data.upgrade_cost(UpgradeId::ShieldWall).is_none());
+	}
+
+	#[test]
+	fn tech_tree_from_data() {
+		let tree = fixture::DATA.tech_tree();
+		assert_eq!(
+			tree.requirements(UnitTypeId::Marauder),
+			vec![
+				Requirement::Unit(UnitTypeId::Barracks),
+				Requirement::Addon(UnitTypeId::BarracksTechLab),
+			]
+		);
+		assert_eq!(
+			tree.requirements(UpgradeId::TerranInfantryWeaponsLevel2),
+			vec![
+				Requirement::Unit(UnitTypeId::EngineeringBay),
+				Requirement::Unit(UnitTypeId::Armory),
+				Requirement::Upgrade(UpgradeId::TerranInfantryWeaponsLevel1),
+			]
+		);
+		// Requirements of unknown units are dropped
+		assert_eq!(tree.requirements(UnitTypeId::Armory), vec![]);
+		assert!(tree.get(UnitTypeId::OrbitalCommand).unwrap().is_morph);
+		assert!(!tree.get(UnitTypeId::Marine).unwrap().is_morph);
+		assert!(tree.get(UnitTypeId::CommandCenter).is_none());
+	}
+}
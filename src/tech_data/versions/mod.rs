@@ -0,0 +1,7 @@
+//! Auto generated with `generate_data.py` script from `stableid.json` and game data dumps.
+//! Tech data of different game versions.
+#![allow(missing_docs)]
+
+use super::TechData;
+
+pub(super) static VERSIONS: &[&TechData] = &[];
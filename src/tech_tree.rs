@@ -0,0 +1,657 @@
+//! Tech tree of all races with requirements resolution.
+//!
+//! Unlike flat tables in [`consts`](crate::consts), tech tree contains all requirements
+//! of units, structures and upgrades: producers, required structures, addons,
+//! previous levels of upgrades and morph chains.
+
+use crate::{
+	bot::Bot,
+	consts::{ALL_PRODUCERS, ALL_RESEARCHERS, TECH_REQUIREMENTS},
+	ids::{UnitTypeId, UpgradeId},
+	unit::Unit,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::HashMap;
+
+/// Item of tech tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tech {
+	/// Unit or structure.
+	Unit(UnitTypeId),
+	/// Upgrade.
+	Upgrade(UpgradeId),
+}
+impl From<UnitTypeId> for Tech {
+	fn from(id: UnitTypeId) -> Self {
+		Tech::Unit(id)
+	}
+}
+impl From<UpgradeId> for Tech {
+	fn from(id: UpgradeId) -> Self {
+		Tech::Upgrade(id)
+	}
+}
+
+/// Requirement of item in tech tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Requirement {
+	/// Ready unit or structure of given type (or it's advanced form, e.g. `Lair` for `Hatchery`).
+	Unit(UnitTypeId),
+	/// Ready addon of given type attached to producer of item (e.g. `BarracksTechLab`).
+	Addon(UnitTypeId),
+	/// Complete upgrade.
+	Upgrade(UpgradeId),
+}
+
+/// Node of tech tree.
+#[derive(Debug, Clone)]
+pub struct TechNode {
+	/// Item this node describes.
+	pub tech: Tech,
+	/// Units or structures which can produce, build, research or morph to this item.
+	pub producers: Vec<UnitTypeId>,
+	/// Requirements except producer.
+	pub requirements: Vec<Requirement>,
+	/// `true` if item is produced by morphing producer (e.g. `Lair` from `Hatchery`).
+	pub is_morph: bool,
+}
+
+lazy_static! {
+	/// Default tech tree built from [`consts`](crate::consts) tables.
+	pub static ref TECH_TREE: TechTree = TechTree::default();
+
+	/// Addons required by producers of units.
+	pub static ref ADDON_REQUIREMENTS: HashMap<UnitTypeId, UnitTypeId> = hashmap![
+		UnitTypeId::Marauder => UnitTypeId::BarracksTechLab,
+		UnitTypeId::Ghost => UnitTypeId::BarracksTechLab,
+		UnitTypeId::SiegeTank => UnitTypeId::FactoryTechLab,
+		UnitTypeId::Thor => UnitTypeId::FactoryTechLab,
+		UnitTypeId::Raven => UnitTypeId::StarportTechLab,
+		UnitTypeId::Banshee => UnitTypeId::StarportTechLab,
+		UnitTypeId::Battlecruiser => UnitTypeId::StarportTechLab,
+	];
+	/// Additional requirements of units, which aren't in [`TECH_REQUIREMENTS`].
+	pub static ref UNIT_REQUIREMENTS: HashMap<UnitTypeId, Vec<Requirement>> = hashmap![
+		UnitTypeId::Ravager => vec![Requirement::Unit(UnitTypeId::RoachWarren)],
+		UnitTypeId::Mothership => vec![Requirement::Unit(UnitTypeId::FleetBeacon)],
+		UnitTypeId::OrbitalCommand => vec![Requirement::Unit(UnitTypeId::Barracks)],
+	];
+	/// Requirements of upgrades except researcher.
+	pub static ref UPGRADE_REQUIREMENTS: HashMap<UpgradeId, Vec<Requirement>> = hashmap![
+		// Terran
+		UpgradeId::TerranInfantryWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::TerranInfantryWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::Armory),
+		],
+		UpgradeId::TerranInfantryWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::TerranInfantryWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::Armory),
+		],
+		UpgradeId::TerranInfantryArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::TerranInfantryArmorsLevel1),
+			Requirement::Unit(UnitTypeId::Armory),
+		],
+		UpgradeId::TerranInfantryArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::TerranInfantryArmorsLevel2),
+			Requirement::Unit(UnitTypeId::Armory),
+		],
+		UpgradeId::TerranVehicleWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::TerranVehicleWeaponsLevel1),
+		],
+		UpgradeId::TerranVehicleWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::TerranVehicleWeaponsLevel2),
+		],
+		UpgradeId::TerranShipWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::TerranShipWeaponsLevel1),
+		],
+		UpgradeId::TerranShipWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::TerranShipWeaponsLevel2),
+		],
+		UpgradeId::TerranVehicleAndShipArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::TerranVehicleAndShipArmorsLevel1),
+		],
+		UpgradeId::TerranVehicleAndShipArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::TerranVehicleAndShipArmorsLevel2),
+		],
+		// Protoss
+		UpgradeId::ProtossGroundWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossGroundWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossGroundWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossGroundWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossGroundArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossGroundArmorsLevel1),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossGroundArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossGroundArmorsLevel2),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossShieldsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossShieldsLevel1),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossShieldsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossShieldsLevel2),
+			Requirement::Unit(UnitTypeId::TwilightCouncil),
+		],
+		UpgradeId::ProtossAirWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossAirWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::FleetBeacon),
+		],
+		UpgradeId::ProtossAirWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossAirWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::FleetBeacon),
+		],
+		UpgradeId::ProtossAirArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossAirArmorsLevel1),
+			Requirement::Unit(UnitTypeId::FleetBeacon),
+		],
+		UpgradeId::ProtossAirArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ProtossAirArmorsLevel2),
+			Requirement::Unit(UnitTypeId::FleetBeacon),
+		],
+		// Zerg
+		UpgradeId::ZergMeleeWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ZergMeleeWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::Lair),
+		],
+		UpgradeId::ZergMeleeWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ZergMeleeWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::Hive),
+		],
+		UpgradeId::ZergMissileWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ZergMissileWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::Lair),
+		],
+		UpgradeId::ZergMissileWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ZergMissileWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::Hive),
+		],
+		UpgradeId::ZergGroundArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ZergGroundArmorsLevel1),
+			Requirement::Unit(UnitTypeId::Lair),
+		],
+		UpgradeId::ZergGroundArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ZergGroundArmorsLevel2),
+			Requirement::Unit(UnitTypeId::Hive),
+		],
+		UpgradeId::ZergFlyerWeaponsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ZergFlyerWeaponsLevel1),
+			Requirement::Unit(UnitTypeId::Lair),
+		],
+		UpgradeId::ZergFlyerWeaponsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ZergFlyerWeaponsLevel2),
+			Requirement::Unit(UnitTypeId::Hive),
+		],
+		UpgradeId::ZergFlyerArmorsLevel2 => vec![
+			Requirement::Upgrade(UpgradeId::ZergFlyerArmorsLevel1),
+			Requirement::Unit(UnitTypeId::Lair),
+		],
+		UpgradeId::ZergFlyerArmorsLevel3 => vec![
+			Requirement::Upgrade(UpgradeId::ZergFlyerArmorsLevel2),
+			Requirement::Unit(UnitTypeId::Hive),
+		],
+		UpgradeId::Zerglingattackspeed => vec![Requirement::Unit(UnitTypeId::Hive)],
+		UpgradeId::TunnelingClaws => vec![Requirement::Unit(UnitTypeId::Lair)],
+		UpgradeId::GlialReconstitution => vec![Requirement::Unit(UnitTypeId::Lair)],
+		UpgradeId::DiggingClaws => vec![Requirement::Unit(UnitTypeId::Hive)],
+		UpgradeId::LurkerRange => vec![Requirement::Unit(UnitTypeId::Hive)],
+	];
+	/// Units and structures produced by morphing their producer.
+	pub static ref MORPHS: FxHashSet<UnitTypeId> = [
+		UnitTypeId::OrbitalCommand,
+		UnitTypeId::PlanetaryFortress,
+		UnitTypeId::Lair,
+		UnitTypeId::Hive,
+		UnitTypeId::GreaterSpire,
+		UnitTypeId::Baneling,
+		UnitTypeId::Ravager,
+		UnitTypeId::LurkerMP,
+		UnitTypeId::BroodLord,
+		UnitTypeId::Overseer,
+		UnitTypeId::OverlordTransport,
+	]
+	.iter()
+	.copied()
+	.collect();
+	/// Types, which also satisfy requirement of given type (i.e. it's advanced or alternative forms).
+	pub static ref SATISFIED_BY: HashMap<UnitTypeId, Vec<UnitTypeId>> = hashmap![
+		UnitTypeId::CommandCenter => vec![
+			UnitTypeId::CommandCenterFlying,
+			UnitTypeId::OrbitalCommand,
+			UnitTypeId::OrbitalCommandFlying,
+			UnitTypeId::PlanetaryFortress,
+		],
+		UnitTypeId::OrbitalCommand => vec![UnitTypeId::OrbitalCommandFlying],
+		UnitTypeId::SupplyDepot => vec![UnitTypeId::SupplyDepotLowered],
+		UnitTypeId::Barracks => vec![UnitTypeId::BarracksFlying],
+		UnitTypeId::Factory => vec![UnitTypeId::FactoryFlying],
+		UnitTypeId::Starport => vec![UnitTypeId::StarportFlying],
+		UnitTypeId::Gateway => vec![UnitTypeId::WarpGate],
+		UnitTypeId::Hatchery => vec![UnitTypeId::Lair, UnitTypeId::Hive],
+		UnitTypeId::Lair => vec![UnitTypeId::Hive],
+		UnitTypeId::Spire => vec![UnitTypeId::GreaterSpire],
+		UnitTypeId::Overlord => vec![UnitTypeId::OverlordTransport],
+		UnitTypeId::Zergling => vec![UnitTypeId::ZerglingBurrowed],
+		UnitTypeId::Roach => vec![UnitTypeId::RoachBurrowed],
+		UnitTypeId::Hydralisk => vec![UnitTypeId::HydraliskBurrowed],
+		UnitTypeId::Pylon => vec![UnitTypeId::PylonOvercharged],
+	];
+}
+
+/// Graph of units, structures, upgrades and their requirements.
+///
+/// Default tree is available in [`TECH_TREE`] static.
+/// Tree of current game version can be constructed from generated data with
+/// [`TechData::tech_tree`](crate::tech_data::TechData::tech_tree).
+#[derive(Debug, Clone)]
+pub struct TechTree {
+	nodes: FxHashMap<Tech, TechNode>,
+}
+impl Default for TechTree {
+	fn default() -> Self {
+		let mut nodes = FxHashMap::default();
+
+		for (&unit, producers) in ALL_PRODUCERS.iter() {
+			let mut requirements = vec![];
+			if let Some(&req) = TECH_REQUIREMENTS.get(&unit) {
+				requirements.push(Requirement::Unit(req));
+			}
+			if let Some(&addon) = ADDON_REQUIREMENTS.get(&unit) {
+				requirements.push(Requirement::Addon(addon));
+			}
+			if let Some(reqs) = UNIT_REQUIREMENTS.get(&unit) {
+				requirements.extend(reqs.iter().copied().filter(|req| !requirements.contains(req)));
+			}
+			nodes.insert(
+				Tech::Unit(unit),
+				TechNode {
+					tech: Tech::Unit(unit),
+					producers: producers.clone(),
+					requirements,
+					is_morph: MORPHS.contains(&unit),
+				},
+			);
+		}
+
+		for (addon, producer) in [
+			(UnitTypeId::BarracksTechLab, UnitTypeId::Barracks),
+			(UnitTypeId::BarracksReactor, UnitTypeId::Barracks),
+			(UnitTypeId::FactoryTechLab, UnitTypeId::Factory),
+			(UnitTypeId::FactoryReactor, UnitTypeId::Factory),
+			(UnitTypeId::StarportTechLab, UnitTypeId::Starport),
+			(UnitTypeId::StarportReactor, UnitTypeId::Starport),
+		] {
+			nodes.entry(Tech::Unit(addon)).or_insert_with(|| TechNode {
+				tech: Tech::Unit(addon),
+				producers: vec![producer],
+				requirements: vec![],
+				is_morph: false,
+			});
+		}
+
+		for (&upgrade, researchers) in ALL_RESEARCHERS.iter() {
+			nodes.insert(
+				Tech::Upgrade(upgrade),
+				TechNode {
+					tech: Tech::Upgrade(upgrade),
+					producers: researchers.clone(),
+					requirements: UPGRADE_REQUIREMENTS.get(&upgrade).cloned().unwrap_or_default(),
+					is_morph: false,
+				},
+			);
+		}
+
+		Self { nodes }
+	}
+}
+impl TechTree {
+	/// Constructs tech tree from given nodes.
+	pub fn from_nodes<I: IntoIterator<Item = TechNode>>(nodes: I) -> Self {
+		Self {
+			nodes: nodes.into_iter().map(|node| (node.tech, node)).collect(),
+		}
+	}
+	/// Returns node of given item or `None` if it's not in the tree.
+	pub fn get<T: Into<Tech>>(&self, tech: T) -> Option<&TechNode> {
+		self.nodes.get(&tech.into())
+	}
+	/// Returns iterator over all nodes of the tree.
+	pub fn nodes(&self) -> impl Iterator<Item = &TechNode> {
+		self.nodes.values()
+	}
+	/// Returns all direct requirements of given item including it's producer.
+	///
+	/// Producer requirement is omitted for workers and larva, since they're always available.
+	/// Only the first of [`producers`](TechNode::producers) is required, others are its
+	/// advanced or alternative forms (e.g. `OrbitalCommand` for units of `CommandCenter`).
+	pub fn requirements<T: Into<Tech>>(&self, tech: T) -> Vec<Requirement> {
+		let node = match self.get(tech) {
+			Some(node) => node,
+			None => return vec![],
+		};
+		let mut requirements = vec![];
+		if let Some(&producer) = node.producers.first() {
+			match producer {
+				UnitTypeId::Larva => requirements.push(Requirement::Unit(UnitTypeId::Hatchery)),
+				producer if producer.is_worker() => {}
+				producer => requirements.push(Requirement::Unit(producer)),
+			}
+		}
+		requirements.extend(node.requirements.iter().copied());
+		requirements
+	}
+
+	/// Checks if given requirement is satisfied by current state of the bot.
+	///
+	/// [`Addon`](Requirement::Addon) requirement is satisfied when any ready structure
+	/// has ready addon of given type attached. Use [`is_satisfied_by`](Self::is_satisfied_by)
+	/// to check it for particular producer.
+	pub fn is_satisfied(&self, bot: &Bot, requirement: Requirement) -> bool {
+		match requirement {
+			Requirement::Unit(unit) => satisfying(unit).any(|id| bot.current_units.contains_key(&id)),
+			Requirement::Addon(_) => bot
+				.units
+				.my
+				.structures
+				.iter()
+				.any(|s| s.is_ready() && self.is_satisfied_by(bot, s, requirement)),
+			Requirement::Upgrade(upgrade) => bot.has_upgrade(upgrade),
+		}
+	}
+	/// Checks if given requirement is satisfied for given producer.
+	///
+	/// The same as [`is_satisfied`](Self::is_satisfied), but [`Addon`](Requirement::Addon)
+	/// requirement is satisfied only if addon is attached to this producer.
+	pub fn is_satisfied_by(&self, bot: &Bot, producer: &Unit, requirement: Requirement) -> bool {
+		match requirement {
+			Requirement::Addon(addon) => producer
+				.addon_tag()
+				.and_then(|tag| bot.units.my.structures.get(tag))
+				.is_some_and(|a| a.type_id() == addon && a.is_ready()),
+			_ => self.is_satisfied(bot, requirement),
+		}
+	}
+	/// Checks if given requirement is in progress (e.g. structure is being built or upgrade researched).
+	pub fn is_in_progress(&self, bot: &Bot, requirement: Requirement) -> bool {
+		match requirement {
+			Requirement::Unit(unit) | Requirement::Addon(unit) => satisfying(unit).any(|id| {
+				(bot.game_data.units.contains_key(&id) && bot.counter().ordered().count(id) > 0)
+					|| bot
+						.units
+						.my
+						.structures
+						.iter()
+						.any(|s| s.type_id() == id && !s.is_ready())
+			}),
+			Requirement::Upgrade(upgrade) => bot.is_ordered_upgrade(upgrade),
+		}
+	}
+	/// Checks if given item can be produced right now (ignoring cost).
+	pub fn is_available<T: Into<Tech>>(&self, bot: &Bot, tech: T) -> bool {
+		self.missing(bot, tech).is_empty()
+	}
+	/// Returns direct requirements of given item, which aren't satisfied yet.
+	pub fn missing<T: Into<Tech>>(&self, bot: &Bot, tech: T) -> Vec<Requirement> {
+		self.requirements(tech)
+			.into_iter()
+			.filter(|req| !self.is_satisfied(bot, *req))
+			.collect()
+	}
+	/// Returns ordered list of items required to unlock given item, which aren't satisfied yet
+	/// and not in progress.
+	///
+	/// The list doesn't contain item itself and items producible without requirements
+	/// (e.g. workers), requirements always go before items depending on them.
+	pub fn path<T: Into<Tech>>(&self, bot: &Bot, tech: T) -> Vec<Tech> {
+		let mut path = vec![];
+		let mut visited = FxHashSet::default();
+		self.visit(bot, tech.into(), &mut path, &mut visited);
+		path
+	}
+	/// Returns ordered list of structures required to unlock given item.
+	/// The same as [`path`](Self::path), but without units and upgrades.
+	pub fn build_order<T: Into<Tech>>(&self, bot: &Bot, tech: T) -> Vec<UnitTypeId> {
+		self.path(bot, tech)
+			.into_iter()
+			.filter_map(|tech| match tech {
+				Tech::Unit(id) if id.is_structure() => Some(id),
+				_ => None,
+			})
+			.collect()
+	}
+
+	fn visit(&self, bot: &Bot, tech: Tech, path: &mut Vec<Tech>, visited: &mut FxHashSet<Tech>) {
+		if !visited.insert(tech) {
+			return;
+		}
+		for req in self.requirements(tech) {
+			if self.is_satisfied(bot, req) || self.is_in_progress(bot, req) {
+				continue;
+			}
+			let next = match req {
+				Requirement::Unit(unit) | Requirement::Addon(unit) => Tech::Unit(unit),
+				Requirement::Upgrade(upgrade) => Tech::Upgrade(upgrade),
+			};
+			if visited.contains(&next) {
+				continue;
+			}
+			self.visit(bot, next, path, visited);
+			path.push(next);
+		}
+	}
+}
+
+fn satisfying(unit: UnitTypeId) -> impl Iterator<Item = UnitTypeId> {
+	std::iter::once(unit).chain(SATISFIED_BY.get(&unit).into_iter().flatten().copied())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::Rs,
+		game_data::{GameData, UnitTypeData},
+		ids::AbilityId,
+		unit::tests::{from_proto, proto, type_data},
+	};
+
+	fn bot_with(units: &[UnitTypeId]) -> Bot {
+		let mut bot = Bot::default();
+		for id in units {
+			*bot.current_units.entry(*id).or_default() += 1;
+		}
+		bot
+	}
+	/// Adds barracks with given tag and barracks techlab with tag `tag + 1` if build progress is given.
+	fn add_barracks(bot: &mut Bot, tag: u64, techlab: Option<f32>) {
+		let mut barracks = proto(tag, UnitTypeId::Barracks, (10.5, 10.5 * tag as f32));
+		if let Some(progress) = techlab {
+			barracks.set_add_on_tag(tag + 1);
+			let mut addon = proto(tag + 1, UnitTypeId::BarracksTechLab, (13.0, 10.0 * tag as f32));
+			addon.set_build_progress(progress);
+			bot.units.my.structures.push(from_proto(&addon));
+		}
+		bot.units.my.structures.push(from_proto(&barracks));
+	}
+
+	#[test]
+	fn requirements_include_producer_and_addon() {
+		assert_eq!(
+			TECH_TREE.requirements(UnitTypeId::Marauder),
+			vec![
+				Requirement::Unit(UnitTypeId::Barracks),
+				Requirement::Addon(UnitTypeId::BarracksTechLab),
+			]
+		);
+	}
+
+	#[test]
+	fn requirements_skip_workers_and_replace_larva() {
+		assert_eq!(
+			TECH_TREE.requirements(UnitTypeId::Zergling),
+			vec![
+				Requirement::Unit(UnitTypeId::Hatchery),
+				Requirement::Unit(UnitTypeId::SpawningPool),
+			]
+		);
+		assert!(!TECH_TREE
+			.requirements(UnitTypeId::SpawningPool)
+			.contains(&Requirement::Unit(UnitTypeId::Drone)));
+	}
+
+	#[test]
+	fn requirements_are_not_duplicated() {
+		assert_eq!(
+			TECH_TREE.requirements(UnitTypeId::Mothership),
+			vec![
+				Requirement::Unit(UnitTypeId::Nexus),
+				Requirement::Unit(UnitTypeId::FleetBeacon),
+			]
+		);
+	}
+
+	#[test]
+	fn upgrade_requires_previous_level() {
+		let requirements = TECH_TREE.requirements(UpgradeId::ZergMeleeWeaponsLevel2);
+		assert_eq!(requirements[0], Requirement::Unit(UnitTypeId::EvolutionChamber));
+		assert!(requirements.contains(&Requirement::Upgrade(UpgradeId::ZergMeleeWeaponsLevel1)));
+		assert!(requirements.contains(&Requirement::Unit(UnitTypeId::Lair)));
+	}
+
+	#[test]
+	fn morphs_and_addons_are_in_tree() {
+		assert!(TECH_TREE.get(UnitTypeId::Lair).unwrap().is_morph);
+		assert!(!TECH_TREE.get(UnitTypeId::Barracks).unwrap().is_morph);
+		assert_eq!(
+			TECH_TREE.get(UnitTypeId::FactoryReactor).unwrap().producers,
+			vec![UnitTypeId::Factory]
+		);
+	}
+
+	#[test]
+	fn advanced_forms_satisfy_requirement() {
+		let ids = satisfying(UnitTypeId::Hatchery).collect::<Vec<_>>();
+		assert_eq!(
+			ids,
+			vec![UnitTypeId::Hatchery, UnitTypeId::Lair, UnitTypeId::Hive]
+		);
+		assert_eq!(
+			satisfying(UnitTypeId::Zealot).collect::<Vec<_>>(),
+			vec![UnitTypeId::Zealot]
+		);
+	}
+
+	#[test]
+	fn missing_requirements() {
+		let techlab = Requirement::Addon(UnitTypeId::BarracksTechLab);
+		let bot = bot_with(&[]);
+		assert_eq!(
+			TECH_TREE.missing(&bot, UnitTypeId::Marauder),
+			vec![Requirement::Unit(UnitTypeId::Barracks), techlab]
+		);
+
+		let mut bot = bot_with(&[UnitTypeId::BarracksFlying]);
+		assert_eq!(TECH_TREE.missing(&bot, UnitTypeId::Marauder), vec![techlab]);
+		add_barracks(&mut bot, 1, Some(1.0));
+		assert!(TECH_TREE.is_available(&bot, UnitTypeId::Marauder));
+
+		let bot = bot_with(&[UnitTypeId::Hive, UnitTypeId::EvolutionChamber]);
+		assert_eq!(
+			TECH_TREE.missing(&bot, UpgradeId::ZergMeleeWeaponsLevel2),
+			vec![Requirement::Upgrade(UpgradeId::ZergMeleeWeaponsLevel1)]
+		);
+	}
+
+	#[test]
+	fn addon_attached_to_other_producer() {
+		let requirement = Requirement::Addon(UnitTypeId::BarracksTechLab);
+		let mut bot = bot_with(&[UnitTypeId::Barracks]);
+		add_barracks(&mut bot, 1, Some(1.0));
+		add_barracks(&mut bot, 10, None);
+		let with_techlab = bot.units.my.structures.get(1).unwrap();
+		let without_techlab = bot.units.my.structures.get(10).unwrap();
+
+		assert!(TECH_TREE.is_satisfied(&bot, requirement));
+		assert!(TECH_TREE.is_satisfied_by(&bot, with_techlab, requirement));
+		assert!(!TECH_TREE.is_satisfied_by(&bot, without_techlab, requirement));
+		// Other requirements don't depend on producer
+		let barracks = Requirement::Unit(UnitTypeId::Barracks);
+		assert!(TECH_TREE.is_satisfied_by(&bot, without_techlab, barracks));
+
+		let mut bot = bot_with(&[UnitTypeId::Barracks]);
+		add_barracks(&mut bot, 1, Some(0.5));
+		let building_techlab = bot.units.my.structures.get(1).unwrap();
+		assert!(!TECH_TREE.is_satisfied(&bot, requirement));
+		assert!(!TECH_TREE.is_satisfied_by(&bot, building_techlab, requirement));
+		assert!(TECH_TREE.is_in_progress(&bot, requirement));
+	}
+
+	#[test]
+	fn path_to_tech() {
+		let bot = bot_with(&[UnitTypeId::Hatchery]);
+		assert_eq!(
+			TECH_TREE.path(&bot, UnitTypeId::Hydralisk),
+			vec![
+				Tech::Unit(UnitTypeId::SpawningPool),
+				Tech::Unit(UnitTypeId::Lair),
+				Tech::Unit(UnitTypeId::HydraliskDen),
+			]
+		);
+		assert_eq!(
+			TECH_TREE.path(&bot, UpgradeId::ZergMeleeWeaponsLevel2),
+			vec![
+				Tech::Unit(UnitTypeId::EvolutionChamber),
+				Tech::Upgrade(UpgradeId::ZergMeleeWeaponsLevel1),
+				Tech::Unit(UnitTypeId::SpawningPool),
+				Tech::Unit(UnitTypeId::Lair),
+			]
+		);
+		assert_eq!(
+			TECH_TREE.build_order(&bot, UpgradeId::ZergMeleeWeaponsLevel2),
+			vec![
+				UnitTypeId::EvolutionChamber,
+				UnitTypeId::SpawningPool,
+				UnitTypeId::Lair,
+			]
+		);
+
+		let bot = bot_with(&[UnitTypeId::Hive]);
+		assert_eq!(
+			TECH_TREE.path(&bot, UnitTypeId::Zergling),
+			vec![Tech::Unit(UnitTypeId::SpawningPool)]
+		);
+	}
+
+	#[test]
+	fn requirements_in_progress_skipped() {
+		let pool = Requirement::Unit(UnitTypeId::SpawningPool);
+		let mut bot = bot_with(&[UnitTypeId::Hatchery]);
+		let mut building = proto(1, UnitTypeId::SpawningPool, (10.5, 10.5));
+		building.set_build_progress(0.5);
+		bot.units.my.structures.push(from_proto(&building));
+		assert!(TECH_TREE.is_in_progress(&bot, pool));
+		assert!(TECH_TREE.path(&bot, UnitTypeId::Lair).is_empty());
+
+		// Ordered to drone, but not placed yet
+		let mut bot = bot_with(&[UnitTypeId::Hatchery]);
+		let mut game_data = GameData::default();
+		game_data.units.insert(
+			UnitTypeId::SpawningPool,
+			UnitTypeData {
+				ability: Some(AbilityId::ZergBuildSpawningPool),
+				..type_data(UnitTypeId::SpawningPool)
+			},
+		);
+		bot.game_data = Rs::new(game_data);
+		assert!(!TECH_TREE.is_in_progress(&bot, pool));
+		bot.orders.insert(AbilityId::ZergBuildSpawningPool, 1);
+		assert!(TECH_TREE.is_in_progress(&bot, pool));
+		assert!(TECH_TREE.path(&bot, UnitTypeId::Lair).is_empty());
+		assert!(!TECH_TREE.is_in_progress(&bot, Requirement::Unit(UnitTypeId::Lair)));
+	}
+}
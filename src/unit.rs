@@ -8,7 +8,7 @@ use crate::{
 		RaceValues, ANTI_ARMOR_BUFF, DAMAGE_BONUS_PER_UPGRADE, FRAMES_PER_SECOND, MISSED_WEAPONS,
 		OFF_CREEP_SPEED_UPGRADES, SPEED_BUFFS, SPEED_ON_CREEP, SPEED_UPGRADES, WARPGATE_ABILITIES,
 	},
-	distance::Distance,
+	distance::{cmp, Distance},
 	game_data::{Attribute, Cost, GameData, TargetType, UnitTypeData, Weapon},
 	game_state::Alliance,
 	geometry::{Point2, Point3},
@@ -53,6 +53,7 @@ pub(crate) struct DataForUnit {
 	pub max_cooldowns: Rw<FxHashMap<UnitTypeId, f32>>,
 	pub last_units_hits: Rw<FxHashMap<u64, u32>>,
 	pub last_units_seen: Rw<FxHashMap<u64, u32>>,
+	pub last_units: Rw<FxHashMap<u64, (Rs<UnitBase>, u32)>>,
 	pub abilities_units: Rw<FxHashMap<u64, FxHashSet<AbilityId>>>,
 	pub upgrades: Rw<FxHashSet<UpgradeId>>,
 	pub enemy_upgrades: Rw<FxHashSet<UpgradeId>>,
@@ -555,6 +556,80 @@ impl Unit {
 			0
 		}
 	}
+	/// Returns unit as it was on previous step or `None` if it wasn't seen on previous step.
+	pub fn previous(&self) -> Option<Unit> {
+		self.data.last_units.read_lock().get(&self.tag()).map(|(base, _)| Unit {
+			data: Rs::clone(&self.data),
+			base: Rs::clone(base),
+		})
+	}
+	/// Observed velocity of unit in distance per game loop since previous step.
+	///
+	/// Returns `None` if unit wasn't seen on previous step.
+	pub fn velocity(&self) -> Option<Point2> {
+		let last_units = self.data.last_units.read_lock();
+		let (base, last_loop) = last_units.get(&self.tag())?;
+		let frames = self.data.game_loop.get_locked().saturating_sub(*last_loop);
+		if frames == 0 {
+			return None;
+		}
+		Some((self.position() - base.position) / frames as f32)
+	}
+	/// Checks if unit has moved since previous step.
+	pub fn has_moved(&self) -> bool {
+		self.velocity().is_some_and(|velocity| velocity.len_squared() > f32::EPSILON)
+	}
+	/// Change of health since previous step or `0` if it's unknown.
+	pub fn health_delta(&self) -> i32 {
+		self.delta(|u| u.health())
+	}
+	/// Change of shield since previous step or `0` if it's unknown.
+	pub fn shield_delta(&self) -> i32 {
+		self.delta(|u| u.shield())
+	}
+	/// Change of energy since previous step or `0` if it's unknown.
+	pub fn energy_delta(&self) -> i32 {
+		self.delta(|u| u.energy())
+	}
+	fn delta<F: Fn(&Unit) -> Option<u32>>(&self, f: F) -> i32 {
+		match (self.previous().and_then(|u| f(&u)), f(self)) {
+			(Some(last), Some(current)) => current as i32 - last as i32,
+			_ => 0,
+		}
+	}
+	/// Buffs unit has got since previous step.
+	pub fn buffs_gained(&self) -> Vec<BuffId> {
+		match self.previous() {
+			Some(last) => self.buffs().difference(last.buffs()).copied().collect(),
+			None => vec![],
+		}
+	}
+	/// Buffs unit has lost since previous step.
+	pub fn buffs_lost(&self) -> Vec<BuffId> {
+		match self.previous() {
+			Some(last) => last.buffs().difference(self.buffs()).copied().collect(),
+			None => vec![],
+		}
+	}
+	/// Previous type of unit if it has changed since previous step (e.g. unit morphed, sieged or burrowed).
+	pub fn previous_type(&self) -> Option<UnitTypeId> {
+		self.previous()
+			.map(|u| u.type_id())
+			.filter(|type_id| *type_id != self.type_id())
+	}
+	/// Checks if orders of unit have changed since previous step. Progress of orders isn't compared.
+	///
+	/// Doesn't work with enemies.
+	pub fn orders_changed(&self) -> bool {
+		self.previous().is_some_and(|previous| {
+			let (last, current) = (previous.orders(), self.orders());
+			last.len() != current.len()
+				|| last
+					.iter()
+					.zip(current)
+					.any(|(o1, o2)| o1.ability != o2.ability || o1.target != o2.target)
+		})
+	}
 	/// Abilities available for unit to use.
 	///
 	/// Ability won't be available if it's on cooldown, unit
@@ -842,6 +917,75 @@ impl Unit {
 	pub fn distance_to_weapon_ready(&self) -> f32 {
 		self.real_speed() / FRAMES_PER_SECOND * self.weapon_cooldown().unwrap_or(0.0)
 	}
+	/// Estimated movement of unit per one game loop.
+	///
+	/// Own units move towards target point of their order, other units are extrapolated
+	/// from their positions on previous and current step. If unit wasn't seen on previous step,
+	/// it's considered to move in the direction it's facing when it has orders and to stand still otherwise.
+	pub fn movement(&self) -> Point2 {
+		let speed = self.real_speed() / FRAMES_PER_SECOND;
+		let pos = self.position();
+		if let Some(target) = self.target_pos() {
+			if !pos.is_closer(0.01, target) {
+				return (target - pos).normalize() * speed;
+			}
+		}
+		if let Some(velocity) = self.velocity() {
+			return velocity;
+		}
+		if self.is_idle() {
+			Point2::default()
+		} else {
+			Point2::new(self.facing().cos(), self.facing().sin()) * speed
+		}
+	}
+	/// Predicts position of unit after given number of game loops using its [`movement`](Self::movement).
+	///
+	/// Units moving to target point stop there.
+	pub fn predict_position(&self, frames: f32) -> Point2 {
+		let pos = self.position();
+		let shift = self.movement() * frames;
+		if let Some(target) = self.target_pos() {
+			if shift.len_squared() >= pos.distance_squared(target) {
+				return target;
+			}
+		}
+		pos + shift
+	}
+	/// Returns the closest point where unit can catch up with given target,
+	/// considering target keeps its current [`movement`](Self::movement).
+	///
+	/// Returns `None` if unit is too slow to catch up.
+	pub fn intercept_point(&self, target: &Unit) -> Option<Point2> {
+		let offset = target.position() - self.position();
+		if offset.len_squared() < f32::EPSILON {
+			return Some(target.position());
+		}
+
+		// Solving |offset + movement * t| = speed * t for the smallest t >= 0
+		let speed = self.real_speed() / FRAMES_PER_SECOND;
+		let movement = target.movement();
+		let a = movement.len_squared() - speed * speed;
+		let b = 2.0 * offset.dot(movement);
+		let c = offset.len_squared();
+		let time = if a.abs() < f32::EPSILON {
+			if b >= 0.0 {
+				return None;
+			}
+			-c / b
+		} else {
+			let d = b * b - 4.0 * a * c;
+			if d < 0.0 {
+				return None;
+			}
+			let sqrt = d.sqrt();
+			[(-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a)]
+				.into_iter()
+				.filter(|t| *t >= 0.0)
+				.min_by(cmp)?
+		};
+		Some(target.position() + movement * time)
+	}
 	/// Attributes of unit, dependent on it's type.
 	pub fn attributes(&self) -> &[Attribute] {
 		self.type_data().map_or(&[], |data| data.attributes.as_slice())
@@ -1706,6 +1850,30 @@ impl Unit {
 	pub fn ordered_ability(&self) -> Option<AbilityId> {
 		self.orders().first().map(|order| order.ability)
 	}
+	/// Checks if the only order of unit is given command, so giving it again won't change anything.
+	/// Target points closer than `tolerance` to each other are considered the same.
+	///
+	/// Doesn't work with enemies.
+	pub fn has_order(&self, ability: AbilityId, target: Target, tolerance: f32) -> bool {
+		let order = match self.orders() {
+			[order] => order,
+			_ => return false,
+		};
+		let general = |ability: AbilityId| {
+			self.data
+				.game_data
+				.abilities
+				.get(&ability)
+				.and_then(|data| data.remaps_to_ability_id)
+				.unwrap_or(ability)
+		};
+
+		general(order.ability) == general(ability)
+			&& match (order.target, target) {
+				(Target::Pos(p1), Target::Pos(p2)) => p1.distance_squared(p2) <= tolerance * tolerance,
+				(t1, t2) => t1 == t2,
+			}
+	}
 	/// Checks if unit don't have any orders currently.
 	pub fn is_idle(&self) -> bool {
 		self.orders().is_empty()
@@ -1930,6 +2098,18 @@ impl Unit {
 			.or_default()
 			.push(self.tag());
 	}
+	/// Orders unit to execute given command even if it's identical to unit's current order.
+	///
+	/// Such commands are never dropped by [`command_filter`](crate::bot::Bot::command_filter).
+	pub fn force_command(&self, ability: AbilityId, target: Target, queue: bool) {
+		let mut commander = self.data.commander.write_lock();
+		commander
+			.commands
+			.entry((ability, target, queue))
+			.or_default()
+			.push(self.tag());
+		commander.forced.insert((self.tag(), ability, target, queue));
+	}
 	/// Orders unit to use given ability (This is equivalent of `unit.command(ability, Target::None, queue)`).
 	pub fn use_ability(&self, ability: AbilityId, queue: bool) {
 		self.command(ability, Target::None, queue)
@@ -2284,3 +2464,307 @@ impl Radius for Unit {
 		self.radius()
 	}
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::{
+		game_data::{AbilityData, AbilityTarget},
+		pixel_map::{Pixel, Visibility},
+		IntoProto,
+	};
+	use ndarray::Array2;
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+
+	/// Proto of own visible unit, which can be modified before creating [`Unit`] from it.
+	pub(crate) fn proto(tag: u64, type_id: UnitTypeId, position: (f32, f32)) -> ProtoUnit {
+		let mut u = ProtoUnit::new();
+		u.set_tag(tag);
+		u.set_unit_type(type_id as u32);
+		u.set_alliance(ProtoAlliance::value_Self);
+		u.set_display_type(ProtoDisplayType::Visible);
+		u.set_pos(Point3::new(position.0, position.1, 0.0).into_proto());
+		u.set_radius(0.5);
+		u.set_build_progress(1.0);
+		u.set_health(100.0);
+		u.set_health_max(100.0);
+		u
+	}
+	pub(crate) fn from_proto(u: &ProtoUnit) -> Unit {
+		let visibility = Array2::from_elem((256, 256), Visibility::Visible);
+		Unit::from_proto(Default::default(), &visibility, u)
+	}
+	pub(crate) fn unit(tag: u64, type_id: UnitTypeId, position: (f32, f32)) -> Unit {
+		from_proto(&proto(tag, type_id, position))
+	}
+
+	/// Type data without abilities, weapons and costs.
+	pub(crate) fn type_data(id: UnitTypeId) -> UnitTypeData {
+		UnitTypeData {
+			id,
+			name: String::new(),
+			available: true,
+			cargo_size: 0,
+			mineral_cost: 0,
+			vespene_cost: 0,
+			food_required: 0.0,
+			food_provided: 0.0,
+			ability: None,
+			race: Race::Random,
+			build_time: 0.0,
+			has_vespene: false,
+			has_minerals: false,
+			sight_range: 0.0,
+			tech_alias: vec![],
+			unit_alias: None,
+			tech_requirement: None,
+			require_attached: false,
+			attributes: vec![],
+			movement_speed: 0.0,
+			armor: 0,
+			weapons: vec![],
+		}
+	}
+	/// Data of ability, which remaps to given general ability.
+	pub(crate) fn remapped_ability(id: AbilityId, general: AbilityId) -> AbilityData {
+		AbilityData {
+			id,
+			link_name: String::new(),
+			link_index: 0,
+			button_name: None,
+			friendly_name: None,
+			hotkey: None,
+			remaps_to_ability_id: Some(general),
+			available: true,
+			target: AbilityTarget::PointOrUnit,
+			allow_minimap: false,
+			allow_autocast: false,
+			is_building: false,
+			footprint_radius: None,
+			is_instant_placement: false,
+			cast_range: None,
+		}
+	}
+	/// Shared data with weapons and speed of marine, zergling and sieged tank on map without creep.
+	pub(crate) fn combat_data() -> SharedUnitData {
+		let mut game_data = GameData::default();
+		for (id, target, damage, range, cooldown, speed) in [
+			(UnitTypeId::Marine, TargetType::Any, 6, 5.0, 0.6, 3.15),
+			(UnitTypeId::Zergling, TargetType::Ground, 5, 0.1, 0.5, 4.13),
+			(
+				UnitTypeId::SiegeTankSieged,
+				TargetType::Ground,
+				40,
+				13.0,
+				2.0,
+				0.0,
+			),
+		] {
+			let weapon = Weapon {
+				target,
+				damage,
+				damage_bonus: vec![],
+				attacks: 1,
+				range,
+				speed: cooldown,
+			};
+			let data = UnitTypeData {
+				movement_speed: speed,
+				weapons: vec![weapon],
+				..type_data(id)
+			};
+			game_data.units.insert(id, data);
+		}
+		let data = DataForUnit {
+			game_data: Rs::new(game_data),
+			..Default::default()
+		};
+		*data.creep.write_lock() = Array2::from_elem((256, 256), Pixel::Set);
+		Rs::new(data)
+	}
+	/// Shared data where given unit types are structures.
+	pub(crate) fn structure_data(types: &[UnitTypeId]) -> SharedUnitData {
+		let mut game_data = GameData::default();
+		for id in types {
+			let data = UnitTypeData {
+				attributes: vec![Attribute::Structure],
+				..type_data(*id)
+			};
+			game_data.units.insert(*id, data);
+		}
+		Rs::new(DataForUnit {
+			game_data: Rs::new(game_data),
+			..Default::default()
+		})
+	}
+	/// Creates unit with given shared data, e.g. from [`combat_data`].
+	pub(crate) fn with_data(data: &SharedUnitData, u: &ProtoUnit) -> Unit {
+		let visibility = Array2::from_elem((256, 256), Visibility::Visible);
+		Unit::from_proto(Rs::clone(data), &visibility, u)
+	}
+
+	/// Enemy zergling at (11, 0), which was at given position 10 game loops ago.
+	fn moving_zergling(data: &SharedUnitData, previous: (f32, f32)) -> Unit {
+		let mut u = proto(1, UnitTypeId::Zergling, previous);
+		u.set_alliance(ProtoAlliance::Enemy);
+		let last = with_data(data, &u);
+		data.last_units.write_lock().insert(1, (Rs::clone(&last.base), 0));
+		data.game_loop.set_locked(10);
+
+		u.set_pos(Point3::new(11.0, 0.0, 0.0).into_proto());
+		with_data(data, &u)
+	}
+
+	#[test]
+	fn velocity_from_previous_step() {
+		let data = combat_data();
+		let zergling = moving_zergling(&data, (10.0, 0.0));
+		assert!(zergling
+			.velocity()
+			.unwrap()
+			.is_closer(1e-4, Point2::new(0.1, 0.0)));
+		assert!(zergling.has_moved());
+		assert!(zergling
+			.predict_position(10.0)
+			.is_closer(1e-3, Point2::new(12.0, 0.0)));
+
+		let idle = with_data(&data, &proto(2, UnitTypeId::Marine, (5.0, 5.0)));
+		assert_eq!(idle.velocity(), None);
+		assert!(!idle.has_moved());
+		assert_eq!(idle.movement(), Point2::default());
+		assert_eq!(idle.predict_position(10.0), Point2::new(5.0, 5.0));
+	}
+
+	#[test]
+	fn intercept_moving_target() {
+		let data = combat_data();
+		let speed = 3.15 / FRAMES_PER_SECOND;
+
+		// Target runs towards the marine
+		let zergling = moving_zergling(&data, (10.0, 0.0));
+		let marine = with_data(&data, &proto(2, UnitTypeId::Marine, (21.0, 0.0)));
+		let time = 10.0 / (0.1 + speed);
+		let point = marine.intercept_point(&zergling).unwrap();
+		assert!(point.is_closer(1e-2, Point2::new(11.0 + 0.1 * time, 0.0)));
+
+		// Target runs away faster than the marine
+		let zergling = moving_zergling(&data, (6.0, 0.0));
+		let marine = with_data(&data, &proto(2, UnitTypeId::Marine, (0.0, 0.0)));
+		assert_eq!(marine.intercept_point(&zergling), None);
+	}
+
+	#[test]
+	fn intercept_standing_target() {
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (0.0, 0.0)));
+		let target = with_data(&data, &proto(2, UnitTypeId::Marine, (5.0, 5.0)));
+		let point = marine.intercept_point(&target).unwrap();
+		assert!(point.is_closer(1e-3, target.position()));
+		assert_eq!(marine.intercept_point(&marine), Some(marine.position()));
+	}
+
+	fn move_order(pos: (f32, f32), progress: f32) -> sc2_proto::raw::UnitOrder {
+		let mut order = sc2_proto::raw::UnitOrder::new();
+		order.set_ability_id(AbilityId::MoveMove as u32);
+		order.set_target_world_space_pos(Point3::new(pos.0, pos.1, 0.0).into_proto());
+		order.set_progress(progress);
+		order
+	}
+	/// Unit created from `current`, which was `previous` on the last step 10 game loops ago.
+	fn with_previous(previous: &ProtoUnit, current: &ProtoUnit) -> Unit {
+		let data = combat_data();
+		let last = with_data(&data, previous);
+		data.last_units
+			.write_lock()
+			.insert(last.tag(), (Rs::clone(&last.base), 0));
+		data.game_loop.set_locked(10);
+		with_data(&data, current)
+	}
+
+	#[test]
+	fn deltas_since_previous_step() {
+		let mut previous = proto(1, UnitTypeId::Stalker, (0.0, 0.0));
+		previous.set_shield(50.0);
+		previous.set_energy(40.0);
+		let mut current = previous.clone();
+		current.set_health(80.0);
+		current.set_shield(20.0);
+		current.set_energy(50.0);
+
+		let u = with_previous(&previous, &current);
+		assert_eq!(u.previous().unwrap().health(), Some(100));
+		assert_eq!(u.health_delta(), -20);
+		assert_eq!(u.shield_delta(), -30);
+		assert_eq!(u.energy_delta(), 10);
+
+		let new = from_proto(&current);
+		assert!(new.previous().is_none());
+		assert_eq!(new.health_delta(), 0);
+	}
+
+	#[test]
+	fn buffs_and_type_changes() {
+		let mut previous = proto(1, UnitTypeId::SiegeTank, (0.0, 0.0));
+		previous.mut_buff_ids().push(BuffId::Stimpack as u32);
+		let mut current = proto(1, UnitTypeId::SiegeTankSieged, (0.0, 0.0));
+		current.mut_buff_ids().push(BuffId::GuardianShield as u32);
+
+		let u = with_previous(&previous, &current);
+		assert_eq!(u.buffs_gained(), vec![BuffId::GuardianShield]);
+		assert_eq!(u.buffs_lost(), vec![BuffId::Stimpack]);
+		assert_eq!(u.previous_type(), Some(UnitTypeId::SiegeTank));
+
+		let u = with_previous(&previous, &previous);
+		assert!(u.buffs_gained().is_empty());
+		assert_eq!(u.previous_type(), None);
+	}
+
+	#[test]
+	fn changed_orders() {
+		let mut previous = proto(1, UnitTypeId::Marine, (0.0, 0.0));
+		previous.mut_orders().push(move_order((5.0, 5.0), 0.0));
+
+		// Progress isn't compared
+		let mut current = proto(1, UnitTypeId::Marine, (1.0, 1.0));
+		current.mut_orders().push(move_order((5.0, 5.0), 0.5));
+		assert!(!with_previous(&previous, &current).orders_changed());
+
+		let mut current = proto(1, UnitTypeId::Marine, (1.0, 1.0));
+		current.mut_orders().push(move_order((8.0, 5.0), 0.0));
+		assert!(with_previous(&previous, &current).orders_changed());
+
+		let mut current = previous.clone();
+		current.mut_orders().push(move_order((8.0, 5.0), 0.0));
+		assert!(with_previous(&previous, &current).orders_changed());
+
+		assert!(!from_proto(&current).orders_changed());
+	}
+
+	#[test]
+	fn order_within_tolerance() {
+		let mut game_data = GameData::default();
+		game_data.abilities.insert(
+			AbilityId::MoveMove,
+			remapped_ability(AbilityId::MoveMove, AbilityId::Move),
+		);
+		let data = Rs::new(DataForUnit {
+			game_data: Rs::new(game_data),
+			..Default::default()
+		});
+		let mut marine = proto(1, UnitTypeId::Marine, (1.0, 1.0));
+		marine.mut_orders().push(move_order((5.0, 5.0), 0.5));
+		let target = Target::Pos(Point2::new(5.3, 5.0));
+
+		let moving = with_data(&data, &marine);
+		assert!(moving.has_order(AbilityId::MoveMove, target, 0.5));
+		assert!(moving.has_order(AbilityId::Move, target, 0.5));
+		assert!(!moving.has_order(AbilityId::MoveMove, target, 0.1));
+		assert!(!moving.has_order(AbilityId::AttackAttack, target, 0.5));
+		assert!(!moving.has_order(AbilityId::MoveMove, Target::Tag(2), 0.5));
+
+		// Only the single order is compared
+		marine.mut_orders().push(move_order((8.0, 5.0), 0.0));
+		assert!(!with_data(&data, &marine).has_order(AbilityId::MoveMove, target, 0.5));
+		assert!(!unit(2, UnitTypeId::Marine, (1.0, 1.0)).has_order(AbilityId::MoveMove, target, 0.5));
+	}
+}
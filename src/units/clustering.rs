@@ -0,0 +1,312 @@
+//! Clustering of units by their positions.
+//!
+//! Useful to split enemy army into separate groups every step:
+//! ```
+//! let (groups, stragglers) = self.units.enemy.units.dbscan(8.0, 3);
+//! ```
+#![warn(missing_docs)]
+
+use super::{spatial::SpatialIndex, Units};
+use crate::{
+	distance::{cmp, Distance},
+	geometry::Point2,
+	unit::Unit,
+};
+use rustc_hash::FxHashMap;
+
+/// Maximum number of iterations of k-means.
+const KMEANS_ITERATIONS: usize = 100;
+
+/// Weight of every unit used in clustering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClusterWeight {
+	/// Every unit weights `1`.
+	#[default]
+	Count,
+	/// Units weight their supply cost.
+	Supply,
+	/// Units weight their maximum of ground and air dps.
+	Dps,
+}
+impl ClusterWeight {
+	/// Returns weight of given unit.
+	pub fn of(self, u: &Unit) -> f32 {
+		match self {
+			ClusterWeight::Count => 1.0,
+			ClusterWeight::Supply => u.supply_cost(),
+			ClusterWeight::Dps => u.ground_dps().max(u.air_dps()),
+		}
+	}
+}
+
+impl Units {
+	/// Splits units into clusters with DBSCAN.
+	///
+	/// Units closer than `distance` to each other are neighbors, and unit with at least
+	/// `min_points` neighbors (counting itself) forms a cluster.
+	///
+	/// Returns clusters and noise (units which don't belong to any cluster).
+	pub fn dbscan(&self, distance: f32, min_points: usize) -> (Vec<Units>, Units) {
+		self.dbscan_weighted(distance, min_points as f32, ClusterWeight::Count)
+	}
+	/// Splits units into clusters with DBSCAN, where unit forms a cluster if summed weight
+	/// of its neighbors (counting itself) is at least `min_weight`.
+	///
+	/// Returns clusters and noise (units which don't belong to any cluster).
+	pub fn dbscan_weighted(
+		&self,
+		distance: f32,
+		min_weight: f32,
+		weight: ClusterWeight,
+	) -> (Vec<Units>, Units) {
+		let index = SpatialIndex::new(self, distance.max(1.0));
+		self.dbscan_indexed(&index, distance, min_weight, weight)
+	}
+	/// The same as [`dbscan_weighted`](Self::dbscan_weighted), but uses given spatial index
+	/// to find neighbors, instead of building new one (e.g. [`index`](super::AllUnits::index)
+	/// of all units, which is rebuilt every step anyway).
+	///
+	/// Index should contain all units of this collection.
+	pub fn dbscan_indexed(
+		&self,
+		index: &SpatialIndex,
+		distance: f32,
+		min_weight: f32,
+		weight: ClusterWeight,
+	) -> (Vec<Units>, Units) {
+		let units = self.iter().collect::<Vec<_>>();
+		let ids = units
+			.iter()
+			.enumerate()
+			.map(|(i, u)| (u.tag(), i))
+			.collect::<FxHashMap<_, _>>();
+		let weights = units.iter().map(|u| weight.of(u)).collect::<Vec<_>>();
+
+		// Neighborhoods are computed once and reused by the whole algorithm
+		let neighbors = units
+			.iter()
+			.map(|u| {
+				index
+					.tags_closer(distance, u.position())
+					.filter_map(|tag| ids.get(&tag).copied())
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		let is_core = |i: usize| neighbors[i].iter().map(|j| weights[*j]).sum::<f32>() >= min_weight;
+
+		let mut labels = vec![None; units.len()];
+		let mut visited = vec![false; units.len()];
+		let mut clusters = 0;
+		for i in 0..units.len() {
+			if visited[i] {
+				continue;
+			}
+			visited[i] = true;
+			if !is_core(i) {
+				continue;
+			}
+
+			let c = clusters;
+			clusters += 1;
+			labels[i] = Some(c);
+			let mut seeds = neighbors[i].clone();
+			while let Some(j) = seeds.pop() {
+				if labels[j].is_none() {
+					labels[j] = Some(c);
+				}
+				if !visited[j] {
+					visited[j] = true;
+					if is_core(j) {
+						seeds.extend(&neighbors[j]);
+					}
+				}
+			}
+		}
+
+		let mut result = vec![Units::new(); clusters];
+		let mut noise = Units::new();
+		for (u, label) in units.into_iter().zip(labels) {
+			match label {
+				Some(c) => result[c].push(u.clone()),
+				None => noise.push(u.clone()),
+			}
+		}
+		(result, noise)
+	}
+
+	/// Splits units into at most `k` clusters with k-means.
+	///
+	/// Initial centroids are chosen deterministically: furthest from each other units.
+	pub fn kmeans(&self, k: usize) -> Vec<Units> {
+		self.kmeans_weighted(k, ClusterWeight::Count)
+	}
+	/// Splits units into at most `k` clusters with k-means,
+	/// where centroids are weighted by given weight of units.
+	pub fn kmeans_weighted(&self, k: usize, weight: ClusterWeight) -> Vec<Units> {
+		let units = self.iter().collect::<Vec<_>>();
+		let k = k.min(units.len());
+		if k == 0 {
+			return vec![];
+		}
+		let positions = units.iter().map(|u| u.position()).collect::<Vec<_>>();
+		let weights = units.iter().map(|u| weight.of(u)).collect::<Vec<_>>();
+
+		// Farthest point initialization, starting from the heaviest unit
+		let first = (0..units.len())
+			.max_by(|a, b| cmp(&weights[*a], &weights[*b]))
+			.unwrap_or(0);
+		let mut centroids = vec![positions[first]];
+		let mut closest = positions
+			.iter()
+			.map(|p| p.distance_squared(positions[first]))
+			.collect::<Vec<_>>();
+		while centroids.len() < k {
+			let next = (0..units.len())
+				.max_by(|a, b| cmp(&closest[*a], &closest[*b]))
+				.unwrap_or(0);
+			centroids.push(positions[next]);
+			for (d, p) in closest.iter_mut().zip(&positions) {
+				*d = d.min(p.distance_squared(positions[next]));
+			}
+		}
+
+		let nearest = |p: Point2, centroids: &[Point2]| {
+			(0..centroids.len())
+				.min_by(|a, b| {
+					cmp(
+						&p.distance_squared(centroids[*a]),
+						&p.distance_squared(centroids[*b]),
+					)
+				})
+				.unwrap_or(0)
+		};
+
+		let mut labels = vec![usize::MAX; units.len()];
+		for _ in 0..KMEANS_ITERATIONS {
+			let mut changed = false;
+			for (label, p) in labels.iter_mut().zip(&positions) {
+				let c = nearest(*p, &centroids);
+				if *label != c {
+					*label = c;
+					changed = true;
+				}
+			}
+			if !changed {
+				break;
+			}
+
+			let mut sums = vec![(Point2::default(), 0.0, Point2::default(), 0); k];
+			for ((label, p), w) in labels.iter().zip(&positions).zip(&weights) {
+				let (weighted, total, plain, count) = &mut sums[*label];
+				*weighted += *p * *w;
+				*total += *w;
+				*plain += *p;
+				*count += 1;
+			}
+			for (centroid, (weighted, total, plain, count)) in centroids.iter_mut().zip(sums) {
+				if total > 0.0 {
+					*centroid = weighted / total;
+				} else if count > 0 {
+					*centroid = plain / count as f32;
+				}
+			}
+		}
+
+		let mut clusters = vec![Units::new(); k];
+		for (u, label) in units.into_iter().zip(labels) {
+			clusters[label].push(u.clone());
+		}
+		clusters.retain(|c| !c.is_empty());
+		clusters
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ids::UnitTypeId, unit::tests::unit};
+
+	/// Two lines of 3 marines far from each other and one marine far from both.
+	fn units() -> Units {
+		[
+			(0.0, 0.0),
+			(1.0, 0.0),
+			(2.0, 0.0),
+			(20.0, 20.0),
+			(20.0, 21.0),
+			(20.0, 22.0),
+			(50.0, 50.0),
+		]
+		.into_iter()
+		.enumerate()
+		.map(|(i, pos)| unit(i as u64 + 1, UnitTypeId::Marine, pos))
+		.collect()
+	}
+	fn sorted_tags(clusters: &[Units]) -> Vec<Vec<u64>> {
+		let mut tags = clusters
+			.iter()
+			.map(|c| {
+				let mut tags = c.iter().map(|u| u.tag()).collect::<Vec<_>>();
+				tags.sort_unstable();
+				tags
+			})
+			.collect::<Vec<_>>();
+		tags.sort_unstable();
+		tags
+	}
+
+	#[test]
+	fn dbscan_clusters_and_noise() {
+		let (clusters, noise) = units().dbscan(2.0, 3);
+		assert_eq!(sorted_tags(&clusters), vec![vec![1, 2, 3], vec![4, 5, 6]]);
+		assert_eq!(noise.iter().map(|u| u.tag()).collect::<Vec<_>>(), vec![7]);
+	}
+
+	#[test]
+	fn dbscan_everything_is_noise() {
+		let (clusters, noise) = units().dbscan(2.0, 4);
+		assert!(clusters.is_empty());
+		assert_eq!(noise.len(), 7);
+
+		let (clusters, noise) = Units::new().dbscan(2.0, 1);
+		assert!(clusters.is_empty());
+		assert!(noise.is_empty());
+	}
+
+	#[test]
+	fn dbscan_single_points() {
+		let (clusters, noise) = units().dbscan(0.5, 1);
+		assert_eq!(clusters.len(), 7);
+		assert!(noise.is_empty());
+	}
+
+	#[test]
+	fn dbscan_indexed_matches_dbscan() {
+		let units = units();
+		let index = SpatialIndex::new(&units, 3.0);
+		let (clusters, noise) = units.dbscan_indexed(&index, 2.0, 3.0, ClusterWeight::Count);
+		let (expected, expected_noise) = units.dbscan(2.0, 3);
+		assert_eq!(sorted_tags(&clusters), sorted_tags(&expected));
+		assert_eq!(noise.len(), expected_noise.len());
+	}
+
+	#[test]
+	fn kmeans_splits_groups() {
+		let clusters = units().kmeans(3);
+		assert_eq!(
+			sorted_tags(&clusters),
+			vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]
+		);
+
+		let clusters = units().kmeans(2);
+		assert_eq!(clusters.len(), 2);
+		assert_eq!(clusters.iter().map(|c| c.len()).sum::<usize>(), 7);
+	}
+
+	#[test]
+	fn kmeans_k_bounds() {
+		assert!(units().kmeans(0).is_empty());
+		assert!(Units::new().kmeans(3).is_empty());
+		assert_eq!(units().kmeans(10).len(), 7);
+	}
+}
@@ -1,7 +1,7 @@
 //! Data structures for storing units, fast filtering and finding ones that needed.
 #![warn(missing_docs)]
 
-use crate::{geometry::Point2, ids::UnitTypeId, unit::Unit};
+use crate::{bot::Rs, consts::FRAMES_PER_SECOND, geometry::Point2, ids::UnitTypeId, unit::Unit};
 use indexmap::{
 	map::{Iter, IterMut, Keys, Values, ValuesMut},
 	IndexMap, IndexSet,
@@ -14,9 +14,15 @@ use std::{
 	ops::{Index, IndexMut},
 };
 
+pub mod clustering;
 pub mod iter;
 #[cfg(feature = "rayon")]
 pub mod rayon;
+pub mod spatial;
+pub mod targeting;
+pub mod threats;
+
+use spatial::SpatialIndex;
 
 type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
 
@@ -46,8 +52,48 @@ pub struct AllUnits {
 	pub watchtowers: Units,
 	/// Inhubitor zones slow down movement speed of nearby units.
 	pub inhibitor_zones: Units,
+	index: Option<Rs<SpatialIndex>>,
 }
 impl AllUnits {
+	/// Enables [`SpatialIndex`] over all units with given size of grid cells.
+	/// Index is built immediately and then rebuilt every step.
+	///
+	/// Distance filters of collections in this struct and their subsets
+	/// (e.g. [`closer`](Units::closer) or [`closest`](Units::closest)) use the index automatically.
+	pub fn enable_index(&mut self, cell_size: f32) {
+		self.set_index(Some(Rs::new(SpatialIndex::new(&self.all, cell_size))));
+	}
+	/// Disables spatial index.
+	pub fn disable_index(&mut self) {
+		self.set_index(None);
+	}
+	/// Returns spatial index over all units if it's enabled.
+	pub fn index(&self) -> Option<&SpatialIndex> {
+		self.index.as_deref()
+	}
+	pub(crate) fn update_index(&mut self) {
+		if let Some(index) = &self.index {
+			// Collections from previous steps keep their index
+			let index = SpatialIndex::new(&self.all, index.cell_size());
+			self.set_index(Some(Rs::new(index)));
+		}
+	}
+	fn set_index(&mut self, index: Option<Rs<SpatialIndex>>) {
+		for units in [
+			&mut self.all,
+			&mut self.mineral_fields,
+			&mut self.vespene_geysers,
+			&mut self.resources,
+			&mut self.destructables,
+			&mut self.watchtowers,
+			&mut self.inhibitor_zones,
+		] {
+			units.1 = index.clone();
+		}
+		self.my.set_index(&index);
+		self.enemy.set_index(&index);
+		self.index = index;
+	}
 	pub(crate) fn clear(&mut self) {
 		self.all.clear();
 		self.my.clear();
@@ -95,29 +141,47 @@ impl PlayerUnits {
 		self.placeholders.clear();
 		self.tumors.clear();
 	}
+	fn set_index(&mut self, index: &Option<Rs<SpatialIndex>>) {
+		for units in [
+			&mut self.all,
+			&mut self.units,
+			&mut self.structures,
+			&mut self.townhalls,
+			&mut self.workers,
+			&mut self.gas_buildings,
+			&mut self.larvas,
+			&mut self.placeholders,
+			&mut self.tumors,
+		] {
+			units.1 = index.clone();
+		}
+	}
 }
 
 /// Convinient [`Unit`]s collection.
 ///
+/// Collections taken from [`AllUnits`] (and filtered from them) hold its [`SpatialIndex`]
+/// if it's enabled, the index is dropped when new units are added to collection.
+///
 // [`Unit`]: crate::unit::Unit
 #[derive(Default, Clone)]
-pub struct Units(FxIndexMap<u64, Unit>);
+pub struct Units(FxIndexMap<u64, Unit>, Option<Rs<SpatialIndex>>);
 impl Units {
 	// HashMap methods
 
 	/// Constrructs new empty units collection.
 	#[inline]
 	pub fn new() -> Self {
-		Self(Default::default())
+		Self(Default::default(), None)
 	}
 
 	/// Constructs new units collection with given capacity.
 	#[inline]
 	pub fn with_capacity(n: usize) -> Self {
-		Self(IndexMap::with_capacity_and_hasher(
-			n,
-			BuildHasherDefault::<FxHasher>::default(),
-		))
+		Self(
+			IndexMap::with_capacity_and_hasher(n, BuildHasherDefault::<FxHasher>::default()),
+			None,
+		)
 	}
 
 	/// Returns current capacity of the collection.
@@ -150,6 +214,7 @@ impl Units {
 	/// replaces it and returns previous unit.
 	#[inline]
 	pub fn push(&mut self, u: Unit) -> Option<Unit> {
+		self.1 = None;
 		self.0.insert(u.tag(), u)
 	}
 
@@ -214,6 +279,7 @@ impl Units {
 	/// Removes all units from the collection, while preserving its capacity.
 	#[inline]
 	pub fn clear(&mut self) {
+		self.1 = None;
 		self.0.clear()
 	}
 
@@ -297,6 +363,35 @@ impl Units {
 	pub fn positions(&self) -> Vec<Point2> {
 		self.iter().map(|u| u.position()).collect()
 	}
+	/// Returns tags of units with their predicted positions after given number of game loops.
+	///
+	/// Units following other units of this collection move towards their current positions,
+	/// others are predicted with [`predict_position`](Unit::predict_position).
+	pub fn predict_positions(&self, frames: f32) -> Vec<(u64, Point2)> {
+		self.iter()
+			.map(|u| {
+				let pos = match u.target_tag().and_then(|tag| self.get(tag)) {
+					Some(target) => {
+						let distance = u.real_speed() / FRAMES_PER_SECOND * frames;
+						u.position()
+							.towards(target.position(), distance.min(u.distance(target)))
+					}
+					None => u.predict_position(frames),
+				};
+				(u.tag(), pos)
+			})
+			.collect()
+	}
+	/// Returns predicted central position of units after given number of game loops
+	/// or `None` if collection is empty.
+	pub fn predict_center(&self, frames: f32) -> Option<Point2> {
+		if self.is_empty() {
+			None
+		} else {
+			let positions = self.predict_positions(frames);
+			Some(positions.iter().map(|(_, pos)| *pos).sum::<Point2>() / positions.len() as f32)
+		}
+	}
 	/// Leaves only non-flying units and makes new collection of them.
 	///
 	/// Warning: This method will clone units in order to create a new collection
@@ -399,6 +494,9 @@ impl Units {
 	///
 	/// [`in_range_of`]: UnitsIterator::in_range_of
 	pub fn in_range_of(&self, unit: &Unit, gap: f32) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.in_range_of(self, unit, gap));
+		}
 		self.filter(|u| unit.in_range(u, gap))
 	}
 	/// Leaves only units that are close enough to attack given unit and makes new collection of them.
@@ -409,6 +507,9 @@ impl Units {
 	///
 	/// [`in_range`]: UnitsIterator::in_range
 	pub fn in_range(&self, unit: &Unit, gap: f32) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.in_range(self, unit, gap));
+		}
 		self.filter(|u| u.in_range(unit, gap))
 	}
 	/// Leaves only units in attack range of given unit and makes new collection of them.
@@ -421,6 +522,9 @@ impl Units {
 	/// [`in_range_of`]: Self::in_range_of
 	/// [`in_real_range_of`]: UnitsIterator::in_real_range_of
 	pub fn in_real_range_of(&self, unit: &Unit, gap: f32) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.in_real_range_of(self, unit, gap));
+		}
 		self.filter(|u| unit.in_real_range(u, gap))
 	}
 	/// Leaves only units that are close enough to attack given unit and makes new collection of them.
@@ -433,6 +537,9 @@ impl Units {
 	/// [`in_range`]: Self::in_range
 	/// [`in_real_range`]: UnitsIterator::in_real_range
 	pub fn in_real_range(&self, unit: &Unit, gap: f32) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.in_real_range(self, unit, gap));
+		}
 		self.filter(|u| u.in_real_range(unit, gap))
 	}
 	/// Leaves only units visible on current step and makes new collection of them.
@@ -470,13 +577,13 @@ impl Units {
 impl FromIterator<Unit> for Units {
 	#[inline]
 	fn from_iter<I: IntoIterator<Item = Unit>>(iter: I) -> Self {
-		Self(iter.into_iter().map(|u| (u.tag(), u)).collect())
+		Self(iter.into_iter().map(|u| (u.tag(), u)).collect(), None)
 	}
 }
 impl FromIterator<(u64, Unit)> for Units {
 	#[inline]
 	fn from_iter<I: IntoIterator<Item = (u64, Unit)>>(iter: I) -> Self {
-		Self(iter.into_iter().collect())
+		Self(iter.into_iter().collect(), None)
 	}
 }
 
@@ -511,12 +618,14 @@ impl<'a> IntoIterator for &'a mut Units {
 impl Extend<Unit> for Units {
 	#[inline]
 	fn extend<T: IntoIterator<Item = Unit>>(&mut self, iter: T) {
+		self.1 = None;
 		self.0.extend(iter.into_iter().map(|u| (u.tag(), u)));
 	}
 }
 impl Extend<(u64, Unit)> for Units {
 	#[inline]
 	fn extend<T: IntoIterator<Item = (u64, Unit)>>(&mut self, iter: T) {
+		self.1 = None;
 		self.0.extend(iter);
 	}
 }
@@ -596,7 +705,10 @@ impl Units {
 	where
 		F: Fn(&&Unit) -> bool,
 	{
-		Self(self.iter().filter(f).map(|u| (u.tag(), u.clone())).collect())
+		Self(
+			self.iter().filter(f).map(|u| (u.tag(), u.clone())).collect(),
+			self.1.clone(),
+		)
 	}
 	/// Leaves only units of given types and makes a new collection of them.
 	///
@@ -628,6 +740,9 @@ impl Units {
 	///
 	/// [`closer`]: UnitsIterator::closer
 	pub fn closer<P: Into<Point2> + Copy>(&self, distance: f32, target: P) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.closer(self, distance, target));
+		}
 		self.filter(|u| u.is_closer(distance, target))
 	}
 	/// Leaves only units further than given distance to target and makes new collection of them.
@@ -643,6 +758,9 @@ impl Units {
 
 	/// Returns closest from the collection unit to given target.
 	pub fn closest<P: Into<Point2> + Copy>(&self, target: P) -> Option<&Unit> {
+		if let Some(index) = self.indexed() {
+			return index.closest(self, target);
+		}
 		self.min(|u| u.distance_squared(target))
 	}
 	/// Returns furthest from the collection unit to given target.
@@ -652,8 +770,7 @@ impl Units {
 
 	/// Returns distance from closest unit in the collection to given target.
 	pub fn closest_distance<P: Into<Point2> + Copy>(&self, target: P) -> Option<f32> {
-		self.min_value(|u| u.distance_squared(target))
-			.map(|dist| dist.sqrt())
+		self.closest_distance_squared(target).map(|dist| dist.sqrt())
 	}
 	/// Returns distance from furthest unit in the collection to given target.
 	pub fn furthest_distance<P: Into<Point2> + Copy>(&self, target: P) -> Option<f32> {
@@ -663,6 +780,9 @@ impl Units {
 
 	/// Returns squared distance from closest unit in the collection to given target.
 	pub fn closest_distance_squared<P: Into<Point2> + Copy>(&self, target: P) -> Option<f32> {
+		if self.indexed().is_some() {
+			return self.closest(target).map(|u| u.distance_squared(target));
+		}
 		self.min_value(|u| u.distance_squared(target))
 	}
 	/// Returns squared distance from furthest unit in the collection to given target.
@@ -57,7 +57,10 @@ impl Units {
 	where
 		F: Fn(&&Unit) -> bool + Sync + Send,
 	{
-		Self(self.par_iter().filter(f).map(|u| (u.tag(), u.clone())).collect())
+		Self(
+			self.par_iter().filter(f).map(|u| (u.tag(), u.clone())).collect(),
+			self.1.clone(),
+		)
 	}
 
 	/// Leaves only units of given types and makes a new collection of them.
@@ -90,6 +93,9 @@ impl Units {
 	///
 	/// [`closer`]: crate::distance::DistanceIterator::closer
 	pub fn closer<P: Into<Point2> + Copy + Sync>(&self, distance: f32, target: P) -> Self {
+		if let Some(index) = self.indexed() {
+			return self.with_index(index.closer(self, distance, target));
+		}
 		self.filter(|u| u.is_closer(distance, target))
 	}
 	/// Leaves only units further than given distance to target and makes new collection of them.
@@ -105,6 +111,9 @@ impl Units {
 
 	/// Returns closest from the collection unit to given target.
 	pub fn closest<P: Into<Point2> + Copy + Sync>(&self, target: P) -> Option<&Unit> {
+		if let Some(index) = self.indexed() {
+			return index.closest(self, target);
+		}
 		self.min(|u| u.distance_squared(target))
 	}
 	/// Returns furthest from the collection unit to given target.
@@ -114,8 +123,7 @@ impl Units {
 
 	/// Returns distance from closest unit in the collection to given target.
 	pub fn closest_distance<P: Into<Point2> + Copy + Sync>(&self, target: P) -> Option<f32> {
-		self.min_value(|u| u.distance_squared(target))
-			.map(|dist| dist.sqrt())
+		self.closest_distance_squared(target).map(|dist| dist.sqrt())
 	}
 	/// Returns distance from furthest unit in the collection to given target.
 	pub fn furthest_distance<P: Into<Point2> + Copy + Sync>(&self, target: P) -> Option<f32> {
@@ -125,6 +133,9 @@ impl Units {
 
 	/// Returns squared distance from closest unit in the collection to given target.
 	pub fn closest_distance_squared<P: Into<Point2> + Copy + Sync>(&self, target: P) -> Option<f32> {
+		if self.indexed().is_some() {
+			return self.closest(target).map(|u| u.distance_squared(target));
+		}
 		self.min_value(|u| u.distance_squared(target))
 	}
 	/// Returns squared distance from furthest unit in the collection to given target.
@@ -227,12 +238,14 @@ impl<'a> IntoParallelIterator for &'a mut Units {
 impl ParallelExtend<Unit> for Units {
 	#[inline]
 	fn par_extend<T: IntoParallelIterator<Item = Unit>>(&mut self, par_iter: T) {
+		self.1 = None;
 		self.0.par_extend(par_iter.into_par_iter().map(|u| (u.tag(), u)));
 	}
 }
 impl ParallelExtend<(u64, Unit)> for Units {
 	#[inline]
 	fn par_extend<T: IntoParallelIterator<Item = (u64, Unit)>>(&mut self, par_iter: T) {
+		self.1 = None;
 		self.0.par_extend(par_iter);
 	}
 }
@@ -240,13 +253,13 @@ impl ParallelExtend<(u64, Unit)> for Units {
 impl FromParallelIterator<Unit> for Units {
 	#[inline]
 	fn from_par_iter<I: IntoParallelIterator<Item = Unit>>(par_iter: I) -> Self {
-		Self(par_iter.into_par_iter().map(|u| (u.tag(), u)).collect())
+		Self(par_iter.into_par_iter().map(|u| (u.tag(), u)).collect(), None)
 	}
 }
 impl FromParallelIterator<(u64, Unit)> for Units {
 	#[inline]
 	fn from_par_iter<I: IntoParallelIterator<Item = (u64, Unit)>>(par_iter: I) -> Self {
-		Self(par_iter.into_par_iter().collect())
+		Self(par_iter.into_par_iter().collect(), None)
 	}
 }
 
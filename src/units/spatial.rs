@@ -0,0 +1,359 @@
+//! Spatial index over units for fast distance queries.
+//!
+//! Index is built with grid hashing: every unit is put to the cell of fixed size it's in,
+//! so queries have to check only units in nearby cells instead of scanning whole collection.
+//!
+//! Index of all units can be enabled with [`enable_index`](super::AllUnits::enable_index),
+//! then it's rebuilt each step and can be accessed with [`index`](super::AllUnits::index).
+//! Queries take any collection of units (e.g. `bot.units.enemy.units`) and return only units
+//! from this collection.
+//!
+//! Collections of [`AllUnits`](super::AllUnits) hold the index, so their distance filters
+//! (e.g. [`closer`](Units::closer), [`closest`](Units::closest), [`in_range_of`](Units::in_range_of))
+//! use it automatically when collection is large enough.
+#![warn(missing_docs)]
+
+use super::Units;
+use crate::{
+	distance::{cmp, Distance},
+	geometry::Point2,
+	unit::Unit,
+};
+use rustc_hash::FxHashMap;
+
+type Cell = (i32, i32);
+
+/// Collections smaller than this are filtered without index, since it's faster.
+const MIN_INDEXED_LEN: usize = 32;
+
+/// Grid based spatial index over units.
+#[derive(Default, Clone)]
+pub struct SpatialIndex {
+	cell_size: f32,
+	cells: FxHashMap<Cell, Vec<(u64, Point2)>>,
+	min: Cell,
+	max: Cell,
+	max_radius: f32,
+	max_range: f32,
+	len: usize,
+}
+
+impl SpatialIndex {
+	/// Constructs index over given units with given size of grid cells.
+	///
+	/// # Panics
+	/// If `cell_size` isn't positive.
+	pub fn new(units: &Units, cell_size: f32) -> Self {
+		assert!(cell_size > 0.0, "Cell size of spatial index must be positive");
+		let mut index = Self {
+			cell_size,
+			..Default::default()
+		};
+		index.rebuild(units);
+		index
+	}
+
+	/// Rebuilds index over given units, keeping size of the cells.
+	pub fn rebuild(&mut self, units: &Units) {
+		self.cells.clear();
+		self.min = (i32::MAX, i32::MAX);
+		self.max = (i32::MIN, i32::MIN);
+		self.max_radius = 0.0;
+		self.max_range = 0.0;
+		self.len = units.len();
+
+		for u in units {
+			let pos = u.position();
+			let cell = self.cell(pos);
+			self.cells.entry(cell).or_default().push((u.tag(), pos));
+
+			self.min = (self.min.0.min(cell.0), self.min.1.min(cell.1));
+			self.max = (self.max.0.max(cell.0), self.max.1.max(cell.1));
+			self.max_radius = self.max_radius.max(u.radius());
+			self.max_range = self.max_range.max(u.real_ground_range()).max(u.real_air_range());
+		}
+	}
+
+	/// Returns size of the grid cells.
+	pub fn cell_size(&self) -> f32 {
+		self.cell_size
+	}
+	/// Returns number of indexed units.
+	pub fn len(&self) -> usize {
+		self.len
+	}
+	/// Checks if index is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	fn cell(&self, pos: Point2) -> Cell {
+		(
+			(pos.x / self.cell_size).floor() as i32,
+			(pos.y / self.cell_size).floor() as i32,
+		)
+	}
+	fn candidates(&self, target: Point2, distance: f32) -> impl Iterator<Item = &(u64, Point2)> {
+		let (x0, y0) = self.cell(Point2::new(target.x - distance, target.y - distance));
+		let (x1, y1) = self.cell(Point2::new(target.x + distance, target.y + distance));
+		let (x0, y0) = (x0.max(self.min.0), y0.max(self.min.1));
+		let (x1, y1) = (x1.min(self.max.0), y1.min(self.max.1));
+
+		(x0..=x1)
+			.flat_map(move |x| (y0..=y1).filter_map(move |y| self.cells.get(&(x, y))))
+			.flatten()
+	}
+	fn ring(&self, center: Cell, r: i32) -> impl Iterator<Item = &(u64, Point2)> {
+		let (cx, cy) = center;
+		(cx - r..=cx + r)
+			.flat_map(move |x| {
+				let step = if x == cx - r || x == cx + r {
+					1
+				} else {
+					(2 * r).max(1) as usize
+				};
+				(cy - r..=cy + r)
+					.step_by(step)
+					.filter_map(move |y| self.cells.get(&(x, y)))
+			})
+			.flatten()
+	}
+	fn nearest<F>(&self, k: usize, target: Point2, f: F) -> Vec<(u64, f32)>
+	where
+		F: Fn(u64) -> bool,
+	{
+		let mut found = Vec::<(u64, f32)>::new();
+		if k == 0 || self.cells.is_empty() {
+			return found;
+		}
+
+		let center = self.cell(target);
+		let max_ring = (center.0 - self.min.0)
+			.max(self.max.0 - center.0)
+			.max(center.1 - self.min.1)
+			.max(self.max.1 - center.1)
+			.max(0);
+
+		for r in 0..=max_ring {
+			found.extend(
+				self.ring(center, r)
+					.filter(|(tag, _)| f(*tag))
+					.map(|(tag, pos)| (*tag, pos.distance_squared(target))),
+			);
+			if found.len() >= k {
+				found.sort_unstable_by(|(_, d1), (_, d2)| cmp(d1, d2));
+				found.truncate(k);
+
+				// Units in next rings are at least this far from target
+				let bound = r as f32 * self.cell_size;
+				if found[k - 1].1 <= bound * bound {
+					return found;
+				}
+			}
+		}
+
+		found.sort_unstable_by(|(_, d1), (_, d2)| cmp(d1, d2));
+		found.truncate(k);
+		found
+	}
+
+	/// Returns tags of indexed units closer than given distance to target.
+	pub fn tags_closer<P: Into<Point2>>(&self, distance: f32, target: P) -> impl Iterator<Item = u64> + '_ {
+		let target = target.into();
+		self.candidates(target, distance)
+			.filter(move |(_, pos)| pos.is_closer(distance, target))
+			.map(|(tag, _)| *tag)
+	}
+	/// Returns tags of `k` indexed units closest to target, sorted by distance in ascending order.
+	pub fn tags_k_closest<P: Into<Point2>>(&self, k: usize, target: P) -> Vec<u64> {
+		self.nearest(k, target.into(), |_| true)
+			.into_iter()
+			.map(|(tag, _)| tag)
+			.collect()
+	}
+
+	/// Leaves only units closer than given distance to target and makes new collection of them.
+	///
+	/// Fast alternative to [`Units::closer`].
+	pub fn closer<P: Into<Point2>>(&self, units: &Units, distance: f32, target: P) -> Units {
+		self.tags_closer(distance, target)
+			.filter_map(|tag| units.get(tag).cloned())
+			.collect()
+	}
+	/// Returns closest from the collection unit to given target.
+	///
+	/// Fast alternative to [`Units::closest`].
+	pub fn closest<'a, P: Into<Point2>>(&self, units: &'a Units, target: P) -> Option<&'a Unit> {
+		self.nearest(1, target.into(), |tag| units.contains_tag(tag))
+			.first()
+			.and_then(|(tag, _)| units.get(*tag))
+	}
+	/// Returns `k` units from the collection closest to target, sorted by distance in ascending order.
+	pub fn k_closest<P: Into<Point2>>(&self, units: &Units, k: usize, target: P) -> Units {
+		self.nearest(k, target.into(), |tag| units.contains_tag(tag))
+			.into_iter()
+			.filter_map(|(tag, _)| units.get(tag).cloned())
+			.collect()
+	}
+	/// Leaves only units in attack range of given unit and makes new collection of them.
+	///
+	/// Fast alternative to [`Units::in_range_of`].
+	pub fn in_range_of(&self, units: &Units, unit: &Unit, gap: f32) -> Units {
+		let distance = unit.ground_range().max(unit.air_range()) + unit.radius() + self.max_radius + gap;
+		self.tags_closer(distance, unit)
+			.filter_map(|tag| units.get(tag))
+			.filter(|u| unit.in_range(u, gap))
+			.cloned()
+			.collect()
+	}
+	/// Leaves only units that are close enough to attack given unit and makes new collection of them.
+	///
+	/// Fast alternative to [`Units::in_range`].
+	pub fn in_range(&self, units: &Units, unit: &Unit, gap: f32) -> Units {
+		let distance = self.max_range + unit.radius() + self.max_radius + gap;
+		self.tags_closer(distance, unit)
+			.filter_map(|tag| units.get(tag))
+			.filter(|u| u.in_range(unit, gap))
+			.cloned()
+			.collect()
+	}
+	/// Leaves only units in attack range of given unit and makes new collection of them.
+	/// Unlike [`in_range_of`](Self::in_range_of) this takes range upgrades into account.
+	///
+	/// Fast alternative to [`Units::in_real_range_of`].
+	pub fn in_real_range_of(&self, units: &Units, unit: &Unit, gap: f32) -> Units {
+		let distance =
+			unit.real_ground_range().max(unit.real_air_range()) + unit.radius() + self.max_radius + gap;
+		self.tags_closer(distance, unit)
+			.filter_map(|tag| units.get(tag))
+			.filter(|u| unit.in_real_range(u, gap))
+			.cloned()
+			.collect()
+	}
+	/// Leaves only units that are close enough to attack given unit and makes new collection of them.
+	/// Unlike [`in_range`](Self::in_range) this takes range upgrades into account.
+	///
+	/// Fast alternative to [`Units::in_real_range`].
+	pub fn in_real_range(&self, units: &Units, unit: &Unit, gap: f32) -> Units {
+		let distance = self.max_range + unit.radius() + self.max_radius + gap;
+		self.tags_closer(distance, unit)
+			.filter_map(|tag| units.get(tag))
+			.filter(|u| u.in_real_range(unit, gap))
+			.cloned()
+			.collect()
+	}
+}
+
+impl Units {
+	pub(super) fn indexed(&self) -> Option<&SpatialIndex> {
+		self.1.as_deref().filter(|_| self.len() >= MIN_INDEXED_LEN)
+	}
+	pub(super) fn with_index(&self, mut units: Units) -> Units {
+		units.1 = self.1.clone();
+		units
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{bot::Rs, ids::UnitTypeId, unit::tests::unit};
+
+	fn units() -> Units {
+		(0..100)
+			.map(|i| {
+				let pos = ((i % 10) as f32 * 3.0, (i / 10) as f32 * 3.0);
+				unit(i, UnitTypeId::Marine, pos)
+			})
+			.collect()
+	}
+	fn sorted_distances<I: IntoIterator<Item = u64>>(units: &Units, tags: I, target: Point2) -> Vec<f32> {
+		let mut distances = tags
+			.into_iter()
+			.map(|tag| units[tag].distance_squared(target))
+			.collect::<Vec<_>>();
+		distances.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+		distances
+	}
+
+	#[test]
+	#[should_panic]
+	fn cell_size_must_be_positive() {
+		SpatialIndex::new(&units(), 0.0);
+	}
+
+	#[test]
+	fn tags_closer_matches_linear_search() {
+		let units = units();
+		let index = SpatialIndex::new(&units, 4.0);
+		for (target, distance) in [
+			(Point2::new(0.0, 0.0), 5.0),
+			(Point2::new(13.5, 13.5), 7.0),
+			(Point2::new(-10.0, 40.0), 20.0),
+			(Point2::new(100.0, 100.0), 1.0),
+		] {
+			let mut tags = index.tags_closer(distance, target).collect::<Vec<_>>();
+			tags.sort_unstable();
+			let expected = units
+				.iter()
+				.filter(|u| u.is_closer(distance, target))
+				.map(|u| u.tag())
+				.collect::<Vec<_>>();
+			assert_eq!(tags, expected);
+		}
+	}
+
+	#[test]
+	fn nearest_matches_linear_search() {
+		let units = units();
+		let index = SpatialIndex::new(&units, 4.0);
+		for target in [
+			Point2::new(14.0, 14.0),
+			Point2::new(-20.0, 5.0),
+			Point2::new(50.0, 50.0),
+		] {
+			for k in [1, 5, 30, 150] {
+				let found = index.tags_k_closest(k, target);
+				assert_eq!(found.len(), k.min(units.len()));
+				let expected = sorted_distances(&units, units.tags().copied(), target);
+				assert_eq!(
+					sorted_distances(&units, found, target),
+					&expected[..k.min(units.len())]
+				);
+			}
+		}
+		assert!(index.tags_k_closest(0, Point2::new(0.0, 0.0)).is_empty());
+		assert!(SpatialIndex::new(&Units::new(), 4.0)
+			.tags_k_closest(3, Point2::new(0.0, 0.0))
+			.is_empty());
+	}
+
+	#[test]
+	fn units_filters_use_index() {
+		let mut units = units();
+		units.1 = Some(Rs::new(SpatialIndex::new(&units, 4.0)));
+		assert!(units.indexed().is_some());
+
+		let target = Point2::new(13.0, 7.0);
+		let closest = units.closest(target).unwrap();
+		assert_eq!(
+			closest.distance_squared(target),
+			units.min_value(|u| u.distance_squared(target)).unwrap()
+		);
+
+		let closer = units.closer(5.0, target);
+		assert!(closer.1.is_some());
+		let mut tags = closer.tags().copied().collect::<Vec<_>>();
+		tags.sort_unstable();
+		let expected = units
+			.filter(|u| u.is_closer(5.0, target))
+			.tags()
+			.copied()
+			.collect::<Vec<_>>();
+		assert_eq!(tags, expected);
+		assert!(closer.indexed().is_none());
+
+		units.push(unit(1000, UnitTypeId::Marine, (0.0, 0.0)));
+		assert!(units.indexed().is_none());
+	}
+}
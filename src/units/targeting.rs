@@ -0,0 +1,253 @@
+//! Target selection and focus fire.
+//!
+//! Targets are valued by how fast they can be killed and how dangerous they are:
+//! `value = dps_vs_target * (1 + target_dps) / remaining_hits`,
+//! so damaged and dangerous units are killed first, and splash damage dealers prefer clumped targets.
+//!
+//! Attackers are assigned one by one, and every assignment reduces expected remaining hits
+//! of the target, which makes attackers focus fire, but switch to other targets to avoid overkill:
+//! ```
+//! let idle = self.units.my.units.focus_fire(&self.units.enemy.all, 0.0);
+//! for u in &idle {
+//!     u.attack(Target::Pos(self.enemy_start), false);
+//! }
+//! ```
+#![warn(missing_docs)]
+
+use super::Units;
+use crate::{
+	action::Target,
+	distance::{cmp, Distance},
+	ids::UnitTypeId,
+	unit::Unit,
+};
+use rustc_hash::FxHashMap;
+
+/// Used as remaining hits of targets with unknown health (i.e. snapshots).
+const UNKNOWN_HITS: f32 = 100.0;
+
+/// Returns approximate radius of splash damage dealt by given unit.
+pub fn splash_radius(u: &Unit) -> Option<f32> {
+	match u.type_id() {
+		UnitTypeId::SiegeTankSieged | UnitTypeId::Colossus | UnitTypeId::Archon => Some(1.25),
+		UnitTypeId::Hellion | UnitTypeId::HellionTank | UnitTypeId::LurkerMPBurrowed => Some(1.0),
+		UnitTypeId::Thor | UnitTypeId::Ultralisk => Some(1.5),
+		UnitTypeId::WidowMineBurrowed | UnitTypeId::LiberatorAG | UnitTypeId::Liberator => Some(1.5),
+		UnitTypeId::Baneling => Some(2.2),
+		_ => None,
+	}
+}
+
+/// Returns value of attacking given target by given attacker.
+///
+/// `remaining_hits` is expected health and shield of the target after damage already assigned to it.
+/// `targets` are used to count units hit by splash damage.
+pub fn target_value(attacker: &Unit, target: &Unit, remaining_hits: f32, targets: &Units) -> f32 {
+	let weapon = attacker.real_weapon_vs(target);
+	if weapon.damage == 0 {
+		return 0.0;
+	}
+	let dps = weapon.dps();
+	let threat = 1.0 + target.ground_dps().max(target.air_dps());
+	let mut value = dps * threat / remaining_hits.max(1.0);
+
+	if let Some(radius) = splash_radius(attacker) {
+		let hit = targets
+			.iter()
+			.filter(|t| t.tag() != target.tag() && t.is_flying() == target.is_flying())
+			.filter(|t| t.is_closer(radius + t.radius(), target))
+			.count();
+		value *= 1.0 + hit as f32;
+	}
+	value
+}
+
+fn hits(u: &Unit) -> f32 {
+	u.hits().map_or(UNKNOWN_HITS, |hits| hits as f32)
+}
+
+impl Units {
+	/// Returns the most valuable target among these units, which is in range of given attacker.
+	///
+	/// See [module-level documentation](crate::units::targeting) for how targets are valued.
+	pub fn best_target(&self, attacker: &Unit, gap: f32) -> Option<&Unit> {
+		self.iter()
+			.filter(|t| t.can_be_attacked() && attacker.in_real_range(t, gap))
+			.map(|t| (t, target_value(attacker, t, hits(t), self)))
+			.filter(|(_, value)| *value > 0.0)
+			.max_by(|(_, v1), (_, v2)| cmp(v1, v2))
+			.map(|(t, _)| t)
+	}
+	/// Assigns these units as attackers to given targets, avoiding overkill.
+	///
+	/// Attackers with fewer targets in range choose first. Every attacker chooses the most valuable
+	/// target, which won't die from damage already assigned to it. If all targets in range are
+	/// expected to die, the most valuable of them is chosen anyway.
+	///
+	/// Returns map of attacker tags to target tags. Attackers without targets in range are skipped.
+	pub fn assign_targets(&self, targets: &Units, gap: f32) -> FxHashMap<u64, u64> {
+		let mut attackers = self
+			.iter()
+			.map(|a| {
+				let in_range = targets
+					.iter()
+					.filter(|t| t.can_be_attacked() && a.in_real_range(t, gap))
+					.collect::<Vec<_>>();
+				(a, in_range)
+			})
+			.filter(|(_, in_range)| !in_range.is_empty())
+			.collect::<Vec<_>>();
+		attackers.sort_by_key(|(_, in_range)| in_range.len());
+
+		let mut remaining = FxHashMap::<u64, f32>::default();
+		let mut assigned = FxHashMap::default();
+		for (attacker, in_range) in attackers {
+			let mut best = None;
+			let mut best_overkill = None;
+			for t in in_range {
+				let left = *remaining.entry(t.tag()).or_insert_with(|| hits(t));
+				let value = target_value(attacker, t, left, targets);
+				if value <= 0.0 {
+					continue;
+				}
+				let slot = if left > 0.0 { &mut best } else { &mut best_overkill };
+				if slot.map_or(true, |(_, v)| value > v) {
+					*slot = Some((t, value));
+				}
+			}
+
+			if let Some((t, _)) = best.or(best_overkill) {
+				let damage = attacker.real_weapon_vs(t).damage as f32;
+				if let Some(left) = remaining.get_mut(&t.tag()) {
+					*left -= damage;
+				}
+				assigned.insert(attacker.tag(), t.tag());
+			}
+		}
+		assigned
+	}
+	/// Orders these units to attack targets chosen by [`assign_targets`](Self::assign_targets).
+	///
+	/// Returns attackers, which didn't get any target.
+	pub fn focus_fire(&self, targets: &Units, gap: f32) -> Units {
+		let assigned = self.assign_targets(targets, gap);
+		let mut idle = Units::new();
+		for u in self {
+			match assigned.get(&u.tag()) {
+				Some(target) => u.attack(Target::Tag(*target), false),
+				None => {
+					idle.push(u.clone());
+				}
+			}
+		}
+		idle
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::Rs,
+		unit::tests::{combat_data, proto, with_data},
+		unit::DataForUnit,
+	};
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+
+	fn enemy(data: &Rs<DataForUnit>, tag: u64, type_id: UnitTypeId, pos: (f32, f32), health: f32) -> Unit {
+		let mut u = proto(tag, type_id, pos);
+		u.set_alliance(ProtoAlliance::Enemy);
+		u.set_health(health);
+		with_data(data, &u)
+	}
+
+	#[test]
+	fn splash_radius_of_units() {
+		let data = combat_data();
+		let baneling = with_data(&data, &proto(1, UnitTypeId::Baneling, (0.0, 0.0)));
+		let marine = with_data(&data, &proto(2, UnitTypeId::Marine, (0.0, 0.0)));
+		assert_eq!(splash_radius(&baneling), Some(2.2));
+		assert_eq!(splash_radius(&marine), None);
+	}
+
+	#[test]
+	fn damaged_and_dangerous_targets_first() {
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (0.0, 0.0)));
+		let zergling = enemy(&data, 2, UnitTypeId::Zergling, (3.0, 0.0), 100.0);
+		let drone = enemy(&data, 3, UnitTypeId::Drone, (3.0, 0.0), 100.0);
+		let targets = Units::new();
+
+		// Marine dps is 10 and zergling dps is 10
+		assert!((target_value(&marine, &zergling, 100.0, &targets) - 1.1).abs() < 1e-4);
+		assert!((target_value(&marine, &zergling, 10.0, &targets) - 11.0).abs() < 1e-4);
+		assert!((target_value(&marine, &drone, 100.0, &targets) - 0.1).abs() < 1e-4);
+		assert_eq!(target_value(&drone, &marine, 100.0, &targets), 0.0);
+	}
+
+	#[test]
+	fn splash_prefers_clumped_targets() {
+		let data = combat_data();
+		let tank = with_data(&data, &proto(1, UnitTypeId::SiegeTankSieged, (0.0, 0.0)));
+		let targets: Units = [
+			enemy(&data, 2, UnitTypeId::Zergling, (10.0, 0.0), 100.0),
+			enemy(&data, 3, UnitTypeId::Zergling, (10.5, 0.0), 100.0),
+			enemy(&data, 4, UnitTypeId::Zergling, (10.0, 0.5), 100.0),
+			enemy(&data, 5, UnitTypeId::Zergling, (10.0, 8.0), 100.0),
+		]
+		.into_iter()
+		.collect();
+
+		let clumped = target_value(&tank, targets.get(2).unwrap(), 100.0, &targets);
+		let alone = target_value(&tank, targets.get(5).unwrap(), 100.0, &targets);
+		assert!((clumped - alone * 3.0).abs() < 1e-4);
+	}
+
+	#[test]
+	fn best_target_in_range() {
+		let data = combat_data();
+		let marine = with_data(&data, &proto(1, UnitTypeId::Marine, (0.0, 0.0)));
+		let targets: Units = [
+			enemy(&data, 2, UnitTypeId::Zergling, (3.0, 0.0), 35.0),
+			enemy(&data, 3, UnitTypeId::Zergling, (4.0, 0.0), 10.0),
+			enemy(&data, 4, UnitTypeId::Zergling, (20.0, 0.0), 1.0),
+		]
+		.into_iter()
+		.collect();
+
+		assert_eq!(targets.best_target(&marine, 0.0).map(|t| t.tag()), Some(3));
+		assert!(Units::new().best_target(&marine, 0.0).is_none());
+	}
+
+	#[test]
+	fn assign_targets_avoids_overkill() {
+		let data = combat_data();
+		let marines: Units = [
+			with_data(&data, &proto(1, UnitTypeId::Marine, (0.0, 0.0))),
+			with_data(&data, &proto(2, UnitTypeId::Marine, (0.0, 1.0))),
+			with_data(&data, &proto(3, UnitTypeId::Marine, (0.0, 30.0))),
+		]
+		.into_iter()
+		.collect();
+		let targets: Units = [
+			enemy(&data, 4, UnitTypeId::Zergling, (3.0, 0.0), 5.0),
+			enemy(&data, 5, UnitTypeId::Zergling, (3.0, 1.0), 35.0),
+		]
+		.into_iter()
+		.collect();
+
+		let assigned = marines.assign_targets(&targets, 0.0);
+		assert_eq!(assigned.len(), 2);
+		assert_ne!(assigned[&1], assigned[&2]);
+		assert!(!assigned.contains_key(&3));
+
+		// All attackers focus the only target even if it's going to die
+		let single = targets.filter(|t| t.tag() == 4);
+		let assigned = marines.assign_targets(&single, 0.0);
+		assert_eq!(assigned.get(&1), Some(&4));
+		assert_eq!(assigned.get(&2), Some(&4));
+
+		let idle = marines.focus_fire(&targets, 0.0);
+		assert_eq!(idle.iter().map(|u| u.tag()).collect::<Vec<_>>(), vec![3]);
+	}
+}
@@ -0,0 +1,207 @@
+//! Estimation of time until threats can attack units.
+//!
+//! Time to attack consists of time attacker needs to get in range with its
+//! [`real_speed`](Unit::real_speed), time to turn towards the target and time until its weapon is ready.
+//! Incoming damage helps to decide when to blink, retreat or use shield batteries:
+//! ```
+//! let enemies = &self.units.enemy.all;
+//! for u in &self.units.my.units.of_type(UnitTypeId::Stalker) {
+//!     let hits = u.hits().unwrap_or(0) as f32;
+//!     if enemies.incoming_damage(u, 1.0) >= hits && u.has_ability(AbilityId::EffectBlinkStalker) {
+//!         u.command(
+//!             AbilityId::EffectBlinkStalker,
+//!             Target::Pos(u.position().towards(self.start_location, 8.0)),
+//!             false,
+//!         );
+//!     }
+//! }
+//! ```
+#![warn(missing_docs)]
+
+use super::Units;
+use crate::{
+	consts::FRAMES_PER_SECOND,
+	distance::{cmp, Distance},
+	unit::Unit,
+};
+use std::f32::consts::PI;
+
+/// Turning speed of units in radians per second, most of them turn almost instantly.
+const TURN_RATE: f32 = 999.8 * PI / 180.0;
+
+/// Unit which can attack the target soon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threat {
+	/// Tag of the attacker.
+	pub tag: u64,
+	/// Estimated time in seconds until attacker can attack the target.
+	pub time: f32,
+	/// Dps of the attacker against the target.
+	pub dps: f32,
+}
+
+/// Returns estimated time in seconds until `attacker` can attack `target`.
+///
+/// Returns `None` if attacker can't attack the target at all
+/// or can't move while target is out of its range.
+///
+/// Weapon cooldown isn't populated for enemies, so their weapons are considered ready.
+pub fn time_to_attack(attacker: &Unit, target: &Unit) -> Option<f32> {
+	let range = attacker.real_range_vs(target);
+	if range < f32::EPSILON {
+		return None;
+	}
+
+	let gap = attacker.distance(target) - attacker.radius() - target.radius() - range;
+	let move_time = if gap > 0.0 {
+		let speed = attacker.real_speed();
+		if speed <= 0.0 {
+			return None;
+		}
+		gap / speed
+	} else {
+		0.0
+	};
+
+	let turn_time = if attacker.is_closer(0.01, target) {
+		0.0
+	} else {
+		let direction = target.position() - attacker.position();
+		let angle = (direction.y.atan2(direction.x) - attacker.facing()).rem_euclid(PI * 2.0);
+		angle.min(PI * 2.0 - angle) / TURN_RATE
+	};
+
+	let cooldown = attacker.weapon_cooldown().unwrap_or(0.0) / FRAMES_PER_SECOND;
+	Some((move_time + turn_time).max(cooldown))
+}
+
+impl Units {
+	/// Returns units, which can attack given target in given number of seconds, sorted by time.
+	pub fn threats_to(&self, target: &Unit, seconds: f32) -> Vec<Threat> {
+		let mut threats = self
+			.iter()
+			.filter_map(|u| {
+				let time = time_to_attack(u, target)?;
+				if time > seconds {
+					return None;
+				}
+				Some(Threat {
+					tag: u.tag(),
+					time,
+					dps: u.real_weapon_vs(target).dps(),
+				})
+			})
+			.collect::<Vec<_>>();
+		threats.sort_unstable_by(|a, b| cmp(&a.time, &b.time));
+		threats
+	}
+	/// Returns estimated time in seconds until the first of these units can attack given target.
+	pub fn time_to_first_attack(&self, target: &Unit) -> Option<f32> {
+		self.iter().filter_map(|u| time_to_attack(u, target)).min_by(cmp)
+	}
+	/// Returns summed dps of these units, which can attack given target in given number of seconds.
+	pub fn incoming_dps(&self, target: &Unit, seconds: f32) -> f32 {
+		self.threats_to(target, seconds).iter().map(|t| t.dps).sum()
+	}
+	/// Returns estimated damage given target takes from these units in given number of seconds.
+	///
+	/// Every attacker deals damage with its dps from the moment it can attack.
+	pub fn incoming_damage(&self, target: &Unit, seconds: f32) -> f32 {
+		self.threats_to(target, seconds)
+			.iter()
+			.map(|t| t.dps * (seconds - t.time))
+			.sum()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::Rs,
+		ids::UnitTypeId,
+		unit::{
+			tests::{combat_data, proto, with_data},
+			DataForUnit,
+		},
+	};
+
+	fn assert_close(a: f32, b: f32) {
+		assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+	}
+	fn attacker(data: &Rs<DataForUnit>, tag: u64, type_id: UnitTypeId, pos: (f32, f32), facing: f32) -> Unit {
+		let mut u = proto(tag, type_id, pos);
+		u.set_facing(facing);
+		with_data(data, &u)
+	}
+
+	#[test]
+	fn time_to_get_in_range() {
+		let data = combat_data();
+		let target = attacker(&data, 1, UnitTypeId::Marine, (20.0, 0.0), 0.0);
+
+		// Distance 10 minus radii and range 5 at speed 3.15
+		let marine = attacker(&data, 2, UnitTypeId::Marine, (10.0, 0.0), 0.0);
+		assert_close(time_to_attack(&marine, &target).unwrap(), 4.0 / 3.15);
+
+		let close = attacker(&data, 3, UnitTypeId::Marine, (16.0, 0.0), 0.0);
+		assert_eq!(time_to_attack(&close, &target), Some(0.0));
+	}
+
+	#[test]
+	fn time_to_turn_and_cooldown() {
+		let data = combat_data();
+		let target = attacker(&data, 1, UnitTypeId::Marine, (20.0, 0.0), 0.0);
+
+		let turned = attacker(&data, 2, UnitTypeId::Marine, (16.0, 0.0), PI);
+		assert_close(time_to_attack(&turned, &target).unwrap(), PI / TURN_RATE);
+
+		let mut reloading = proto(3, UnitTypeId::Marine, (16.0, 0.0));
+		reloading.set_weapon_cooldown(FRAMES_PER_SECOND);
+		let reloading = with_data(&data, &reloading);
+		assert_close(time_to_attack(&reloading, &target).unwrap(), 1.0);
+	}
+
+	#[test]
+	fn no_time_if_cant_attack() {
+		let data = combat_data();
+		let target = attacker(&data, 1, UnitTypeId::Marine, (20.0, 0.0), 0.0);
+
+		let unarmed = attacker(&data, 2, UnitTypeId::Drone, (19.0, 0.0), 0.0);
+		assert_eq!(time_to_attack(&unarmed, &target), None);
+
+		// Sieged tank can't move to targets out of its range
+		let tank = attacker(&data, 3, UnitTypeId::SiegeTankSieged, (0.0, 0.0), 0.0);
+		assert_eq!(time_to_attack(&tank, &target), None);
+		let tank = attacker(&data, 4, UnitTypeId::SiegeTankSieged, (10.0, 0.0), 0.0);
+		assert_eq!(time_to_attack(&tank, &target), Some(0.0));
+	}
+
+	#[test]
+	fn threats_sorted_by_time() {
+		let data = combat_data();
+		let target = attacker(&data, 1, UnitTypeId::Marine, (20.0, 0.0), 0.0);
+		let enemies: Units = [
+			attacker(&data, 2, UnitTypeId::Marine, (10.0, 0.0), 0.0),
+			attacker(&data, 3, UnitTypeId::Zergling, (17.0, 0.0), 0.0),
+			attacker(&data, 4, UnitTypeId::Drone, (19.0, 0.0), 0.0),
+		]
+		.into_iter()
+		.collect();
+		let zergling_time = 1.9 / 4.13;
+		let marine_time = 4.0 / 3.15;
+
+		let threats = enemies.threats_to(&target, 1.0);
+		assert_eq!(threats.iter().map(|t| t.tag).collect::<Vec<_>>(), vec![3]);
+		let threats = enemies.threats_to(&target, 2.0);
+		assert_eq!(threats.iter().map(|t| t.tag).collect::<Vec<_>>(), vec![3, 2]);
+
+		assert_close(enemies.time_to_first_attack(&target).unwrap(), zergling_time);
+		assert_close(enemies.incoming_dps(&target, 2.0), 20.0);
+		assert_close(
+			enemies.incoming_damage(&target, 2.0),
+			10.0 * (2.0 - zergling_time) + 10.0 * (2.0 - marine_time),
+		);
+		assert_eq!(Units::new().time_to_first_attack(&target), None);
+	}
+}
@@ -14,6 +14,8 @@ type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
 /// - `min_points`: minimum neighbors required for point to not be marked as noise.
 ///
 /// Returns: (Clusters, Noise).
+///
+/// To cluster units use [`Units::dbscan`](crate::units::Units::dbscan) instead.
 pub fn dbscan<'a, DT, P, F>(data: DT, range_query: F, min_points: usize) -> (Vec<Vec<P>>, FxHashSet<P>)
 where
 	DT: IntoIterator<Item = &'a P>,
@@ -0,0 +1,293 @@
+//! Vision and detection coverage maps.
+//!
+//! Grids are rebuilt every step from sight, detection and radar ranges of units
+//! and active scanner sweeps, and can be accessed through
+//! [`vision`](crate::bot::Bot::vision) field.
+//!
+//! Enemy coverage is built from currently visible enemy units
+//! and remembered enemy structures, so it covers only known detectors.
+
+use crate::{
+	bot::Bot, distance::*, game_state::Effect, geometry::Point2, ids::EffectId, pixel_map::fill_circle,
+	unit::Unit,
+};
+use ndarray::Array2;
+
+/// Coverage of the map by vision, detection and radar of both players.
+#[derive(Default, Clone)]
+pub struct Vision {
+	detection: Array2<bool>,
+	radar: Array2<bool>,
+	enemy_sight: Array2<bool>,
+	enemy_detection: Array2<bool>,
+	enemy_radar: Array2<bool>,
+	detectors: Vec<(Point2, f32)>,
+	enemy_detectors: Vec<(Point2, f32)>,
+}
+
+impl Vision {
+	pub(crate) fn update(&mut self, bot: &Bot) {
+		let dim = bot.game_info.pathing_grid.dim();
+		for grid in [
+			&mut self.detection,
+			&mut self.radar,
+			&mut self.enemy_sight,
+			&mut self.enemy_detection,
+			&mut self.enemy_radar,
+		] {
+			if grid.dim() == dim {
+				grid.fill(false);
+			} else {
+				*grid = Array2::from_elem(dim, false);
+			}
+		}
+		let effects = &bot.state.observation.raw.effects;
+
+		// Our coverage
+		for u in &bot.units.my.all {
+			if u.radar_range() > 0.0 {
+				fill_circle(&mut self.radar, u.position(), u.radar_range());
+			}
+		}
+		self.detectors =
+			detection_circles(&bot.units.my.all, effects.iter().filter(|e| e.alliance.is_mine()));
+
+		// Enemy coverage from visible units and remembered structures
+		let enemies = bot
+			.units
+			.enemy
+			.all
+			.iter()
+			.filter(|u| !u.is_hallucination())
+			.chain(
+				bot.enemy_memory
+					.structures()
+					.filter(|u| !u.is_observed)
+					.map(|u| &u.unit),
+			);
+		for u in enemies.clone() {
+			let pos = u.position();
+			let sight = u.sight_range();
+			if sight > 0.0 {
+				fill_circle(&mut self.enemy_sight, pos, u.radius() + sight);
+			}
+			if u.radar_range() > 0.0 {
+				fill_circle(&mut self.enemy_radar, pos, u.radar_range());
+			}
+		}
+		let enemy_scans = effects
+			.iter()
+			.filter(|e| e.id == EffectId::ScannerSweep && e.alliance.is_enemy());
+		for scan in enemy_scans.clone() {
+			for p in &scan.positions {
+				fill_circle(&mut self.enemy_sight, *p, scan.radius);
+			}
+		}
+		self.enemy_detectors = detection_circles(enemies, enemy_scans);
+
+		for (pos, radius) in &self.detectors {
+			fill_circle(&mut self.detection, *pos, *radius);
+		}
+		for (pos, radius) in &self.enemy_detectors {
+			fill_circle(&mut self.enemy_detection, *pos, *radius);
+		}
+	}
+
+	/// Checks if given position is detected by our detectors or scans.
+	pub fn is_detected<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
+		get(&self.detection, pos)
+	}
+	/// Checks if given position is covered by our radars.
+	pub fn is_radar_covered<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
+		get(&self.radar, pos)
+	}
+	/// Checks if given position is in sight range of known enemy units or scans.
+	pub fn is_seen_by_enemy<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
+		get(&self.enemy_sight, pos)
+	}
+	/// Checks if given position is detected by known enemy detectors or scans.
+	pub fn is_detected_by_enemy<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
+		get(&self.enemy_detection, pos)
+	}
+	/// Checks if given position is covered by known enemy radars.
+	pub fn is_in_enemy_radar<P: Into<(usize, usize)>>(&self, pos: P) -> bool {
+		get(&self.enemy_radar, pos)
+	}
+
+	/// Checks if given unit is detected by our detectors or scans.
+	///
+	/// Unlike [`is_detected`](Self::is_detected) this takes radius of the unit into account.
+	pub fn is_unit_detected(&self, unit: &Unit, gap: f32) -> bool {
+		is_detected(unit, &self.detectors, gap)
+	}
+	/// Checks if given unit is detected by known enemy detectors or scans.
+	///
+	/// Unlike [`is_detected_by_enemy`](Self::is_detected_by_enemy)
+	/// this takes radius of the unit into account.
+	pub fn is_unit_detected_by_enemy(&self, unit: &Unit, gap: f32) -> bool {
+		is_detected(unit, &self.enemy_detectors, gap)
+	}
+	/// Returns detection circles (center and radius) of our detectors and scans.
+	pub fn detectors(&self) -> &[(Point2, f32)] {
+		&self.detectors
+	}
+	/// Returns detection circles (center and radius) of known enemy detectors and scans.
+	pub fn enemy_detectors(&self) -> &[(Point2, f32)] {
+		&self.enemy_detectors
+	}
+
+	/// Returns grid of tiles detected by us.
+	pub fn detection(&self) -> &Array2<bool> {
+		&self.detection
+	}
+	/// Returns grid of tiles covered by our radars.
+	pub fn radar(&self) -> &Array2<bool> {
+		&self.radar
+	}
+	/// Returns grid of tiles seen by known enemy units.
+	pub fn enemy_sight(&self) -> &Array2<bool> {
+		&self.enemy_sight
+	}
+	/// Returns grid of tiles detected by known enemy detectors.
+	pub fn enemy_detection(&self) -> &Array2<bool> {
+		&self.enemy_detection
+	}
+	/// Returns grid of tiles covered by known enemy radars.
+	pub fn enemy_radar(&self) -> &Array2<bool> {
+		&self.enemy_radar
+	}
+}
+
+/// Checks if given unit is inside of any given detection circle (center and radius),
+/// taking radius of the unit and additional `gap` into account.
+///
+/// Circles can be taken from [`Vision::detectors`] and [`Vision::enemy_detectors`].
+pub fn is_detected(unit: &Unit, detectors: &[(Point2, f32)], gap: f32) -> bool {
+	let additional = unit.radius() + gap;
+	detectors
+		.iter()
+		.any(|(pos, radius)| unit.is_closer(additional + radius, *pos))
+}
+
+/// Returns detection circles of given detector units and scanner sweeps among given effects.
+pub(crate) fn detection_circles<'a>(
+	units: impl IntoIterator<Item = &'a Unit>,
+	effects: impl IntoIterator<Item = &'a Effect>,
+) -> Vec<(Point2, f32)> {
+	let detectors = units
+		.into_iter()
+		.filter(|u| u.is_detector() && u.detect_range() > 0.0)
+		.map(|u| (u.position(), u.radius() + u.detect_range()));
+	let scans = effects
+		.into_iter()
+		.filter(|e| e.id == EffectId::ScannerSweep)
+		.flat_map(|e| e.positions.iter().map(move |p| (*p, e.radius)));
+	detectors.chain(scans).collect()
+}
+
+fn get<P: Into<(usize, usize)>>(grid: &Array2<bool>, pos: P) -> bool {
+	grid.get(pos.into()).copied().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		game_state::Alliance,
+		ids::UnitTypeId,
+		pixel_map::Pixel,
+		unit::tests::{from_proto, proto, unit},
+	};
+	use sc2_proto::raw::Alliance as ProtoAlliance;
+
+	fn detector(tag: u64, type_id: UnitTypeId, pos: (f32, f32), range: f32, enemy: bool) -> Unit {
+		let mut u = proto(tag, type_id, pos);
+		u.set_detect_range(range);
+		if enemy {
+			u.set_alliance(ProtoAlliance::Enemy);
+		}
+		from_proto(&u)
+	}
+
+	fn scan(pos: Point2, alliance: Alliance) -> Effect {
+		Effect {
+			id: EffectId::ScannerSweep,
+			positions: vec![pos],
+			alliance,
+			owner: 1,
+			radius: 13.0,
+		}
+	}
+
+	#[test]
+	fn unit_detected_by_circles() {
+		let u = unit(1, UnitTypeId::DarkTemplar, (10.0, 10.0));
+		let circles = [(Point2::new(15.0, 10.0), 4.0)];
+		assert!(!is_detected(&u, &circles, 0.0));
+		assert!(is_detected(&u, &circles, 1.0));
+		assert!(!is_detected(&u, &[], 10.0));
+	}
+
+	#[test]
+	fn circles_of_detectors_and_scans() {
+		let units = [
+			detector(1, UnitTypeId::Observer, (10.0, 10.0), 11.0, false),
+			detector(2, UnitTypeId::Marine, (20.0, 10.0), 11.0, false),
+			detector(3, UnitTypeId::Raven, (30.0, 10.0), 0.0, false),
+		];
+		let mut other = scan(Point2::new(50.0, 50.0), Alliance::Own);
+		other.id = EffectId::PsiStormPersistent;
+		let effects = [scan(Point2::new(40.0, 40.0), Alliance::Own), other];
+
+		let circles = detection_circles(&units, &effects);
+		assert_eq!(
+			circles,
+			vec![(Point2::new(10.0, 10.0), 11.5), (Point2::new(40.0, 40.0), 13.0)]
+		);
+	}
+
+	#[test]
+	fn coverage_of_both_players() {
+		let mut bot = Bot::default();
+		bot.game_info.pathing_grid = Array2::from_elem((128, 128), Pixel::Empty);
+		bot.units
+			.my
+			.all
+			.push(detector(1, UnitTypeId::Observer, (20.0, 20.0), 11.0, false));
+		let mut tower = proto(2, UnitTypeId::SensorTower, (60.0, 20.0));
+		tower.set_radar_range(30.0);
+		bot.units.my.all.push(from_proto(&tower));
+		bot.units
+			.enemy
+			.all
+			.push(detector(3, UnitTypeId::Observer, (100.0, 100.0), 11.0, true));
+		let effects = &mut bot.state.observation.raw.effects;
+		effects.push(scan(Point2::new(20.0, 100.0), Alliance::Own));
+		effects.push(scan(Point2::new(100.0, 20.0), Alliance::Enemy));
+
+		let mut vision = Vision::default();
+		vision.update(&bot);
+		assert_eq!(vision.detection().dim(), (128, 128));
+
+		assert!(vision.is_detected((25, 20)));
+		assert!(vision.is_detected((20, 110)));
+		assert!(!vision.is_detected((60, 60)));
+		assert!(vision.is_radar_covered((80, 20)));
+		assert!(!vision.is_radar_covered((100, 100)));
+
+		assert!(vision.is_detected_by_enemy((105, 100)));
+		assert!(vision.is_detected_by_enemy((100, 30)));
+		assert!(vision.is_seen_by_enemy((100, 30)));
+		assert!(!vision.is_detected_by_enemy((20, 20)));
+		assert!(!vision.is_seen_by_enemy((20, 20)));
+		assert!(!vision.is_in_enemy_radar((100, 100)));
+		assert_eq!(vision.detectors().len(), 2);
+		assert_eq!(vision.enemy_detectors().len(), 2);
+
+		let dt = unit(4, UnitTypeId::DarkTemplar, (31.0, 20.0));
+		assert!(vision.is_unit_detected(&dt, 0.0));
+		assert!(!vision.is_unit_detected(&dt, -1.0));
+		assert!(!vision.is_unit_detected_by_enemy(&dt, 0.0));
+		assert!(!vision.is_detected((300, 300)));
+	}
+}
@@ -0,0 +1,511 @@
+//! Wall-off solver for arbitrary chokes.
+//!
+//! Finds placements of given buildings, which fully block ground pathing through the choke
+//! or leave a gap of 1 tile, which can be closed by a unit.
+//!
+//! Walls can be found with [`find_wall`](crate::bot::Bot::find_wall) method.
+
+use crate::{
+	bot::Bot, distance::*, geometry::Point2, ids::UnitTypeId, pixel_map::corner, placement::PlacementGrid,
+	ramp::Ramp,
+};
+use std::collections::VecDeque;
+
+type Cell = (isize, isize);
+
+/// Choke, which should be walled off.
+#[derive(Debug, Clone, Copy)]
+pub struct Choke {
+	/// Center of the choke, where wall should be placed.
+	pub center: Point2,
+	/// Point on the side of the choke, which should be protected by wall.
+	pub inside: Point2,
+	/// Point on the other side of the choke.
+	pub outside: Point2,
+	/// Radius of area around center, where buildings can be placed.
+	/// Area should cover the whole choke, because paths outside of it aren't considered.
+	pub radius: f32,
+}
+impl Choke {
+	/// Constructs new choke.
+	pub fn new(center: Point2, inside: Point2, outside: Point2, radius: f32) -> Self {
+		Self {
+			center,
+			inside,
+			outside,
+			radius,
+		}
+	}
+	/// Constructs choke on top of given ramp.
+	pub fn from_ramp(ramp: &Ramp) -> Option<Self> {
+		let (x, y) = ramp.top_center()?;
+		let top = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+		let (x, y) = ramp.bottom_center()?;
+		let bottom = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+		let direction = (top - bottom).normalize();
+		Some(Self {
+			center: top,
+			inside: top + direction * 4.0,
+			outside: bottom,
+			radius: 6.0,
+		})
+	}
+}
+
+/// Additional options for [`find_wall`](Bot::find_wall).
+#[derive(Debug, Clone, Copy)]
+pub struct WallOptions {
+	/// Leave a gap of 1 tile in the wall. [Default: `false`]
+	pub gap: bool,
+	/// Maximum number of checked combinations of placements. [Default: `50000`]
+	pub max_iterations: usize,
+}
+impl Default for WallOptions {
+	fn default() -> Self {
+		Self {
+			gap: false,
+			max_iterations: 50_000,
+		}
+	}
+}
+
+/// Wall found by solver.
+#[derive(Debug, Clone)]
+pub struct Wall {
+	/// Buildings and their positions in the wall.
+	/// May contain less buildings than requested, if they're enough to block the choke.
+	pub buildings: Vec<(UnitTypeId, Point2)>,
+	/// Position of 1 tile gap, if it was requested.
+	pub gap: Option<Point2>,
+}
+
+struct Piece {
+	building: Option<UnitTypeId>,
+	size: usize,
+	candidates: Vec<Point2>,
+}
+
+struct Solver {
+	origin: Cell,
+	width: isize,
+	height: isize,
+	pathable: Vec<bool>,
+	blocked: Vec<bool>,
+	inside: Cell,
+	outside: Cell,
+	iterations: usize,
+	max_iterations: usize,
+}
+
+impl Solver {
+	fn index(&self, (x, y): Cell) -> Option<usize> {
+		let (x, y) = (x - self.origin.0, y - self.origin.1);
+		if x < 0 || y < 0 || x >= self.width || y >= self.height {
+			None
+		} else {
+			Some((y * self.width + x) as usize)
+		}
+	}
+	fn is_open(&self, cell: Cell) -> bool {
+		self.index(cell)
+			.is_some_and(|i| self.pathable[i] && !self.blocked[i])
+	}
+	fn cells(pos: Point2, size: usize) -> impl Iterator<Item = Cell> {
+		let (x0, y0) = corner(pos, size, size);
+		(x0..x0 + size as isize).flat_map(move |x| (y0..y0 + size as isize).map(move |y| (x, y)))
+	}
+	fn fits(&self, pos: Point2, size: usize) -> bool {
+		Self::cells(pos, size).all(|c| self.is_open(c) && c != self.inside && c != self.outside)
+	}
+	fn set(&mut self, pos: Point2, size: usize, blocked: bool) {
+		for c in Self::cells(pos, size) {
+			if let Some(i) = self.index(c) {
+				self.blocked[i] = blocked;
+			}
+		}
+	}
+	fn touches_obstacle(&self, pos: Point2, size: usize) -> bool {
+		let (x0, y0) = corner(pos, size, size);
+		let s = size as isize;
+		(x0 - 1..=x0 + s).any(|x| {
+			(y0 - 1..=y0 + s)
+				.filter(|&y| x < x0 || x >= x0 + s || y < y0 || y >= y0 + s)
+				.any(|y| self.index((x, y)).is_some() && !self.is_open((x, y)))
+		})
+	}
+	fn is_connected(&self) -> bool {
+		let (start, goal) = match (self.index(self.inside), self.index(self.outside)) {
+			(Some(start), Some(goal)) => (start, goal),
+			_ => return false,
+		};
+		if !self.is_open(self.inside) || !self.is_open(self.outside) {
+			return false;
+		}
+
+		let mut visited = vec![false; self.pathable.len()];
+		let mut queue = VecDeque::new();
+		visited[start] = true;
+		queue.push_back(self.inside);
+
+		while let Some((x, y)) = queue.pop_front() {
+			for n in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+				if let Some(i) = self.index(n) {
+					if i == goal {
+						return true;
+					}
+					if !visited[i] && self.is_open(n) {
+						visited[i] = true;
+						queue.push_back(n);
+					}
+				}
+			}
+		}
+		false
+	}
+
+	fn solve(&mut self, pieces: &[Piece], placed: &mut Vec<Point2>, gap: bool) -> Option<usize> {
+		let i = placed.len();
+		if i == pieces.len() {
+			return None;
+		}
+		let piece = &pieces[i];
+		for &pos in &piece.candidates {
+			if self.iterations >= self.max_iterations {
+				return None;
+			}
+			if !self.fits(pos, piece.size) || !self.touches_obstacle(pos, piece.size) {
+				continue;
+			}
+
+			self.iterations += 1;
+			self.set(pos, piece.size, true);
+			placed.push(pos);
+
+			if !self.is_connected() {
+				if !gap {
+					return Some(placed.len());
+				}
+				// Gap is placed first, so wall is correct if opening it restores the path
+				self.set(placed[0], pieces[0].size, false);
+				let opened = self.is_connected();
+				self.set(placed[0], pieces[0].size, true);
+				if opened && i > 0 {
+					return Some(placed.len());
+				}
+			} else if let Some(n) = self.solve(pieces, placed, gap) {
+				return Some(n);
+			}
+
+			placed.pop();
+			self.set(pos, piece.size, false);
+		}
+		None
+	}
+}
+
+pub(crate) fn find_wall(
+	bot: &Bot,
+	choke: &Choke,
+	buildings: &[UnitTypeId],
+	options: WallOptions,
+) -> Option<Wall> {
+	let grid = &bot.placement;
+	let r = choke.radius.ceil() as isize + 2;
+	let center = (choke.center.x as isize, choke.center.y as isize);
+	let origin = (center.0 - r, center.1 - r);
+	let size = 2 * r + 1;
+
+	let mut pathable = vec![false; (size * size) as usize];
+	for y in 0..size {
+		for x in 0..size {
+			let (cx, cy) = (origin.0 + x, origin.1 + y);
+			pathable[(y * size + x) as usize] =
+				cx >= 0 && cy >= 0 && bot.is_pathable((cx as usize, cy as usize));
+		}
+	}
+
+	let mut solver = Solver {
+		origin,
+		width: size,
+		height: size,
+		pathable,
+		blocked: vec![false; (size * size) as usize],
+		inside: (0, 0),
+		outside: (0, 0),
+		iterations: 0,
+		max_iterations: options.max_iterations,
+	};
+
+	for u in &bot.units.all {
+		if u.is_flying() || !(u.is_structure() || u.is_mineral() || u.is_geyser()) {
+			continue;
+		}
+		let (width, height) = if u.is_mineral() {
+			(2, 1)
+		} else if u.is_geyser() {
+			(3, 3)
+		} else if let Some(size) = u.building_size() {
+			(size, size)
+		} else {
+			continue;
+		};
+		let (x0, y0) = corner(u.position(), width, height);
+		for x in x0..x0 + width as isize {
+			for y in y0..y0 + height as isize {
+				if let Some(i) = solver.index((x, y)) {
+					solver.blocked[i] = true;
+				}
+			}
+		}
+	}
+
+	// Moving endpoints into the solver area
+	let endpoint = |target: Point2| -> Option<Cell> {
+		let pos = if target.is_closer(choke.radius + 1.0, choke.center) {
+			target
+		} else {
+			choke.center.towards(target, choke.radius + 1.0)
+		};
+		let mut cells = (-2..=2)
+			.flat_map(|dx| (-2..=2).map(move |dy| (pos.x as isize + dx, pos.y as isize + dy)))
+			.filter(|c| solver.is_open(*c))
+			.collect::<Vec<_>>();
+		cells.sort_unstable_by_key(|(x, y)| {
+			let (dx, dy) = (*x - pos.x as isize, *y - pos.y as isize);
+			dx * dx + dy * dy
+		});
+		cells.first().copied()
+	};
+	let inside = endpoint(choke.inside)?;
+	let outside = endpoint(choke.outside)?;
+	solver.inside = inside;
+	solver.outside = outside;
+	if !solver.is_connected() {
+		return None;
+	}
+
+	let candidates = |size: usize| {
+		let mut positions = (-r..=r)
+			.flat_map(|dx| {
+				(-r..=r).map(move |dy| PlacementGrid::align(choke.center.offset(dx as f32, dy as f32), size))
+			})
+			.filter(|pos| pos.is_closer(choke.radius, choke.center))
+			.filter(|pos| size == 1 || grid.is_free_area(*pos, size))
+			.collect::<Vec<_>>();
+		positions.sort_unstable_by(|a, b| {
+			cmp(
+				&a.distance_squared(choke.center),
+				&b.distance_squared(choke.center),
+			)
+		});
+		positions
+	};
+
+	let mut pieces = vec![];
+	if options.gap {
+		pieces.push(Piece {
+			building: None,
+			size: 1,
+			candidates: candidates(1),
+		});
+	}
+	for building in buildings {
+		let size = grid.building_size(*building)?;
+		pieces.push(Piece {
+			building: Some(*building),
+			size,
+			candidates: candidates(size),
+		});
+	}
+
+	let mut placed = vec![];
+	let n = solver.solve(&pieces, &mut placed, options.gap)?;
+
+	let mut wall = Wall {
+		buildings: vec![],
+		gap: None,
+	};
+	for (piece, pos) in pieces.iter().zip(placed).take(n) {
+		match piece.building {
+			Some(building) => wall.buildings.push((building, pos)),
+			None => wall.gap = Some(pos),
+		}
+	}
+	Some(wall)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{bot::Rs, pixel_map::Pixel, placement::tests::game_data};
+	use ndarray::Array2;
+
+	const SIZE: isize = 16;
+
+	/// Solver over area with horizontal corridor of given width starting at `y = 5`.
+	fn corridor(width: isize) -> Solver {
+		let pathable = (0..SIZE)
+			.flat_map(|y| (0..SIZE).map(move |_| (5..5 + width).contains(&y)))
+			.collect();
+		Solver {
+			origin: (0, 0),
+			width: SIZE,
+			height: SIZE,
+			pathable,
+			blocked: vec![false; (SIZE * SIZE) as usize],
+			inside: (1, 6),
+			outside: (SIZE - 2, 6),
+			iterations: 0,
+			max_iterations: 10_000,
+		}
+	}
+
+	fn candidates(center: Point2, radius: f32, size: usize) -> Vec<Point2> {
+		let r = radius.ceil() as isize;
+		let mut positions = (-r..=r)
+			.flat_map(|dx| (-r..=r).map(move |dy| (dx, dy)))
+			.map(|(dx, dy)| PlacementGrid::align(center.offset(dx as f32, dy as f32), size))
+			.filter(|pos| pos.is_closer(radius, center))
+			.collect::<Vec<_>>();
+		positions.sort_unstable_by(|a, b| cmp(&a.distance_squared(center), &b.distance_squared(center)));
+		positions.dedup();
+		positions
+	}
+
+	fn piece(building: Option<UnitTypeId>, size: usize, center: Point2) -> Piece {
+		Piece {
+			building,
+			size,
+			candidates: candidates(center, 4.0, size),
+		}
+	}
+
+	#[test]
+	fn solver_cells_and_bounds() {
+		let solver = corridor(4);
+		assert_eq!(solver.index((0, 0)), Some(0));
+		assert_eq!(solver.index((3, 2)), Some(35));
+		assert_eq!(solver.index((-1, 0)), None);
+		assert_eq!(solver.index((SIZE, 0)), None);
+
+		let cells = Solver::cells(Point2::new(8.0, 6.0), 2).collect::<Vec<_>>();
+		assert_eq!(cells, vec![(7, 5), (7, 6), (8, 5), (8, 6)]);
+
+		assert!(solver.fits(Point2::new(8.0, 6.0), 2));
+		assert!(!solver.fits(Point2::new(8.0, 5.0), 2));
+		assert!(!solver.fits(Point2::new(1.5, 6.5), 1));
+	}
+
+	#[test]
+	fn solver_touching_obstacles() {
+		let mut solver = corridor(4);
+		assert!(solver.touches_obstacle(Point2::new(8.0, 6.0), 2));
+		assert!(!solver.touches_obstacle(Point2::new(8.5, 7.5), 1));
+
+		solver.set(Point2::new(8.0, 8.0), 2, true);
+		assert!(solver.touches_obstacle(Point2::new(9.5, 7.5), 1));
+		// Edges of the area aren't obstacles
+		let open = corridor(SIZE);
+		assert!(!open.touches_obstacle(Point2::new(1.0, 1.0), 2));
+	}
+
+	#[test]
+	fn solver_connectivity() {
+		let mut solver = corridor(4);
+		assert!(solver.is_connected());
+
+		solver.set(Point2::new(8.0, 6.0), 2, true);
+		assert!(solver.is_connected());
+		solver.set(Point2::new(8.0, 8.0), 2, true);
+		assert!(!solver.is_connected());
+		solver.set(Point2::new(8.0, 8.0), 2, false);
+		assert!(solver.is_connected());
+
+		solver.blocked[solver.index(solver.inside).unwrap()] = true;
+		assert!(!solver.is_connected());
+	}
+
+	#[test]
+	fn solve_full_wall() {
+		let mut solver = corridor(4);
+		let center = Point2::new(8.0, 7.0);
+		let pieces = (0..3)
+			.map(|_| piece(Some(UnitTypeId::SupplyDepot), 2, center))
+			.collect::<Vec<_>>();
+		let mut placed = vec![];
+		assert_eq!(solver.solve(&pieces, &mut placed, false), Some(2));
+		assert_eq!(placed.len(), 2);
+		assert!(!solver.is_connected());
+	}
+
+	#[test]
+	fn solve_wall_with_gap() {
+		let mut solver = corridor(5);
+		let center = Point2::new(8.0, 7.5);
+		let pieces = vec![
+			piece(None, 1, center),
+			piece(Some(UnitTypeId::SupplyDepot), 2, center),
+			piece(Some(UnitTypeId::SupplyDepot), 2, center),
+		];
+		let mut placed = vec![];
+		assert_eq!(solver.solve(&pieces, &mut placed, true), Some(3));
+		assert!(!solver.is_connected());
+
+		solver.set(placed[0], 1, false);
+		assert!(solver.is_connected());
+	}
+
+	#[test]
+	fn solve_gives_up() {
+		let center = Point2::new(8.0, 7.0);
+		let pieces = vec![piece(Some(UnitTypeId::SupplyDepot), 2, center)];
+		let mut placed = vec![];
+		assert_eq!(corridor(4).solve(&pieces, &mut placed, false), None);
+		assert!(placed.is_empty());
+
+		let mut solver = corridor(4);
+		solver.max_iterations = 0;
+		let pieces = vec![
+			piece(Some(UnitTypeId::SupplyDepot), 2, center),
+			piece(Some(UnitTypeId::SupplyDepot), 2, center),
+		];
+		assert_eq!(solver.solve(&pieces, &mut placed, false), None);
+	}
+
+	#[test]
+	fn find_wall_on_map() {
+		let mut bot = Bot::default();
+		bot.game_data = Rs::new(game_data());
+		bot.game_info.pathing_grid = Array2::from_shape_fn((32, 32), |(_, y)| {
+			if (12..16).contains(&y) {
+				Pixel::Empty
+			} else {
+				Pixel::Set
+			}
+		});
+		bot.game_info.placement_grid = Array2::from_elem((32, 32), Pixel::Empty);
+		bot.placement = PlacementGrid::new(&bot);
+
+		let choke = Choke::new(
+			Point2::new(16.0, 14.0),
+			Point2::new(8.0, 14.0),
+			Point2::new(24.0, 14.0),
+			4.0,
+		);
+		let depots = [UnitTypeId::SupplyDepot; 3];
+		let wall = bot.find_wall(&choke, &depots, Default::default()).unwrap();
+		assert_eq!(wall.buildings.len(), 2);
+		assert_eq!(wall.gap, None);
+
+		let options = WallOptions {
+			gap: true,
+			..Default::default()
+		};
+		let wall = bot.find_wall(&choke, &depots, options).unwrap();
+		assert!(wall.gap.is_some());
+
+		let units = [UnitTypeId::Marine];
+		assert!(bot.find_wall(&choke, &units, Default::default()).is_none());
+	}
+}
@@ -0,0 +1,670 @@
+//! Built-in worker distribution between mineral fields and gas buildings.
+//!
+//! [`WorkerManager`] is an opt-in component, which should be stored in your bot
+//! and updated every step:
+//! ```
+//! #[bot]
+//! #[derive(Default)]
+//! struct MyBot {
+//!     workers: WorkerManager,
+//! }
+//!
+//! impl Player for MyBot {
+//!     fn on_step(&mut self, _iteration: usize) -> SC2Result<()> {
+//!         self.workers.step(&self._bot);
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use crate::{
+	action::Target,
+	bot::Bot,
+	distance::{cmp, Distance, DistanceIterator},
+	geometry::Point2,
+	ids::AbilityId,
+	unit::Unit,
+};
+use indexmap::IndexSet;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::hash::BuildHasherDefault;
+
+type FxIndexSet<T> = IndexSet<T, BuildHasherDefault<FxHasher>>;
+
+/// Workers can be assigned to mineral fields of expansions, which aren't owned, only
+/// if they are closer than this distance to ready townhall.
+const LONG_DISTANCE_RANGE: f32 = 60.0;
+/// Gas buildings closer than this distance to townhall are harvested by its workers.
+const GAS_RANGE: f32 = 11.0;
+
+/// Job of worker assigned by [`WorkerManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Harvest {
+	/// Tag of mineral field or gas building worker is harvesting.
+	pub resource: u64,
+	/// Tag of townhall worker returns resources to.
+	pub base: u64,
+	/// `true` if worker harvests vespene.
+	pub is_gas: bool,
+	/// `true` if resource doesn't belong to owned expansion.
+	pub is_long_distance: bool,
+}
+
+struct Slot {
+	resource: u64,
+	base: u64,
+	position: Point2,
+	free: usize,
+	is_gas: bool,
+	is_long_distance: bool,
+}
+
+/// Assigns workers to specific mineral fields and gas buildings across
+/// [`owned_expansions`](Bot::owned_expansions).
+///
+/// Manager handles:
+/// - new workers and idle ones
+/// - oversaturated resources, extra workers are transferred to other bases
+/// - new and destroyed townhalls, mined out resources
+/// - long-distance mining, when there're no free resources on owned expansions
+/// - optional speed mining micro
+///
+/// Workers given other commands by bot (e.g. building) keep their job and return to it when idle.
+/// Workers used for long tasks (e.g. scouting) should be [`excluded`](Self::exclude) from the manager.
+pub struct WorkerManager {
+	/// Number of workers assigned to each mineral field. [Default: `2`]
+	pub workers_per_mineral: usize,
+	/// Number of workers assigned to each gas building. [Default: `3`]
+	pub workers_per_gas: usize,
+	/// Assign workers to gas buildings. [Default: `true`]
+	///
+	/// Free workers are sent to gas buildings before mineral fields of the same expansion.
+	pub gas: bool,
+	/// Send extra workers to mineral fields of free expansions. [Default: `true`]
+	pub long_distance_mining: bool,
+	/// Control mineral harvesters to mine faster. [Default: `false`]
+	pub speed_mining: bool,
+	harvesters: FxHashMap<u64, Harvest>,
+	assigned: FxHashMap<u64, FxIndexSet<u64>>,
+	excluded: FxHashSet<u64>,
+}
+impl Default for WorkerManager {
+	fn default() -> Self {
+		Self {
+			workers_per_mineral: 2,
+			workers_per_gas: 3,
+			gas: true,
+			long_distance_mining: true,
+			speed_mining: false,
+			harvesters: Default::default(),
+			assigned: Default::default(),
+			excluded: Default::default(),
+		}
+	}
+}
+
+impl WorkerManager {
+	/// Constructs new manager with default settings.
+	pub fn new() -> Self {
+		Default::default()
+	}
+	/// Returns job of given worker or `None` if it isn't assigned to anything.
+	pub fn harvest(&self, worker: u64) -> Option<&Harvest> {
+		self.harvesters.get(&worker)
+	}
+	/// Returns iterator over all assigned workers with their jobs.
+	pub fn harvesters(&self) -> impl Iterator<Item = (u64, &Harvest)> {
+		self.harvesters.iter().map(|(w, h)| (*w, h))
+	}
+	/// Returns tags of workers assigned to given resource.
+	pub fn assigned(&self, resource: u64) -> impl Iterator<Item = u64> + '_ {
+		self.assigned.get(&resource).into_iter().flatten().copied()
+	}
+	/// Returns number of workers assigned to given resource.
+	pub fn assigned_count(&self, resource: u64) -> usize {
+		self.assigned.get(&resource).map_or(0, |ws| ws.len())
+	}
+	/// Returns number of workers harvesting minerals.
+	pub fn mineral_workers(&self) -> usize {
+		self.harvesters.values().filter(|h| !h.is_gas).count()
+	}
+	/// Returns number of workers harvesting vespene.
+	pub fn gas_workers(&self) -> usize {
+		self.harvesters.values().filter(|h| h.is_gas).count()
+	}
+	/// Removes worker from the manager, so it can be used for other tasks.
+	/// Worker won't be assigned again until [`include`](Self::include) is called.
+	pub fn exclude(&mut self, worker: u64) {
+		self.unassign(worker);
+		self.excluded.insert(worker);
+	}
+	/// Returns previously [`excluded`](Self::exclude) worker to the manager.
+	pub fn include(&mut self, worker: u64) {
+		self.excluded.remove(&worker);
+	}
+	/// Checks if worker was [`excluded`](Self::exclude) from the manager.
+	pub fn is_excluded(&self, worker: u64) -> bool {
+		self.excluded.contains(&worker)
+	}
+	/// Takes closest to given position worker, which is harvesting minerals and not carrying them,
+	/// and [`excludes`](Self::exclude) it from the manager.
+	pub fn take_worker<P: Into<Point2> + Copy>(&mut self, bot: &Bot, pos: P) -> Option<u64> {
+		let workers = &bot.units.my.workers;
+		let tag = self
+			.harvesters
+			.iter()
+			.filter(|(_, h)| !h.is_gas)
+			.filter_map(|(w, _)| workers.get(*w))
+			.filter(|u| !u.is_carrying_resource())
+			.closest(pos)?
+			.tag();
+		self.exclude(tag);
+		Some(tag)
+	}
+
+	fn unassign(&mut self, worker: u64) -> Option<Harvest> {
+		let harvest = self.harvesters.remove(&worker)?;
+		if let Some(ws) = self.assigned.get_mut(&harvest.resource) {
+			ws.shift_remove(&worker);
+			if ws.is_empty() {
+				self.assigned.remove(&harvest.resource);
+			}
+		}
+		Some(harvest)
+	}
+	fn assign(&mut self, worker: u64, harvest: Harvest) {
+		self.assigned.entry(harvest.resource).or_default().insert(worker);
+		self.harvesters.insert(worker, harvest);
+	}
+
+	/// Updates assignments and gives commands to workers. Should be called every step.
+	pub fn step(&mut self, bot: &Bot) {
+		let workers = &bot.units.my.workers;
+		self.excluded.retain(|w| workers.contains_tag(*w));
+
+		let mut slots = self.collect_slots(bot);
+
+		// Releasing dead workers and workers of destroyed bases or mined out resources
+		let outdated = self
+			.harvesters
+			.iter()
+			.filter(|(w, h)| {
+				!workers.contains_tag(**w)
+					|| !slots.contains_key(&h.resource)
+					|| slots[&h.resource].base != h.base
+			})
+			.map(|(w, _)| *w)
+			.collect::<Vec<_>>();
+		for w in outdated {
+			self.unassign(w);
+		}
+
+		// Releasing workers of oversaturated resources
+		let mut released = vec![];
+		for (resource, ws) in &mut self.assigned {
+			let slot = slots.get_mut(resource).unwrap();
+			let count = ws.len();
+			if count > slot.free {
+				released.extend(ws.drain(slot.free..));
+				slot.free = 0;
+			} else {
+				slot.free -= count;
+			}
+		}
+		for w in &released {
+			self.harvesters.remove(w);
+		}
+		self.assigned.retain(|_, ws| !ws.is_empty());
+
+		// Moving long-distance miners back to owned expansions when possible
+		let local_free = slots
+			.values()
+			.filter(|s| !s.is_long_distance)
+			.map(|s| s.free)
+			.sum::<usize>();
+		if local_free > 0 {
+			let long_distance = self
+				.harvesters
+				.iter()
+				.filter(|(_, h)| h.is_long_distance)
+				.map(|(w, _)| *w)
+				.take(local_free)
+				.collect::<Vec<_>>();
+			for w in long_distance {
+				if let Some(h) = self.unassign(w) {
+					if let Some(slot) = slots.get_mut(&h.resource) {
+						slot.free += 1;
+					}
+					released.push(w);
+				}
+			}
+		}
+
+		// Assigning free workers
+		let free_workers = workers
+			.iter()
+			.filter(|u| {
+				let tag = u.tag();
+				!(self.harvesters.contains_key(&tag) || self.excluded.contains(&tag))
+					&& (released.contains(&tag) || u.is_idle() || u.is_gathering() || u.is_returning())
+			})
+			.collect::<Vec<_>>();
+
+		let mut commanded = FxHashSet::default();
+		for u in free_workers {
+			let long_distance = self.long_distance_mining;
+			let slot = slots
+				.values_mut()
+				.filter(|s| s.free > 0 && (long_distance || !s.is_long_distance))
+				.min_by(|a, b| {
+					// local resources first, then gas, then closest
+					let key = |s: &Slot| (s.is_long_distance, !s.is_gas);
+					key(a)
+						.cmp(&key(b))
+						.then_with(|| cmp(&u.distance_squared(a.position), &u.distance_squared(b.position)))
+				});
+
+			if let Some(slot) = slot {
+				slot.free -= 1;
+				let harvest = Harvest {
+					resource: slot.resource,
+					base: slot.base,
+					is_gas: slot.is_gas,
+					is_long_distance: slot.is_long_distance,
+				};
+				self.assign(u.tag(), harvest);
+				commanded.insert(u.tag());
+				if u.is_carrying_resource() {
+					u.return_resource(false);
+					u.gather(harvest.resource, true);
+				} else {
+					u.gather(harvest.resource, false);
+				}
+			}
+		}
+
+		self.execute(bot, &commanded);
+	}
+
+	fn collect_slots(&self, bot: &Bot) -> FxHashMap<u64, Slot> {
+		let mut slots = FxHashMap::default();
+		let townhalls = bot.units.my.townhalls.ready();
+		let minerals = &bot.units.mineral_fields;
+
+		for exp in bot.owned_expansions() {
+			let base = match exp.base.and_then(|b| townhalls.get(b)) {
+				Some(base) => base,
+				None => continue,
+			};
+
+			for m in exp.minerals.iter().filter_map(|m| minerals.get(*m)) {
+				slots.insert(
+					m.tag(),
+					Slot {
+						resource: m.tag(),
+						base: base.tag(),
+						position: m.position(),
+						free: self.workers_per_mineral,
+						is_gas: false,
+						is_long_distance: false,
+					},
+				);
+			}
+
+			if self.gas {
+				for g in bot.units.my.gas_buildings.iter().filter(|g| {
+					g.is_ready()
+						&& g.vespene_contents().map_or(false, |v| v > 0)
+						&& g.is_closer(GAS_RANGE, base)
+				}) {
+					slots.insert(
+						g.tag(),
+						Slot {
+							resource: g.tag(),
+							base: base.tag(),
+							position: g.position(),
+							free: self.workers_per_gas,
+							is_gas: true,
+							is_long_distance: false,
+						},
+					);
+				}
+			}
+		}
+
+		if self.long_distance_mining && !townhalls.is_empty() {
+			for exp in bot.free_expansions() {
+				let base = match townhalls.closest(exp.loc) {
+					Some(base) if base.is_closer(LONG_DISTANCE_RANGE, exp.loc) => base,
+					_ => continue,
+				};
+				for m in exp.minerals.iter().filter_map(|m| minerals.get(*m)) {
+					slots.insert(
+						m.tag(),
+						Slot {
+							resource: m.tag(),
+							base: base.tag(),
+							position: m.position(),
+							free: self.workers_per_mineral,
+							is_gas: false,
+							is_long_distance: true,
+						},
+					);
+				}
+			}
+		}
+
+		slots
+	}
+
+	fn execute(&self, bot: &Bot, commanded: &FxHashSet<u64>) {
+		let workers = &bot.units.my.workers;
+		let minerals = &bot.units.mineral_fields;
+		let townhalls = &bot.units.my.townhalls;
+		let mut mineral_moving = FxHashSet::default();
+
+		for (&w, h) in self.harvesters.iter().filter(|(w, _)| !commanded.contains(*w)) {
+			let u = match workers.get(w) {
+				Some(u) => u,
+				None => continue,
+			};
+
+			if self.speed_mining && !h.is_gas && !h.is_long_distance {
+				if let (Some(mineral), Some(base)) = (minerals.get(h.resource), townhalls.get(h.base)) {
+					if self.speed_mine(bot, u, mineral, base, &mut mineral_moving) {
+						continue;
+					}
+				}
+			}
+
+			// Workers can switch to other resource if their one is busy
+			let is_wrong = if u.is_gathering() {
+				u.target_tag() != Some(h.resource)
+			} else {
+				u.is_idle()
+			};
+			if is_wrong {
+				if u.is_carrying_resource() {
+					u.return_resource(false);
+					u.gather(h.resource, true);
+				} else {
+					u.gather(h.resource, false);
+				}
+				mineral_moving.insert(w);
+			}
+		}
+	}
+
+	// Speed mining micro, returns `false` if default commands should be used.
+	fn speed_mine(
+		&self,
+		bot: &Bot,
+		u: &Unit,
+		mineral: &Unit,
+		base: &Unit,
+		mineral_moving: &mut FxHashSet<u64>,
+	) -> bool {
+		let workers = &bot.units.my.workers;
+		let is_collides = |mineral_moving: &FxHashSet<u64>| {
+			let range = (u.radius() + u.distance_per_step()) * 2.0;
+			!self.assigned(mineral.tag()).all(|w| {
+				w == u.tag()
+					|| mineral_moving.contains(&w)
+					|| workers.get(w).map_or(true, |w| u.is_further(range, w))
+			})
+		};
+
+		match u.orders().first().map(|ord| (ord.ability, ord.target)) {
+			// moving
+			Some((AbilityId::MoveMove, Target::Pos(current_target))) => {
+				let range = mineral.radius() + u.distance_per_step();
+				// moving towards mineral
+				if current_target.is_closer(range, mineral) {
+					// execute gather ability if close enough or colliding with other workers
+					if u.is_closer(u.radius() + range, mineral) || is_collides(mineral_moving) {
+						u.smart(Target::Tag(mineral.tag()), false);
+						mineral_moving.insert(u.tag());
+					}
+					return true;
+				}
+				let range = base.radius() + u.distance_per_step();
+				// moving towards base
+				if current_target.is_closer(range, base) {
+					// execute return ability if close enough or colliding with other workers
+					if u.is_closer(u.radius() + range, base) || is_collides(mineral_moving) {
+						u.smart(Target::Tag(base.tag()), false);
+						mineral_moving.insert(u.tag());
+					}
+					return true;
+				}
+				false
+			}
+			// gathering
+			Some((_, Target::Tag(t))) if u.is_gathering() && t == mineral.tag() => {
+				// execute move ability if far away from mineral and not colliding with other workers
+				if u.is_further(u.radius() + mineral.radius() + u.distance_per_step(), mineral)
+					&& !is_collides(mineral_moving)
+				{
+					u.move_to(
+						Target::Pos(mineral.position().towards(base.position(), mineral.radius())),
+						false,
+					);
+				// otherwise keep gathering
+				} else {
+					mineral_moving.insert(u.tag());
+				}
+				true
+			}
+			// returning
+			Some((_, Target::Tag(t))) if u.is_returning() && t == base.tag() => {
+				// execute move ability if far away from base and not colliding with other workers
+				if u.is_further(u.radius() + base.radius() + u.distance_per_step(), base)
+					&& !is_collides(mineral_moving)
+				{
+					u.move_to(
+						Target::Pos(base.position().towards(u.position(), base.radius())),
+						false,
+					);
+				// otherwise keep returning
+				} else {
+					mineral_moving.insert(u.tag());
+				}
+				true
+			}
+			_ => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		bot::Expansion,
+		game_state::Alliance,
+		ids::UnitTypeId,
+		unit::tests::{from_proto, proto, unit},
+	};
+
+	fn harvest_at(resource: u64, base: u64) -> Harvest {
+		Harvest {
+			resource,
+			base,
+			is_gas: false,
+			is_long_distance: false,
+		}
+	}
+	fn harvest(resource: u64, is_gas: bool) -> Harvest {
+		Harvest {
+			resource,
+			base: 100,
+			is_gas,
+			is_long_distance: false,
+		}
+	}
+
+	#[test]
+	fn assign_and_unassign() {
+		let mut manager = WorkerManager::new();
+		manager.assign(1, harvest(10, false));
+		manager.assign(2, harvest(10, false));
+		manager.assign(3, harvest(20, true));
+
+		assert_eq!(manager.assigned(10).collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(manager.assigned_count(20), 1);
+		assert_eq!(manager.mineral_workers(), 2);
+		assert_eq!(manager.gas_workers(), 1);
+
+		assert_eq!(manager.unassign(1), Some(harvest(10, false)));
+		assert_eq!(manager.unassign(1), None);
+		assert_eq!(manager.assigned(10).collect::<Vec<_>>(), vec![2]);
+
+		manager.unassign(3);
+		assert_eq!(manager.assigned_count(20), 0);
+		assert!(!manager.assigned.contains_key(&20));
+	}
+
+	#[test]
+	fn exclude_and_include() {
+		let mut manager = WorkerManager::new();
+		manager.assign(1, harvest(10, false));
+
+		manager.exclude(1);
+		assert!(manager.is_excluded(1));
+		assert!(manager.harvest(1).is_none());
+		assert_eq!(manager.assigned_count(10), 0);
+
+		manager.include(1);
+		assert!(!manager.is_excluded(1));
+	}
+
+	/// Adds expansion at given position with townhall and mineral fields 6 cells above it.
+	fn add_expansion(
+		bot: &mut Bot,
+		base: Option<u64>,
+		loc: (f32, f32),
+		minerals: &[u64],
+		alliance: Alliance,
+	) {
+		if let Some(base) = base {
+			bot.units
+				.my
+				.townhalls
+				.push(unit(base, UnitTypeId::CommandCenter, loc));
+		}
+		for (i, m) in minerals.iter().enumerate() {
+			let pos = (loc.0 - 2.0 + i as f32, loc.1 + 6.0);
+			bot.units
+				.mineral_fields
+				.push(unit(*m, UnitTypeId::MineralField, pos));
+		}
+		let loc = Point2::new(loc.0, loc.1);
+		bot.expansions.push(Expansion {
+			loc,
+			center: loc,
+			minerals: minerals.iter().copied().collect(),
+			geysers: Default::default(),
+			alliance,
+			base,
+		});
+	}
+	/// Adds idle workers with given tags between the first expansion and its mineral fields.
+	fn add_workers(bot: &mut Bot, tags: impl IntoIterator<Item = u64>) {
+		for tag in tags {
+			bot.units
+				.my
+				.workers
+				.push(unit(tag, UnitTypeId::SCV, (10.5, 14.0)));
+		}
+	}
+	fn base_workers(manager: &WorkerManager, base: u64) -> usize {
+		manager.harvesters().filter(|(_, h)| h.base == base).count()
+	}
+
+	#[test]
+	fn gas_before_minerals() {
+		let mut bot = Bot::default();
+		add_expansion(&mut bot, Some(100), (10.5, 10.5), &[11, 12], Alliance::Own);
+		let mut refinery = proto(20, UnitTypeId::Refinery, (17.5, 10.5));
+		refinery.set_vespene_contents(2000);
+		bot.units.my.gas_buildings.push(from_proto(&refinery));
+		add_workers(&mut bot, 1..=8);
+
+		let mut manager = WorkerManager::new();
+		manager.step(&bot);
+		assert_eq!(manager.gas_workers(), 3);
+		assert_eq!(manager.mineral_workers(), 4);
+		assert!((1..=3).all(|w| manager.harvest(w).is_some_and(|h| h.is_gas)));
+		assert!(manager.harvest(8).is_none());
+
+		let mut manager = WorkerManager {
+			gas: false,
+			..Default::default()
+		};
+		manager.step(&bot);
+		assert_eq!(manager.gas_workers(), 0);
+		assert_eq!(manager.mineral_workers(), 4);
+	}
+
+	#[test]
+	fn oversaturated_base() {
+		let mut bot = Bot::default();
+		add_expansion(&mut bot, Some(100), (10.5, 10.5), &[11], Alliance::Own);
+		add_expansion(&mut bot, Some(200), (40.5, 10.5), &[21, 22], Alliance::Own);
+		add_workers(&mut bot, 1..=4);
+		let mut manager = WorkerManager::new();
+		for w in 1..=4 {
+			manager.assign(w, harvest_at(11, 100));
+		}
+
+		manager.step(&bot);
+		assert_eq!(manager.assigned(11).collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(base_workers(&manager, 200), 2);
+	}
+
+	#[test]
+	fn townhall_destroyed() {
+		let mut bot = Bot::default();
+		add_expansion(&mut bot, Some(100), (10.5, 10.5), &[11], Alliance::Own);
+		add_expansion(&mut bot, Some(200), (40.5, 10.5), &[21, 22], Alliance::Own);
+		add_workers(&mut bot, 1..=2);
+		let mut manager = WorkerManager::new();
+		manager.step(&bot);
+		assert_eq!(base_workers(&manager, 100), 2);
+
+		bot.units.my.townhalls.remove(100);
+		manager.step(&bot);
+		assert_eq!(base_workers(&manager, 100), 0);
+		assert_eq!(base_workers(&manager, 200), 2);
+	}
+
+	#[test]
+	fn long_distance_mining() {
+		let mut bot = Bot::default();
+		add_expansion(&mut bot, Some(100), (10.5, 10.5), &[11], Alliance::Own);
+		add_expansion(&mut bot, None, (30.5, 10.5), &[31, 32], Alliance::Neutral);
+		add_workers(&mut bot, 1..=4);
+
+		let mut manager = WorkerManager {
+			long_distance_mining: false,
+			..Default::default()
+		};
+		manager.step(&bot);
+		assert_eq!(manager.mineral_workers(), 2);
+
+		let mut manager = WorkerManager::new();
+		manager.step(&bot);
+		assert_eq!(manager.assigned_count(11), 2);
+		let long_distance = manager.harvesters().filter(|(_, h)| h.is_long_distance);
+		assert!(long_distance.map(|(_, h)| h.base).eq([100, 100]));
+
+		// New mineral field on owned expansion
+		bot.units
+			.mineral_fields
+			.push(unit(12, UnitTypeId::MineralField, (9.5, 16.5)));
+		bot.expansions[0].minerals.insert(12);
+		manager.step(&bot);
+		assert!(manager.harvesters().all(|(_, h)| !h.is_long_distance));
+		assert_eq!(manager.assigned_count(12), 2);
+	}
+}